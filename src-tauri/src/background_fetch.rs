@@ -0,0 +1,357 @@
+use crate::git::{self, FetchRemoteOptions, RefUpdate, RemoteObserver, TransferProgress};
+use crate::settings::BackgroundFetchSettings;
+use crate::state::AppState;
+use git2::{ErrorClass, ErrorCode, Repository};
+use parking_lot::{Condvar, Mutex};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+// How often the schedule is checked for repositories that are due
+const TICK: Duration = Duration::from_secs(15);
+
+// Failing fetches are retried with exponential backoff up to this many
+// seconds, or the interval if that is longer
+const MAX_BACKOFF: i64 = 6 * 60 * 60;
+
+// Seconds until the first retry after the network was unreachable
+const NETWORK_RETRY: i64 = 60;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RemoteRefsUpdatedEvent {
+    pub repo_id: String,
+    pub updates: Vec<RefUpdate>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RepoFetchStatus {
+    pub repo_id: String,
+    pub last_fetched_at: Option<i64>,
+    pub last_error: Option<String>,
+    // Consecutive failures, each doubling the delay before the next attempt
+    pub failures: u32,
+    // Set when the remote rejected our credentials; the repository isn't
+    // fetched again until resumed
+    pub paused: bool,
+    pub next_fetch_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BackgroundFetchStatus {
+    pub settings: BackgroundFetchSettings,
+    // No repository is fetched before this while the network is unreachable
+    pub network_retry_at: Option<i64>,
+    pub repos: Vec<RepoFetchStatus>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct RepoSchedule {
+    last_attempt_at: Option<i64>,
+    last_fetched_at: Option<i64>,
+    last_error: Option<String>,
+    failures: u32,
+    paused: bool,
+}
+
+impl RepoSchedule {
+    fn next_fetch_at(&self, interval: i64, now: i64) -> Option<i64> {
+        if self.paused {
+            return None;
+        }
+        Some(match self.last_attempt_at {
+            Some(at) => at + backoff(interval, self.failures),
+            None => now,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Schedule {
+    repos: HashMap<String, RepoSchedule>,
+    network_failures: u32,
+    network_retry_at: Option<i64>,
+}
+
+// Periodically fetches the remotes of open repositories. Fetching only
+// updates remote-tracking refs and tags, never local branches or the
+// working tree.
+#[derive(Default)]
+pub struct BackgroundFetcher {
+    schedule: Mutex<Schedule>,
+    wake: Condvar,
+    // Cancellation tokens of the fetches in progress, by repository
+    in_flight: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    fetch_done: Condvar,
+}
+
+// Lets a background fetch be cancelled when the user starts a job on the
+// same repository
+struct CancelObserver(Arc<AtomicBool>);
+
+impl RemoteObserver for CancelObserver {
+    fn progress(&self, _progress: &TransferProgress) {}
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+enum Failure {
+    Credentials,
+    Network,
+    Other,
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn backoff(base: i64, failures: u32) -> i64 {
+    base.saturating_mul(1 << failures.min(16))
+        .min(MAX_BACKOFF.max(base))
+}
+
+fn classify(error: &git2::Error) -> Failure {
+    let message = error.message().to_lowercase();
+    if error.code() == ErrorCode::Auth
+        || message.contains("credential")
+        || message.contains("authenticat")
+    {
+        Failure::Credentials
+    } else if matches!(
+        error.class(),
+        ErrorClass::Net | ErrorClass::Os | ErrorClass::Ssl | ErrorClass::Ssh
+    ) {
+        Failure::Network
+    } else {
+        Failure::Other
+    }
+}
+
+// Fetches every remote, keeping the updates of those that succeeded along
+// with the first error
+fn fetch_repo(
+    repo: &Repository,
+    system_git: bool,
+    observer: &CancelObserver,
+) -> (Vec<RefUpdate>, Option<git2::Error>) {
+    let remotes = match repo.remotes() {
+        Ok(remotes) => remotes,
+        Err(e) => return (Vec::new(), Some(e)),
+    };
+    let options = FetchRemoteOptions {
+        system_git,
+        ..Default::default()
+    };
+    let mut updates = Vec::new();
+    let mut error = None;
+    for name in remotes.iter().flatten() {
+        if observer.is_cancelled() {
+            break;
+        }
+        match git::fetch_remote(repo, name, &options, Some(observer)) {
+            Ok(result) => updates.extend(result.updates),
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    (updates, error)
+}
+
+impl BackgroundFetcher {
+    // Re-reads the settings and schedule right away instead of at the next
+    // tick
+    pub fn wake(&self) {
+        self.wake.notify_all();
+    }
+
+    // Clears a credential pause and any backoff so the repository is
+    // fetched again soon
+    pub fn resume(&self, repo_id: &str) {
+        let mut schedule = self.schedule.lock();
+        schedule
+            .repos
+            .insert(repo_id.to_string(), RepoSchedule::default());
+        schedule.network_failures = 0;
+        schedule.network_retry_at = None;
+        drop(schedule);
+        self.wake();
+    }
+
+    // Cancels the repository's background fetch, if one is in progress, and
+    // waits for it to stop, so the user's jobs don't race it for the ref and
+    // FETCH_HEAD lockfiles
+    pub fn cancel_for(&self, repo_id: &str) {
+        let mut in_flight = self.in_flight.lock();
+        while let Some(token) = in_flight.get(repo_id) {
+            token.store(true, Ordering::Relaxed);
+            self.fetch_done.wait(&mut in_flight);
+        }
+    }
+
+    fn record(&self, repo_id: &str, error: Option<&git2::Error>) {
+        let now = now();
+        let mut schedule = self.schedule.lock();
+        let schedule = &mut *schedule;
+        let entry = schedule.repos.entry(repo_id.to_string()).or_default();
+        let Some(error) = error else {
+            entry.last_attempt_at = Some(now);
+            entry.last_fetched_at = Some(now);
+            entry.last_error = None;
+            entry.failures = 0;
+            schedule.network_failures = 0;
+            schedule.network_retry_at = None;
+            return;
+        };
+        entry.last_error = Some(error.message().to_string());
+        match classify(error) {
+            Failure::Credentials => {
+                entry.last_attempt_at = Some(now);
+                entry.paused = true;
+            }
+            // Not the repository's fault, so it stays due and every
+            // repository waits for the network instead
+            Failure::Network => {
+                schedule.network_retry_at =
+                    Some(now + backoff(NETWORK_RETRY, schedule.network_failures));
+                schedule.network_failures += 1;
+            }
+            Failure::Other => {
+                entry.last_attempt_at = Some(now);
+                entry.failures += 1;
+            }
+        }
+    }
+
+    fn network_down(&self) -> bool {
+        self.schedule
+            .lock()
+            .network_retry_at
+            .is_some_and(|at| at > now())
+    }
+
+    fn run_due(&self, app_handle: &AppHandle) {
+        let state = app_handle.state::<AppState>();
+        let settings = state.settings.lock().get().background_fetch.clone();
+        if !settings.enabled {
+            return;
+        }
+        let interval = i64::from(settings.interval_minutes) * 60;
+        let repos = state.list_repositories();
+
+        let due: Vec<_> = {
+            let mut schedule = self.schedule.lock();
+            schedule
+                .repos
+                .retain(|id, _| repos.iter().any(|(open, _)| open == id));
+            let now = now();
+            if schedule.network_retry_at.is_some_and(|at| at > now) {
+                return;
+            }
+            repos
+                .into_iter()
+                .filter(|(id, _)| {
+                    schedule
+                        .repos
+                        .entry(id.clone())
+                        .or_default()
+                        .next_fetch_at(interval, now)
+                        .is_some_and(|at| at <= now)
+                })
+                .collect()
+        };
+
+        for (repo_id, repo_state) in due {
+            if !state.settings.lock().get().background_fetch.enabled || self.network_down() {
+                break;
+            }
+            // Marked as in flight before checking for jobs, so a job started
+            // in between finds the fetch and cancels it
+            let token = Arc::new(AtomicBool::new(false));
+            self.in_flight.lock().insert(repo_id.clone(), token.clone());
+            // A fetch, pull or push started by the user takes precedence
+            if state.jobs.is_running_for(&repo_id) {
+                self.finish_fetch(&repo_id);
+                continue;
+            }
+            let system_git = state
+                .settings
+                .lock()
+                .get()
+                .system_git
+                .enabled_for(&repo_state.path);
+            let observer = CancelObserver(token);
+            let (updates, error) = match repo_state.reopen() {
+                Ok(repo) => fetch_repo(&repo, system_git, &observer),
+                Err(e) => (Vec::new(), Some(git2::Error::from_str(&e.to_string()))),
+            };
+            self.finish_fetch(&repo_id);
+            // A cancelled fetch stays due and runs again after the user's job
+            if !observer.is_cancelled() {
+                self.record(&repo_id, error.as_ref());
+            }
+            if !updates.is_empty() {
+                let _ = app_handle.emit(
+                    "remote-refs-updated",
+                    RemoteRefsUpdatedEvent { repo_id, updates },
+                );
+            }
+        }
+    }
+
+    fn finish_fetch(&self, repo_id: &str) {
+        self.in_flight.lock().remove(repo_id);
+        self.fetch_done.notify_all();
+    }
+
+    pub fn status(&self, state: &AppState) -> BackgroundFetchStatus {
+        let settings = state.settings.lock().get().background_fetch.clone();
+        let interval = i64::from(settings.interval_minutes) * 60;
+        let now = now();
+        let schedule = self.schedule.lock();
+        let network_retry_at = schedule.network_retry_at.filter(|at| *at > now);
+        let repos = state
+            .list_repositories()
+            .into_iter()
+            .map(|(repo_id, _)| {
+                let entry = schedule.repos.get(&repo_id).cloned().unwrap_or_default();
+                let next_fetch_at = entry
+                    .next_fetch_at(interval, now)
+                    .filter(|_| settings.enabled)
+                    .map(|at| at.max(network_retry_at.unwrap_or(at)));
+                RepoFetchStatus {
+                    repo_id,
+                    last_fetched_at: entry.last_fetched_at,
+                    last_error: entry.last_error,
+                    failures: entry.failures,
+                    paused: entry.paused,
+                    next_fetch_at,
+                }
+            })
+            .collect();
+        BackgroundFetchStatus {
+            settings,
+            network_retry_at,
+            repos,
+        }
+    }
+}
+
+// Runs the schedule on its own thread for the lifetime of the app
+pub fn start(app_handle: AppHandle) {
+    std::thread::spawn(move || loop {
+        let fetcher = &app_handle.state::<AppState>().background_fetch;
+        {
+            let mut schedule = fetcher.schedule.lock();
+            fetcher.wake.wait_for(&mut schedule, TICK);
+        }
+        fetcher.run_due(&app_handle);
+    });
+}
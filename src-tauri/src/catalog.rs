@@ -0,0 +1,268 @@
+use crate::error::GitClientError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Recently opened repositories that are neither favorites nor grouped are
+// forgotten beyond this many entries
+const MAX_RECENT: usize = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CatalogEntry {
+    pub path: String,
+    pub alias: Option<String>,
+    pub favorite: bool,
+    pub group: Option<String>,
+    // Unix timestamp in seconds
+    pub last_opened: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+struct CatalogData {
+    repos: Vec<CatalogEntry>,
+    groups: Vec<String>,
+    // Repositories that were open, in tab order, restored on the next launch
+    session: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CatalogRepo {
+    #[serde(flatten)]
+    pub entry: CatalogEntry,
+    pub exists: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RepoCatalogView {
+    // Most recently opened first
+    pub repos: Vec<CatalogRepo>,
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct RepoCatalog {
+    // None until the app config directory is known
+    file: Option<PathBuf>,
+    data: CatalogData,
+}
+
+fn catalog_key(path: &str) -> &str {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        path
+    } else {
+        trimmed
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+impl RepoCatalog {
+    pub fn load(file: PathBuf) -> Self {
+        let data = match std::fs::read_to_string(&file) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring unreadable repository catalog: {e}");
+                CatalogData::default()
+            }),
+            Err(_) => CatalogData::default(),
+        };
+        Self {
+            file: Some(file),
+            data,
+        }
+    }
+
+    fn save(&self) -> Result<(), GitClientError> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.data)
+            .map_err(|e| GitClientError::Operation(e.to_string()))?;
+        // Write then rename so a crash never leaves a truncated catalog
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, file)?;
+        Ok(())
+    }
+
+    fn find_mut(&mut self, path: &str) -> Result<&mut CatalogEntry, GitClientError> {
+        let key = catalog_key(path);
+        self.data
+            .repos
+            .iter_mut()
+            .find(|e| e.path == key)
+            .ok_or_else(|| GitClientError::Operation(format!("{} is not in the catalog", path)))
+    }
+
+    fn ensure_group(&self, group: &str) -> Result<(), GitClientError> {
+        if self.data.groups.iter().any(|g| g == group) {
+            Ok(())
+        } else {
+            Err(GitClientError::Operation(format!(
+                "Group '{}' does not exist",
+                group
+            )))
+        }
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        let key = catalog_key(path);
+        self.data.repos.iter().any(|e| e.path == key)
+    }
+
+    pub fn view(&self) -> RepoCatalogView {
+        let mut repos: Vec<CatalogRepo> = self
+            .data
+            .repos
+            .iter()
+            .map(|entry| CatalogRepo {
+                entry: entry.clone(),
+                exists: Path::new(&entry.path).exists(),
+            })
+            .collect();
+        repos.sort_by_key(|r| std::cmp::Reverse(r.entry.last_opened));
+        RepoCatalogView {
+            repos,
+            groups: self.data.groups.clone(),
+        }
+    }
+
+    pub fn session(&self) -> Vec<String> {
+        self.data.session.clone()
+    }
+
+    pub fn record_opened(&mut self, path: &str) -> Result<(), GitClientError> {
+        let key = catalog_key(path).to_string();
+        match self.data.repos.iter_mut().find(|e| e.path == key) {
+            Some(entry) => entry.last_opened = Some(now()),
+            None => self.data.repos.push(CatalogEntry {
+                path: key.clone(),
+                last_opened: Some(now()),
+                ..Default::default()
+            }),
+        }
+        if !self.data.session.contains(&key) {
+            self.data.session.push(key);
+        }
+        self.prune_recent();
+        self.save()
+    }
+
+    pub fn record_closed(&mut self, path: &str) -> Result<(), GitClientError> {
+        let key = catalog_key(path);
+        self.data.session.retain(|p| p != key);
+        self.save()
+    }
+
+    fn prune_recent(&mut self) {
+        let mut recent: Vec<(Option<i64>, String)> = self
+            .data
+            .repos
+            .iter()
+            .filter(|e| !e.favorite && e.group.is_none())
+            .map(|e| (e.last_opened, e.path.clone()))
+            .collect();
+        if recent.len() <= MAX_RECENT {
+            return;
+        }
+        recent.sort_by_key(|(last_opened, _)| std::cmp::Reverse(*last_opened));
+        let forgotten: Vec<String> = recent
+            .into_iter()
+            .skip(MAX_RECENT)
+            .map(|(_, path)| path)
+            .collect();
+        self.data.repos.retain(|e| !forgotten.contains(&e.path));
+    }
+
+    pub fn set_favorite(&mut self, path: &str, favorite: bool) -> Result<(), GitClientError> {
+        self.find_mut(path)?.favorite = favorite;
+        self.save()
+    }
+
+    pub fn set_alias(&mut self, path: &str, alias: Option<String>) -> Result<(), GitClientError> {
+        let alias = alias
+            .map(|a| a.trim().to_string())
+            .filter(|a| !a.is_empty());
+        self.find_mut(path)?.alias = alias;
+        self.save()
+    }
+
+    pub fn set_group(&mut self, path: &str, group: Option<String>) -> Result<(), GitClientError> {
+        if let Some(group) = &group {
+            self.ensure_group(group)?;
+        }
+        self.find_mut(path)?.group = group;
+        self.save()
+    }
+
+    pub fn add_group(&mut self, name: &str) -> Result<(), GitClientError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(GitClientError::Operation(
+                "Group name cannot be empty".to_string(),
+            ));
+        }
+        if self.ensure_group(name).is_ok() {
+            return Err(GitClientError::Operation(format!(
+                "Group '{}' already exists",
+                name
+            )));
+        }
+        self.data.groups.push(name.to_string());
+        self.save()
+    }
+
+    pub fn rename_group(&mut self, old_name: &str, new_name: &str) -> Result<(), GitClientError> {
+        self.ensure_group(old_name)?;
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(GitClientError::Operation(
+                "Group name cannot be empty".to_string(),
+            ));
+        }
+        if new_name != old_name && self.ensure_group(new_name).is_ok() {
+            return Err(GitClientError::Operation(format!(
+                "Group '{}' already exists",
+                new_name
+            )));
+        }
+        for group in self.data.groups.iter_mut().filter(|g| *g == old_name) {
+            *group = new_name.to_string();
+        }
+        for entry in &mut self.data.repos {
+            if entry.group.as_deref() == Some(old_name) {
+                entry.group = Some(new_name.to_string());
+            }
+        }
+        self.save()
+    }
+
+    // Removes the group; its repositories stay in the catalog ungrouped
+    pub fn remove_group(&mut self, name: &str) -> Result<(), GitClientError> {
+        self.ensure_group(name)?;
+        self.data.groups.retain(|g| g != name);
+        for entry in &mut self.data.repos {
+            if entry.group.as_deref() == Some(name) {
+                entry.group = None;
+            }
+        }
+        self.save()
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<(), GitClientError> {
+        let key = catalog_key(path);
+        self.data.repos.retain(|e| e.path != key);
+        self.data.session.retain(|p| p != key);
+        self.save()
+    }
+}
@@ -0,0 +1,34 @@
+use crate::background_fetch::BackgroundFetchStatus;
+use crate::error::GitClientError;
+use crate::settings::BackgroundFetchSettings;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_background_fetch_status(
+    state: State<AppState>,
+) -> Result<BackgroundFetchStatus, GitClientError> {
+    Ok(state.background_fetch.status(&state))
+}
+
+#[tauri::command]
+pub fn set_background_fetch_settings(
+    settings: BackgroundFetchSettings,
+    state: State<AppState>,
+) -> Result<BackgroundFetchStatus, GitClientError> {
+    state.settings.lock().set_background_fetch(settings)?;
+    state.background_fetch.wake();
+    Ok(state.background_fetch.status(&state))
+}
+
+// Fetches a repository again after it was paused, e.g. once its credentials
+// have been fixed
+#[tauri::command]
+pub fn resume_background_fetch(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<BackgroundFetchStatus, GitClientError> {
+    state.get_repo(&repo_id)?;
+    state.background_fetch.resume(&repo_id);
+    Ok(state.background_fetch.status(&state))
+}
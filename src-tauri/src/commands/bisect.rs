@@ -0,0 +1,69 @@
+use crate::error::GitClientError;
+use crate::git::{self, BisectStatus};
+use crate::jobs::{spawn_job, JobInfo};
+use crate::state::AppState;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn bisect_start(
+    repo_id: String,
+    bad: Option<String>,
+    good: Vec<String>,
+    state: State<AppState>,
+) -> Result<BisectStatus, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::bisect_start(&repo, bad.as_deref(), &good)
+}
+
+#[tauri::command]
+pub fn bisect_mark(
+    repo_id: String,
+    term: String,
+    commit: Option<String>,
+    state: State<AppState>,
+) -> Result<BisectStatus, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::bisect_mark(&repo, &term, commit.as_deref())
+}
+
+#[tauri::command]
+pub fn bisect_status(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<BisectStatus, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::bisect_status(&repo)
+}
+
+#[tauri::command]
+pub fn bisect_reset(repo_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::bisect_reset(&repo)
+}
+
+#[tauri::command]
+pub fn bisect_run(
+    repo_id: String,
+    command: String,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<JobInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let description = format!("Bisect run {}", command);
+    // The test command can run for a long time, so the run holds its own
+    // handle rather than the repository lock
+    Ok(spawn_job(
+        &app_handle,
+        "bisect_run",
+        Some(repo_id),
+        description,
+        move |observer| {
+            let repo = repo_state.reopen()?;
+            git::bisect_run(&repo, &command, Some(observer))
+        },
+    ))
+}
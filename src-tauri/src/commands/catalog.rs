@@ -0,0 +1,107 @@
+use crate::catalog::RepoCatalogView;
+use crate::commands::repo::{activate_repository, is_gone, OpenRepoInfo};
+use crate::error::GitClientError;
+use crate::git;
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SessionRestore {
+    pub opened: Vec<OpenRepoInfo>,
+    // Session repositories that could not be opened (moved or deleted)
+    pub missing: Vec<String>,
+    // Session repositories that failed to open for another reason, as
+    // "<path>: <error>"; they stay in the session
+    pub failed: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_repo_catalog(state: State<AppState>) -> Result<RepoCatalogView, GitClientError> {
+    Ok(state.catalog.lock().view())
+}
+
+#[tauri::command]
+pub fn set_repo_favorite(
+    path: String,
+    favorite: bool,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    state.catalog.lock().set_favorite(&path, favorite)
+}
+
+#[tauri::command]
+pub fn set_repo_alias(
+    path: String,
+    alias: Option<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    state.catalog.lock().set_alias(&path, alias)
+}
+
+#[tauri::command]
+pub fn set_repo_group(
+    path: String,
+    group: Option<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    state.catalog.lock().set_group(&path, group)
+}
+
+#[tauri::command]
+pub fn add_repo_group(name: String, state: State<AppState>) -> Result<(), GitClientError> {
+    state.catalog.lock().add_group(&name)
+}
+
+#[tauri::command]
+pub fn rename_repo_group(
+    old_name: String,
+    new_name: String,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    state.catalog.lock().rename_group(&old_name, &new_name)
+}
+
+#[tauri::command]
+pub fn remove_repo_group(name: String, state: State<AppState>) -> Result<(), GitClientError> {
+    state.catalog.lock().remove_group(&name)
+}
+
+#[tauri::command]
+pub fn remove_from_catalog(path: String, state: State<AppState>) -> Result<(), GitClientError> {
+    state.catalog.lock().remove(&path)
+}
+
+// Reopens the repositories that were open when the app last exited
+#[tauri::command]
+pub fn restore_session(
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<SessionRestore, GitClientError> {
+    let session = state.catalog.lock().session();
+    let mut restore = SessionRestore {
+        opened: Vec::new(),
+        missing: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for path in session {
+        let opened = match git::open_repo(&path) {
+            Ok(repo) => activate_repository(&state, repo, &path, &app_handle),
+            Err(e) if is_gone(&e) => {
+                if let Err(e) = state.catalog.lock().record_closed(&path) {
+                    eprintln!("Failed to update repository catalog: {e}");
+                }
+                restore.missing.push(path);
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+        match opened {
+            Ok(info) => restore.opened.push(info),
+            Err(e) => restore.failed.push(format!("{}: {}", path, e)),
+        }
+    }
+
+    Ok(restore)
+}
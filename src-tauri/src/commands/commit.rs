@@ -1,148 +1,194 @@
 use crate::error::GitClientError;
-use crate::git::{self, CommitDetails, CommitGraphEntry, CommitInfo};
+use crate::git::{self, CommitDetails, CommitGraphEntry, CommitInfo, CommitOptions, SplitState};
 use crate::state::AppState;
 use tauri::State;
 
 #[tauri::command]
-pub fn stage_file(path: String, state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::stage_file(repo, &path)
+pub fn stage_file(
+    repo_id: String,
+    path: String,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::stage_file(&repo, &path)
 }
 
 #[tauri::command]
-pub fn unstage_file(path: String, state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::unstage_file(repo, &path)
+pub fn unstage_file(
+    repo_id: String,
+    path: String,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::unstage_file(&repo, &path)
 }
 
 #[tauri::command]
-pub fn stage_all(state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::stage_all(repo)
+pub fn stage_all(repo_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::stage_all(&repo)
 }
 
 #[tauri::command]
-pub fn unstage_all(state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::unstage_all(repo)
+pub fn unstage_all(repo_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::unstage_all(&repo)
 }
 
 #[tauri::command]
-pub fn discard_changes(path: String, state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::discard_changes(repo, &path)
+pub fn discard_changes(
+    repo_id: String,
+    path: String,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::discard_changes(&repo, &path)
 }
 
 #[tauri::command]
-pub fn discard_all_changes(state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::discard_all_changes(repo)
+pub fn discard_all_changes(repo_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::discard_all_changes(&repo)
 }
 
 #[tauri::command]
 pub fn create_commit(
+    repo_id: String,
     message: String,
+    options: Option<CommitOptions>,
     state: State<AppState>,
 ) -> Result<CommitInfo, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::create_commit(repo, &message)
+    let options = options.unwrap_or_default();
+    let identities = state.settings.lock().get().identities.clone();
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    if let Some(profile) = git::matching_identity_profile(&repo, &identities) {
+        git::check_commit_identity(&repo, &options, profile)?;
+    }
+    git::create_commit(&repo, &message, &options)
 }
 
 #[tauri::command]
 pub fn get_commit_history(
+    repo_id: String,
     limit: usize,
     skip: usize,
     branch_name: Option<String>,
     state: State<AppState>,
 ) -> Result<Vec<CommitInfo>, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::get_commit_history(repo, limit, skip, branch_name.as_deref())
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_commit_history(&repo, limit, skip, branch_name.as_deref())
 }
 
 #[tauri::command]
 pub fn get_commit_history_with_graph(
+    repo_id: String,
     limit: usize,
     skip: usize,
     branch_name: Option<String>,
     state: State<AppState>,
 ) -> Result<Vec<CommitGraphEntry>, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::get_commit_history_with_graph(repo, limit, skip, branch_name.as_deref())
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_commit_history_with_graph(&repo, limit, skip, branch_name.as_deref())
 }
 
 #[tauri::command]
 pub fn get_commit_details(
+    repo_id: String,
     oid: String,
     state: State<AppState>,
 ) -> Result<CommitDetails, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::get_commit_details(repo, &oid)
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_commit_details(&repo, &oid)
 }
 
 #[tauri::command]
 pub fn reset_to_commit(
+    repo_id: String,
     commit_id: String,
     mode: String,
     state: State<AppState>,
 ) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::reset_to_commit(repo, &commit_id, &mode)
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::reset_to_commit(&repo, &commit_id, &mode)
 }
 
 #[tauri::command]
 pub fn squash_commits(
+    repo_id: String,
     commit_ids: Vec<String>,
     message: String,
     state: State<AppState>,
 ) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::squash_commits(repo, &commit_ids, &message)
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::squash_commits(&repo, &commit_ids, &message)
+}
+
+#[tauri::command]
+pub fn create_fixup_commit(
+    repo_id: String,
+    target_id: String,
+    kind: String,
+    message: Option<String>,
+    state: State<AppState>,
+) -> Result<CommitInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::create_fixup_commit(&repo, &target_id, &kind, message.as_deref().unwrap_or(""))
+}
+
+#[tauri::command]
+pub fn autosquash(repo_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::autosquash(&repo)
+}
+
+#[tauri::command]
+pub fn start_split_commit(
+    repo_id: String,
+    commit_id: String,
+    state: State<AppState>,
+) -> Result<SplitState, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::start_split(&repo, &commit_id)
+}
+
+#[tauri::command]
+pub fn continue_split_commit(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Option<SplitState>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::continue_split(&repo)
+}
+
+#[tauri::command]
+pub fn abort_split_commit(repo_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::abort_split(&repo)
+}
+
+#[tauri::command]
+pub fn get_split_state(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Option<SplitState>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_split_state(&repo)
 }
@@ -1,4 +1,5 @@
 use crate::error::GitClientError;
+use crate::git::{self, ConfigEntryInfo, ConfigInclude};
 use crate::state::AppState;
 use serde::Serialize;
 use tauri::State;
@@ -10,12 +11,12 @@ pub struct GitConfig {
 }
 
 #[tauri::command]
-pub fn get_git_config(state: State<AppState>) -> Result<GitConfig, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
+pub fn get_git_config(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<GitConfig, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
 
     let config = repo.config()?;
 
@@ -30,18 +31,90 @@ pub fn get_git_config(state: State<AppState>) -> Result<GitConfig, GitClientErro
 
 #[tauri::command]
 pub fn set_git_config(
+    repo_id: String,
     key: String,
     value: String,
     state: State<AppState>,
 ) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
 
-    let mut config = repo.config()?;
-    config.set_str(&key, &value)?;
+    git::set_config_value(&repo, "local", &key, &value, None)
+}
 
-    Ok(())
+// Config entries with their levels, from every level or only `level`
+#[tauri::command]
+pub fn list_config_entries(
+    repo_id: String,
+    level: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<ConfigEntryInfo>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::list_config(&repo, level.as_deref())
+}
+
+// All values of a key; the last one is the one that applies
+#[tauri::command]
+pub fn get_config_value(
+    repo_id: String,
+    key: String,
+    level: Option<String>,
+    state: State<AppState>,
+) -> Result<Vec<ConfigEntryInfo>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_config_values(&repo, &key, level.as_deref())
+}
+
+#[tauri::command]
+pub fn set_config_value(
+    repo_id: String,
+    level: String,
+    key: String,
+    value: String,
+    value_type: Option<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::set_config_value(&repo, &level, &key, &value, value_type.as_deref())
+}
+
+#[tauri::command]
+pub fn add_config_value(
+    repo_id: String,
+    level: String,
+    key: String,
+    value: String,
+    value_type: Option<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::add_config_value(&repo, &level, &key, &value, value_type.as_deref())
+}
+
+// Removes the entries of `key` with `value`, or all of them
+#[tauri::command]
+pub fn unset_config_value(
+    repo_id: String,
+    level: String,
+    key: String,
+    value: Option<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::unset_config_value(&repo, &level, &key, value.as_deref())
+}
+
+#[tauri::command]
+pub fn list_config_includes(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Vec<ConfigInclude>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::list_config_includes(&repo)
 }
@@ -5,24 +5,23 @@ use tauri::State;
 
 #[tauri::command]
 pub fn get_file_diff(
+    repo_id: String,
     path: String,
     staged: bool,
     state: State<AppState>,
 ) -> Result<FileDiff, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::get_file_diff(repo, &path, staged)
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_file_diff(&repo, &path, staged)
 }
 
 #[tauri::command]
-pub fn get_commit_diff(oid: String, state: State<AppState>) -> Result<CommitDiff, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::get_commit_diff(repo, &oid)
+pub fn get_commit_diff(
+    repo_id: String,
+    oid: String,
+    state: State<AppState>,
+) -> Result<CommitDiff, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_commit_diff(&repo, &oid)
 }
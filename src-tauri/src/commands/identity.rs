@@ -0,0 +1,52 @@
+use crate::error::GitClientError;
+use crate::git::{self, RepoIdentity};
+use crate::settings::IdentitySettings;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_identity_settings(state: State<AppState>) -> Result<IdentitySettings, GitClientError> {
+    Ok(state.settings.lock().get().identities.clone())
+}
+
+#[tauri::command]
+pub fn set_identity_settings(
+    settings: IdentitySettings,
+    state: State<AppState>,
+) -> Result<IdentitySettings, GitClientError> {
+    let mut store = state.settings.lock();
+    store.set_identities(settings)?;
+    Ok(store.get().identities.clone())
+}
+
+// The profile the rules choose for the repository, and whether its config
+// uses that identity
+#[tauri::command]
+pub fn get_repo_identity(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<RepoIdentity, GitClientError> {
+    let identities = state.settings.lock().get().identities.clone();
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::repo_identity(&repo, &identities)
+}
+
+#[tauri::command]
+pub fn apply_identity_profile(
+    repo_id: String,
+    profile: String,
+    state: State<AppState>,
+) -> Result<RepoIdentity, GitClientError> {
+    let identities = state.settings.lock().get().identities.clone();
+    let Some(selected) = identities.profiles.iter().find(|p| p.name == profile) else {
+        return Err(GitClientError::Operation(format!(
+            "No identity profile is named {}",
+            profile
+        )));
+    };
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::apply_identity_profile(&repo, selected)?;
+    git::repo_identity(&repo, &identities)
+}
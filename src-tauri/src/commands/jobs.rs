@@ -0,0 +1,33 @@
+use crate::error::GitClientError;
+use crate::git::CredentialResponse;
+use crate::jobs::JobInfo;
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_jobs(state: State<AppState>) -> Result<Vec<JobInfo>, GitClientError> {
+    Ok(state.jobs.list())
+}
+
+#[tauri::command]
+pub fn get_job(job_id: String, state: State<AppState>) -> Result<JobInfo, GitClientError> {
+    state
+        .jobs
+        .get(&job_id)
+        .ok_or_else(|| GitClientError::Operation(format!("Job '{}' not found", job_id)))
+}
+
+#[tauri::command]
+pub fn cancel_job(job_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    state.jobs.cancel(&job_id)
+}
+
+// Answers a `credential-requested` event; a null response declines
+#[tauri::command]
+pub fn respond_credential_request(
+    prompt_id: String,
+    response: Option<CredentialResponse>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    state.jobs.respond_credential(&prompt_id, response)
+}
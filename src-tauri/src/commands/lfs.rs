@@ -0,0 +1,47 @@
+use crate::error::GitClientError;
+use crate::git::{self, LfsLock, LfsStatus};
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_lfs_status(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<LfsStatus, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_lfs_status(&repo)
+}
+
+#[tauri::command]
+pub fn list_lfs_locks(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Vec<LfsLock>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::list_lfs_locks(&repo)
+}
+
+#[tauri::command]
+pub fn lock_lfs_file(
+    repo_id: String,
+    path: String,
+    state: State<AppState>,
+) -> Result<LfsLock, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::lock_lfs_file(&repo, &path)
+}
+
+#[tauri::command]
+pub fn unlock_lfs_file(
+    repo_id: String,
+    path: String,
+    force: bool,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::unlock_lfs_file(&repo, &path, force)
+}
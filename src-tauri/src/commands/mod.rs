@@ -1,15 +1,35 @@
+pub mod background_fetch;
+pub mod bisect;
 pub mod branch;
+pub mod catalog;
 pub mod commit;
 pub mod config;
 pub mod diff;
+pub mod identity;
+pub mod jobs;
+pub mod lfs;
 pub mod remote;
 pub mod repo;
+pub mod sparse;
 pub mod stash;
+pub mod submodule;
+pub mod system_git;
+pub mod worktree;
 
+pub use background_fetch::*;
+pub use bisect::*;
 pub use branch::*;
+pub use catalog::*;
 pub use commit::*;
 pub use config::*;
 pub use diff::*;
+pub use identity::*;
+pub use jobs::*;
+pub use lfs::*;
 pub use remote::*;
 pub use repo::*;
+pub use sparse::*;
 pub use stash::*;
+pub use submodule::*;
+pub use system_git::*;
+pub use worktree::*;
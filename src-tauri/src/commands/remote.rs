@@ -1,57 +1,251 @@
 use crate::error::GitClientError;
-use crate::git::{self, PullResult, RemoteInfo};
+use crate::git::{self, FetchRemoteOptions, PushRemoteOptions, RemoteInfo};
+use crate::jobs::{spawn_job, JobInfo};
 use crate::state::AppState;
-use tauri::State;
+use tauri::{AppHandle, State};
 
 #[tauri::command]
-pub fn fetch(remote: String, state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::fetch_remote(repo, &remote).map_err(GitClientError::Git)
+pub fn fetch(
+    repo_id: String,
+    remote: String,
+    options: Option<FetchRemoteOptions>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<JobInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let mut options = options.unwrap_or_default();
+    options.system_git = state
+        .settings
+        .lock()
+        .get()
+        .system_git
+        .enabled_for(&repo_state.path);
+    let description = if options.all_remotes {
+        "Fetch all remotes".to_string()
+    } else {
+        format!("Fetch {}", remote)
+    };
+    Ok(spawn_job(
+        &app_handle,
+        "fetch",
+        Some(repo_id),
+        description,
+        move |observer| {
+            let repo = repo_state.reopen()?;
+            git::fetch_remote(&repo, &remote, &options, Some(observer)).map_err(GitClientError::Git)
+        },
+    ))
 }
 
 #[tauri::command]
 pub fn pull(
+    repo_id: String,
+    remote: String,
+    branch: String,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<JobInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let fetch_options = FetchRemoteOptions {
+        system_git: state
+            .settings
+            .lock()
+            .get()
+            .system_git
+            .enabled_for(&repo_state.path),
+        ..Default::default()
+    };
+    let description = format!("Pull {}/{}", remote, branch);
+    Ok(spawn_job(
+        &app_handle,
+        "pull",
+        Some(repo_id),
+        description,
+        move |observer| {
+            let repo = repo_state.reopen()?;
+            git::pull_remote(&repo, &remote, &branch, &fetch_options, Some(observer))
+                .map_err(GitClientError::Git)
+        },
+    ))
+}
+
+#[tauri::command]
+pub fn push(
+    repo_id: String,
     remote: String,
     branch: String,
+    options: Option<PushRemoteOptions>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<JobInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let mut options = options.unwrap_or_default();
+    options.system_git = state
+        .settings
+        .lock()
+        .get()
+        .system_git
+        .enabled_for(&repo_state.path);
+    let remote_branch = options
+        .remote_branch
+        .clone()
+        .unwrap_or_else(|| branch.clone());
+    let description = if options.delete {
+        format!("Delete {} on {}", remote_branch, remote)
+    } else if options.force || options.force_with_lease {
+        format!("Force push {} to {}/{}", branch, remote, remote_branch)
+    } else {
+        format!("Push {} to {}/{}", branch, remote, remote_branch)
+    };
+    Ok(spawn_job(
+        &app_handle,
+        "push",
+        Some(repo_id),
+        description,
+        move |observer| {
+            let repo = repo_state.reopen()?;
+            git::push_remote(&repo, &remote, &branch, &options, Some(observer))
+                .map_err(GitClientError::Git)
+        },
+    ))
+}
+
+// Fetches more history into a shallow clone; no depth means unshallow
+#[tauri::command]
+pub fn deepen_repository(
+    repo_id: String,
+    remote: String,
+    depth: Option<u32>,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<JobInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let description = match depth {
+        Some(depth) => format!("Deepen to {} commits from {}", depth, remote),
+        None => format!("Unshallow from {}", remote),
+    };
+    Ok(spawn_job(
+        &app_handle,
+        "deepen",
+        Some(repo_id),
+        description,
+        move |observer| {
+            let repo = repo_state.reopen()?;
+            git::deepen_repo(&repo, &remote, depth, Some(observer))
+        },
+    ))
+}
+
+#[tauri::command]
+pub fn list_remotes(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Vec<RemoteInfo>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::list_remotes(&repo)
+}
+
+#[tauri::command]
+pub fn add_remote(
+    repo_id: String,
+    name: String,
+    url: String,
+    fetch_refspec: Option<String>,
     state: State<AppState>,
-) -> Result<PullResult, GitClientError> {
-    let mut guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_mut()
-        .ok_or(GitClientError::NoRepository)?;
+) -> Result<RemoteInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    // A background fetch would race this for the remote's config and refs
+    state.background_fetch.cancel_for(&repo_id);
+    let repo = repo_state.repository.lock();
+    git::add_remote(&repo, &name, &url, fetch_refspec.as_deref())
+}
 
-    let is_dirty = git::is_worktree_dirty(repo).map_err(GitClientError::Git)?;
-    if is_dirty {
-        return Err(GitClientError::Operation(
-            "Worktree has uncommitted changes. Commit, stash, or discard them before pulling."
-                .to_string(),
-        ));
-    }
+// Returns refspecs that could not be renamed automatically
+#[tauri::command]
+pub fn rename_remote(
+    repo_id: String,
+    old_name: String,
+    new_name: String,
+    state: State<AppState>,
+) -> Result<Vec<String>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    state.background_fetch.cancel_for(&repo_id);
+    let repo = repo_state.repository.lock();
+    git::rename_remote(&repo, &old_name, &new_name)
+}
 
-    git::pull_remote(repo, &remote, &branch).map_err(GitClientError::Git)
+#[tauri::command]
+pub fn remove_remote(
+    repo_id: String,
+    name: String,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    state.background_fetch.cancel_for(&repo_id);
+    let repo = repo_state.repository.lock();
+    git::remove_remote(&repo, &name)
 }
 
 #[tauri::command]
-pub fn push(remote: String, branch: String, state: State<AppState>) -> Result<(), GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::push_remote(repo, &remote, &branch).map_err(GitClientError::Git)
+pub fn set_remote_url(
+    repo_id: String,
+    name: String,
+    url: String,
+    state: State<AppState>,
+) -> Result<RemoteInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    state.background_fetch.cancel_for(&repo_id);
+    let repo = repo_state.repository.lock();
+    git::set_remote_url(&repo, &name, &url)
 }
 
 #[tauri::command]
-pub fn list_remotes(state: State<AppState>) -> Result<Vec<RemoteInfo>, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::list_remotes(repo).map_err(GitClientError::Git)
+pub fn set_remote_push_url(
+    repo_id: String,
+    name: String,
+    push_url: Option<String>,
+    state: State<AppState>,
+) -> Result<RemoteInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    state.background_fetch.cancel_for(&repo_id);
+    let repo = repo_state.repository.lock();
+    git::set_remote_push_url(&repo, &name, push_url.as_deref())
+}
+
+#[tauri::command]
+pub fn set_remote_fetch_refspecs(
+    repo_id: String,
+    name: String,
+    refspecs: Vec<String>,
+    state: State<AppState>,
+) -> Result<RemoteInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    state.background_fetch.cancel_for(&repo_id);
+    let repo = repo_state.repository.lock();
+    git::set_remote_fetch_refspecs(&repo, &name, &refspecs)
+}
+
+// `remote` is a configured remote's name or a URL. Runs as a job so that the
+// connection can prompt for host keys, certificates and credentials.
+#[tauri::command]
+pub fn test_remote_connection(
+    repo_id: String,
+    remote: String,
+    push: bool,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<JobInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let description = format!("Test connection to {}", remote);
+    Ok(spawn_job(
+        &app_handle,
+        "test_connection",
+        Some(repo_id),
+        description,
+        move |observer| {
+            let repo = repo_state.reopen()?;
+            git::test_remote_connection(&repo, &remote, push, Some(observer))
+        },
+    ))
 }
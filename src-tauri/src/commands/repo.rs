@@ -1,18 +1,60 @@
 use crate::error::GitClientError;
-use crate::git::{self, RepoInfo, RepoStatus};
-use crate::state::AppState;
+use crate::git::{self, CloneOptions, RepoInfo, RepoStatus, ScannedRepo};
+use crate::jobs::{spawn_job, JobInfo};
+use crate::state::{AppState, RepoState};
 use crate::watcher::RepoWatcher;
-use std::path::PathBuf;
-use tauri::{AppHandle, State};
+use git2::Repository;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
 
-fn start_watcher(state: &AppState, path: &str, app_handle: &AppHandle) {
-    // Stop any existing watcher first
-    let mut watcher_guard = state.watcher.lock();
-    *watcher_guard = None;
+fn start_watcher(repo: &Repository, repo_id: &str, app_handle: &AppHandle) -> Option<RepoWatcher> {
+    match RepoWatcher::start(repo, repo_id.to_string(), app_handle.clone()) {
+        Ok(w) => Some(w),
+        Err(e) => {
+            eprintln!("Failed to start file watcher: {e}");
+            None
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct OpenRepoInfo {
+    pub repo_id: String,
+    #[serde(flatten)]
+    pub info: RepoInfo,
+}
+
+// Registers `repo` and starts its watcher. Opening a repository that is already
+// open replaces it under the same ID rather than adding a second entry.
+pub(crate) fn activate_repository(
+    state: &AppState,
+    repo: Repository,
+    path: &str,
+    app_handle: &AppHandle,
+) -> Result<OpenRepoInfo, GitClientError> {
+    let info = git::get_repo_info(&repo)?;
+    let repo_id = state
+        .find_repo_id(&repo)
+        .unwrap_or_else(|| state.next_repo_id());
+    let watcher = start_watcher(&repo, &repo_id, app_handle);
+    let repo_state = RepoState::new(repo, PathBuf::from(path));
+    *repo_state.watcher.lock() = watcher;
+    state.set_repository(repo_id.clone(), repo_state);
+    if let Err(e) = state.catalog.lock().record_opened(path) {
+        eprintln!("Failed to update repository catalog: {e}");
+    }
+    Ok(OpenRepoInfo { repo_id, info })
+}
 
-    match RepoWatcher::start(PathBuf::from(path), app_handle.clone()) {
-        Ok(w) => *watcher_guard = Some(w),
-        Err(e) => eprintln!("Failed to start file watcher: {e}"),
+// Whether opening a repository failed because it is gone, or is no longer a
+// repository, i.e. it was moved or deleted. Other errors, such as permissions
+// or a corrupt .git, leave it where it is.
+pub(crate) fn is_gone(error: &GitClientError) -> bool {
+    match error {
+        GitClientError::RepoNotFound(_) => true,
+        GitClientError::Git(e) => e.code() == git2::ErrorCode::NotFound,
+        _ => false,
     }
 }
 
@@ -21,12 +63,15 @@ pub fn open_repository(
     path: String,
     state: State<AppState>,
     app_handle: AppHandle,
-) -> Result<RepoInfo, GitClientError> {
-    let repo = git::open_repo(&path)?;
-    let info = git::get_repo_info(&repo)?;
-    state.set_repository(repo, PathBuf::from(&path));
-    start_watcher(&state, &path, &app_handle);
-    Ok(info)
+) -> Result<OpenRepoInfo, GitClientError> {
+    let repo = git::open_repo(&path).map_err(|e| {
+        if is_gone(&e) && state.catalog.lock().contains(&path) {
+            GitClientError::RepoMissing(path.clone())
+        } else {
+            e
+        }
+    })?;
+    activate_repository(&state, repo, &path, &app_handle)
 }
 
 #[tauri::command]
@@ -34,59 +79,76 @@ pub fn init_repository(
     path: String,
     state: State<AppState>,
     app_handle: AppHandle,
-) -> Result<RepoInfo, GitClientError> {
+) -> Result<OpenRepoInfo, GitClientError> {
     let repo = git::init_repo(&path)?;
-    let info = git::get_repo_info(&repo)?;
-    state.set_repository(repo, PathBuf::from(&path));
-    start_watcher(&state, &path, &app_handle);
-    Ok(info)
+    activate_repository(&state, repo, &path, &app_handle)
 }
 
+// Clones in the background; the finished job's result is the OpenRepoInfo
 #[tauri::command]
 pub fn clone_repository(
     url: String,
     path: String,
-    state: State<AppState>,
+    options: Option<CloneOptions>,
     app_handle: AppHandle,
-) -> Result<RepoInfo, GitClientError> {
-    let repo = git::clone_repo(&url, &path)?;
-    let info = git::get_repo_info(&repo)?;
-    state.set_repository(repo, PathBuf::from(&path));
-    start_watcher(&state, &path, &app_handle);
-    Ok(info)
+) -> JobInfo {
+    let description = format!("Clone {}", url);
+    let options = options.unwrap_or_default();
+    let handle = app_handle.clone();
+    spawn_job(&app_handle, "clone", None, description, move |observer| {
+        let repo = git::clone_repo(&url, &path, &options, Some(observer))?;
+        activate_repository(&handle.state::<AppState>(), repo, &path, &handle)
+    })
 }
 
 #[tauri::command]
-pub fn get_status(state: State<AppState>) -> Result<RepoStatus, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::get_status(repo)
+pub fn get_status(repo_id: String, state: State<AppState>) -> Result<RepoStatus, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_status(&repo)
 }
 
 #[tauri::command]
-pub fn get_repo_info(state: State<AppState>) -> Result<RepoInfo, GitClientError> {
-    let guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_ref()
-        .ok_or(GitClientError::NoRepository)?;
-    git::get_repo_info(repo)
+pub fn get_repo_info(repo_id: String, state: State<AppState>) -> Result<RepoInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_repo_info(&repo)
 }
 
 #[tauri::command]
-pub fn close_repository(state: State<AppState>) -> Result<(), GitClientError> {
-    // Stop the file watcher
-    *state.watcher.lock() = None;
-    state.clear_repository();
+pub fn close_repository(repo_id: String, state: State<AppState>) -> Result<(), GitClientError> {
+    // Dropping the state stops its file watcher
+    let repo_state = state
+        .remove_repository(&repo_id)
+        .ok_or(GitClientError::UnknownRepository(repo_id))?;
+    let path = repo_state.path.to_string_lossy();
+    if let Err(e) = state.catalog.lock().record_closed(&path) {
+        eprintln!("Failed to update repository catalog: {e}");
+    }
     Ok(())
 }
 
 #[tauri::command]
-pub fn get_current_repo_path(state: State<AppState>) -> Result<Option<String>, GitClientError> {
-    Ok(state
-        .get_repo_path()
-        .map(|p| p.to_string_lossy().to_string()))
+pub fn list_open_repositories(state: State<AppState>) -> Result<Vec<OpenRepoInfo>, GitClientError> {
+    state
+        .list_repositories()
+        .into_iter()
+        .map(|(repo_id, repo_state)| {
+            let info = git::get_repo_info(&repo_state.repository.lock())?;
+            Ok(OpenRepoInfo { repo_id, info })
+        })
+        .collect()
+}
+
+// Scanning touches every repository's status, so it runs off the main thread
+#[tauri::command]
+pub async fn scan_repositories(
+    root: String,
+    max_depth: Option<usize>,
+) -> Result<Vec<ScannedRepo>, GitClientError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        git::scan_for_repositories(Path::new(&root), max_depth)
+    })
+    .await
+    .map_err(|e| GitClientError::Operation(e.to_string()))?
 }
@@ -0,0 +1,69 @@
+use crate::error::GitClientError;
+use crate::git::{self, SparseCheckoutInfo};
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn get_sparse_checkout(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::get_sparse_checkout(&repo)
+}
+
+#[tauri::command]
+pub fn enable_sparse_checkout(
+    repo_id: String,
+    cone: bool,
+    patterns: Vec<String>,
+    state: State<AppState>,
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::enable_sparse_checkout(&repo, cone, &patterns)
+}
+
+#[tauri::command]
+pub fn disable_sparse_checkout(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::disable_sparse_checkout(&repo)
+}
+
+#[tauri::command]
+pub fn set_sparse_patterns(
+    repo_id: String,
+    patterns: Vec<String>,
+    state: State<AppState>,
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::set_sparse_patterns(&repo, &patterns)
+}
+
+#[tauri::command]
+pub fn add_sparse_patterns(
+    repo_id: String,
+    patterns: Vec<String>,
+    state: State<AppState>,
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::add_sparse_patterns(&repo, &patterns)
+}
+
+#[tauri::command]
+pub fn remove_sparse_patterns(
+    repo_id: String,
+    patterns: Vec<String>,
+    state: State<AppState>,
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::remove_sparse_patterns(&repo, &patterns)
+}
@@ -12,12 +12,13 @@ pub struct StashEntry {
 }
 
 #[tauri::command]
-pub fn stash_save(message: Option<String>, state: State<AppState>) -> Result<(), GitClientError> {
-    let mut guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_mut()
-        .ok_or(GitClientError::NoRepository)?;
+pub fn stash_save(
+    repo_id: String,
+    message: Option<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let mut repo = repo_state.repository.lock();
 
     let signature = repo.signature()?;
     let msg = message.as_deref();
@@ -28,12 +29,13 @@ pub fn stash_save(message: Option<String>, state: State<AppState>) -> Result<(),
 }
 
 #[tauri::command]
-pub fn stash_pop(index: usize, state: State<AppState>) -> Result<(), GitClientError> {
-    let mut guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_mut()
-        .ok_or(GitClientError::NoRepository)?;
+pub fn stash_pop(
+    repo_id: String,
+    index: usize,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let mut repo = repo_state.repository.lock();
 
     repo.stash_pop(index, None)?;
 
@@ -41,12 +43,13 @@ pub fn stash_pop(index: usize, state: State<AppState>) -> Result<(), GitClientEr
 }
 
 #[tauri::command]
-pub fn stash_apply(index: usize, state: State<AppState>) -> Result<(), GitClientError> {
-    let mut guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_mut()
-        .ok_or(GitClientError::NoRepository)?;
+pub fn stash_apply(
+    repo_id: String,
+    index: usize,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let mut repo = repo_state.repository.lock();
 
     repo.stash_apply(index, None)?;
 
@@ -54,12 +57,13 @@ pub fn stash_apply(index: usize, state: State<AppState>) -> Result<(), GitClient
 }
 
 #[tauri::command]
-pub fn stash_drop(index: usize, state: State<AppState>) -> Result<(), GitClientError> {
-    let mut guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_mut()
-        .ok_or(GitClientError::NoRepository)?;
+pub fn stash_drop(
+    repo_id: String,
+    index: usize,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let mut repo = repo_state.repository.lock();
 
     repo.stash_drop(index)?;
 
@@ -67,12 +71,12 @@ pub fn stash_drop(index: usize, state: State<AppState>) -> Result<(), GitClientE
 }
 
 #[tauri::command]
-pub fn stash_list(state: State<AppState>) -> Result<Vec<StashEntry>, GitClientError> {
-    let mut guard = state.repo.lock();
-    let repo = guard
-        .repository
-        .as_mut()
-        .ok_or(GitClientError::NoRepository)?;
+pub fn stash_list(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Vec<StashEntry>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let mut repo = repo_state.repository.lock();
 
     let mut stashes = Vec::new();
 
@@ -0,0 +1,79 @@
+use crate::commands::repo::{activate_repository, OpenRepoInfo};
+use crate::error::GitClientError;
+use crate::git::{self, SubmoduleInfo};
+use crate::jobs::{spawn_job, JobInfo};
+use crate::state::AppState;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn list_submodules(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Vec<SubmoduleInfo>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::list_submodules(&repo)
+}
+
+#[tauri::command]
+pub fn init_submodules(
+    repo_id: String,
+    names: Vec<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::init_submodules(&repo, &names)
+}
+
+#[tauri::command]
+pub fn update_submodules(
+    repo_id: String,
+    names: Vec<String>,
+    init: bool,
+    recursive: bool,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<JobInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let description = "Update submodules".to_string();
+    Ok(spawn_job(
+        &app_handle,
+        "update_submodules",
+        Some(repo_id),
+        description,
+        move |observer| {
+            let repo = repo_state.reopen()?;
+            git::update_submodules(&repo, &names, init, recursive, Some(observer))
+        },
+    ))
+}
+
+#[tauri::command]
+pub fn sync_submodules(
+    repo_id: String,
+    names: Vec<String>,
+    recursive: bool,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::sync_submodules(&repo, &names, recursive)
+}
+
+#[tauri::command]
+pub fn open_submodule(
+    repo_id: String,
+    name: String,
+    state: State<AppState>,
+    app_handle: AppHandle,
+) -> Result<OpenRepoInfo, GitClientError> {
+    let path = {
+        let repo_state = state.get_repo(&repo_id)?;
+        let repo = repo_state.repository.lock();
+        git::submodule_workdir(&repo, &name)?
+    };
+    let path = path.to_string_lossy().to_string();
+    let repo = git::open_repo(&path)?;
+    activate_repository(&state, repo, &path, &app_handle)
+}
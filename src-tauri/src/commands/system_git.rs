@@ -0,0 +1,64 @@
+use crate::error::GitClientError;
+use crate::git;
+use crate::settings::SystemGitSettings;
+use crate::state::AppState;
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SystemGitStatus {
+    pub settings: SystemGitSettings,
+    // `git --version` of the installed git, None if none was found
+    pub version: Option<String>,
+}
+
+fn system_git_status(state: &AppState) -> SystemGitStatus {
+    SystemGitStatus {
+        settings: state.settings.lock().get().system_git.clone(),
+        version: git::system_git_version(),
+    }
+}
+
+fn store_system_git(
+    state: &AppState,
+    settings: SystemGitSettings,
+) -> Result<SystemGitStatus, GitClientError> {
+    let uses_git = settings.enabled || settings.repos.values().any(|enabled| *enabled);
+    if uses_git && git::system_git_version().is_none() {
+        return Err(GitClientError::Operation(
+            "No git executable was found on the PATH".to_string(),
+        ));
+    }
+    state.settings.lock().set_system_git(settings)?;
+    Ok(system_git_status(state))
+}
+
+#[tauri::command]
+pub fn get_system_git_settings(state: State<AppState>) -> Result<SystemGitStatus, GitClientError> {
+    Ok(system_git_status(&state))
+}
+
+#[tauri::command]
+pub fn set_system_git_settings(
+    settings: SystemGitSettings,
+    state: State<AppState>,
+) -> Result<SystemGitStatus, GitClientError> {
+    store_system_git(&state, settings)
+}
+
+// Overrides the global setting for one repository; None follows it again
+#[tauri::command]
+pub fn set_repo_system_git(
+    repo_id: String,
+    enabled: Option<bool>,
+    state: State<AppState>,
+) -> Result<SystemGitStatus, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let key = repo_state.path.to_string_lossy().into_owned();
+    let mut settings = state.settings.lock().get().system_git.clone();
+    match enabled {
+        Some(enabled) => settings.repos.insert(key, enabled),
+        None => settings.repos.remove(&key),
+    };
+    store_system_git(&state, settings)
+}
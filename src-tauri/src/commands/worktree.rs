@@ -0,0 +1,70 @@
+use crate::error::GitClientError;
+use crate::git::{self, AddWorktreeOptions, WorktreeInfo};
+use crate::state::AppState;
+use tauri::State;
+
+#[tauri::command]
+pub fn list_worktrees(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Vec<WorktreeInfo>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::list_worktrees(&repo)
+}
+
+#[tauri::command]
+pub fn add_worktree(
+    repo_id: String,
+    options: AddWorktreeOptions,
+    state: State<AppState>,
+) -> Result<WorktreeInfo, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::add_worktree(&repo, &options)
+}
+
+#[tauri::command]
+pub fn lock_worktree(
+    repo_id: String,
+    name: String,
+    reason: Option<String>,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::lock_worktree(&repo, &name, reason.as_deref())
+}
+
+#[tauri::command]
+pub fn unlock_worktree(
+    repo_id: String,
+    name: String,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::unlock_worktree(&repo, &name)
+}
+
+#[tauri::command]
+pub fn prune_worktrees(
+    repo_id: String,
+    state: State<AppState>,
+) -> Result<Vec<String>, GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::prune_worktrees(&repo)
+}
+
+#[tauri::command]
+pub fn remove_worktree(
+    repo_id: String,
+    name: String,
+    force: bool,
+    state: State<AppState>,
+) -> Result<(), GitClientError> {
+    let repo_state = state.get_repo(&repo_id)?;
+    let repo = repo_state.repository.lock();
+    git::remove_worktree(&repo, &name, force)
+}
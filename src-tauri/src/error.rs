@@ -9,9 +9,15 @@ pub enum GitClientError {
     #[error("No repository open")]
     NoRepository,
 
+    #[error("No open repository with id: {0}")]
+    UnknownRepository(String),
+
     #[error("Repository not found at path: {0}")]
     RepoNotFound(String),
 
+    #[error("Repository moved or deleted: {0}")]
+    RepoMissing(String),
+
     #[error("Invalid path: {0}")]
     InvalidPath(String),
 
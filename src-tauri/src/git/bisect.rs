@@ -0,0 +1,567 @@
+use crate::error::GitClientError;
+use crate::git::credentials::{RemoteObserver, TransferProgress};
+use crate::git::history::{collect_tags_by_commit, commit_to_info, CommitInfo};
+use crate::git::process::{kill_process_group, spawn_in_process_group};
+use git2::build::CheckoutBuilder;
+use git2::{Oid, Repository, Sort, StatusOptions};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+
+// State files shared with command-line `git bisect`
+const BISECT_FILES: [&str; 7] = [
+    "BISECT_START",
+    "BISECT_TERMS",
+    "BISECT_LOG",
+    "BISECT_NAMES",
+    "BISECT_EXPECTED_REV",
+    "BISECT_ANCESTORS_OK",
+    "BISECT_FIRST_PARENT",
+];
+
+// Exact best-commit selection keeps one bitset per suspect, so cap its size
+const MAX_EXACT_SUSPECTS: usize = 8192;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BisectStatus {
+    pub in_progress: bool,
+    pub original_head: Option<String>,
+    pub current: Option<String>,
+    pub bad: Option<String>,
+    pub good: Vec<String>,
+    pub skipped: Vec<String>,
+    pub suspects: Vec<CommitInfo>,
+    pub estimated_steps: u32,
+    pub first_bad_commit: Option<CommitInfo>,
+    pub only_skipped_left: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BisectRunStep {
+    pub commit: String,
+    pub exit_code: Option<i32>,
+    pub result: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BisectRunResult {
+    pub steps: Vec<BisectRunStep>,
+    pub status: BisectStatus,
+}
+
+struct BisectTerms {
+    bad: String,
+    good: String,
+}
+
+fn read_terms(git_dir: &Path) -> BisectTerms {
+    let contents = std::fs::read_to_string(git_dir.join("BISECT_TERMS")).unwrap_or_default();
+    let mut lines = contents.lines();
+    match (lines.next(), lines.next()) {
+        (Some(bad), Some(good)) if !bad.is_empty() && !good.is_empty() => BisectTerms {
+            bad: bad.to_string(),
+            good: good.to_string(),
+        },
+        _ => BisectTerms {
+            bad: "bad".to_string(),
+            good: "good".to_string(),
+        },
+    }
+}
+
+fn is_bisecting(repo: &Repository) -> bool {
+    repo.path().join("BISECT_START").exists()
+}
+
+fn append_log(repo: &Repository, line: &str) -> Result<(), GitClientError> {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(repo.path().join("BISECT_LOG"))?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+fn describe(repo: &Repository, oid: Oid) -> String {
+    let summary = repo
+        .find_commit(oid)
+        .ok()
+        .and_then(|c| c.summary().map(|s| s.to_string()))
+        .unwrap_or_default();
+    format!("[{}] {}", oid, summary)
+}
+
+fn resolve_commit(repo: &Repository, spec: &str) -> Result<Oid, GitClientError> {
+    let object = repo
+        .revparse_single(spec)
+        .map_err(|e| GitClientError::Operation(format!("Unknown revision '{}': {}", spec, e)))?;
+    Ok(object.peel_to_commit()?.id())
+}
+
+fn ensure_no_tracked_changes(repo: &Repository) -> Result<(), GitClientError> {
+    let mut status_options = StatusOptions::new();
+    status_options.include_untracked(false);
+    let statuses = repo.statuses(Some(&mut status_options))?;
+    if !statuses.is_empty() {
+        return Err(GitClientError::Operation(
+            "Cannot bisect with uncommitted changes".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn collect_bisect_refs(repo: &Repository, prefix: &str) -> Result<Vec<Oid>, GitClientError> {
+    let mut oids = Vec::new();
+    for reference in repo.references_glob(&format!("refs/bisect/{}-*", prefix))? {
+        if let Some(oid) = reference?.target() {
+            oids.push(oid);
+        }
+    }
+    Ok(oids)
+}
+
+fn mark_commit(
+    repo: &Repository,
+    terms: &BisectTerms,
+    term: &str,
+    oid: Oid,
+) -> Result<(), GitClientError> {
+    let (refname, log_term) = match term {
+        "bad" => (format!("refs/bisect/{}", terms.bad), terms.bad.as_str()),
+        "good" => (
+            format!("refs/bisect/{}-{}", terms.good, oid),
+            terms.good.as_str(),
+        ),
+        "skip" => (format!("refs/bisect/skip-{}", oid), "skip"),
+        _ => {
+            return Err(GitClientError::Operation(format!(
+                "Invalid bisect term: {}. Expected 'good', 'bad' or 'skip'.",
+                term
+            )))
+        }
+    };
+
+    repo.reference(&refname, oid, true, "bisect")?;
+    append_log(repo, &format!("# {}: {}", log_term, describe(repo, oid)))?;
+    append_log(repo, &format!("git bisect {} {}", log_term, oid))?;
+    Ok(())
+}
+
+// Same estimate `git bisect` prints as "roughly N steps".
+fn estimate_steps(suspects: usize) -> u32 {
+    if suspects < 3 {
+        return 0;
+    }
+    let n = usize::BITS - 1 - suspects.leading_zeros();
+    let e = 1usize << n;
+    let x = suspects - e;
+    if e < 3 * x {
+        n
+    } else {
+        n - 1
+    }
+}
+
+// Picks the suspect that splits the remaining range most evenly, i.e. whose
+// ancestors within the range are closest to half of it.
+fn find_best_suspect(
+    repo: &Repository,
+    suspects: &[Oid],
+    skipped: &HashSet<Oid>,
+) -> Result<Option<Oid>, GitClientError> {
+    let testable: Vec<usize> = (0..suspects.len())
+        .filter(|&i| !skipped.contains(&suspects[i]))
+        .collect();
+    if testable.is_empty() {
+        return Ok(None);
+    }
+
+    let total = suspects.len();
+    if total > MAX_EXACT_SUSPECTS {
+        // Fall back to the topological midpoint for very large ranges
+        let middle = total / 2;
+        let best = testable
+            .iter()
+            .min_by_key(|&&i| i.abs_diff(middle))
+            .copied()
+            .unwrap_or(testable[0]);
+        return Ok(Some(suspects[best]));
+    }
+
+    let index_of: HashMap<Oid, usize> = suspects
+        .iter()
+        .enumerate()
+        .map(|(i, oid)| (*oid, i))
+        .collect();
+    let words = total.div_ceil(64);
+    let mut ancestors: Vec<Vec<u64>> = vec![vec![0; words]; total];
+
+    // Suspects are in topological order (children first), so walk backwards
+    for i in (0..total).rev() {
+        let commit = repo.find_commit(suspects[i])?;
+        let mut bits = vec![0u64; words];
+        bits[i / 64] |= 1 << (i % 64);
+        for parent_id in commit.parent_ids() {
+            if let Some(&p) = index_of.get(&parent_id) {
+                for (word, parent_word) in bits.iter_mut().zip(&ancestors[p]) {
+                    *word |= parent_word;
+                }
+            }
+        }
+        ancestors[i] = bits;
+    }
+
+    let best = testable
+        .iter()
+        .copied()
+        .max_by_key(|&i| {
+            let reach: usize = ancestors[i].iter().map(|w| w.count_ones() as usize).sum();
+            // Prefer the newest commit on ties, like git
+            (reach.min(total - reach), std::cmp::Reverse(i))
+        })
+        .unwrap_or(testable[0]);
+    Ok(Some(suspects[best]))
+}
+
+fn list_suspects(repo: &Repository, bad: Oid, good: &[Oid]) -> Result<Vec<Oid>, GitClientError> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(bad)?;
+    for oid in good {
+        revwalk.hide(*oid)?;
+    }
+    revwalk.set_sorting(Sort::TOPOLOGICAL)?;
+    let suspects = revwalk.collect::<Result<Vec<_>, _>>()?;
+    Ok(suspects)
+}
+
+pub fn bisect_status(repo: &Repository) -> Result<BisectStatus, GitClientError> {
+    if !is_bisecting(repo) {
+        return Ok(BisectStatus {
+            in_progress: false,
+            original_head: None,
+            current: None,
+            bad: None,
+            good: Vec::new(),
+            skipped: Vec::new(),
+            suspects: Vec::new(),
+            estimated_steps: 0,
+            first_bad_commit: None,
+            only_skipped_left: false,
+        });
+    }
+
+    let git_dir = repo.path();
+    let terms = read_terms(git_dir);
+    let original_head = std::fs::read_to_string(git_dir.join("BISECT_START"))
+        .ok()
+        .map(|s| s.trim().to_string());
+    let current = repo
+        .head()
+        .ok()
+        .and_then(|h| h.target())
+        .map(|o| o.to_string());
+    let bad = repo
+        .find_reference(&format!("refs/bisect/{}", terms.bad))
+        .ok()
+        .and_then(|r| r.target());
+    let good = collect_bisect_refs(repo, &terms.good)?;
+    let skipped: HashSet<Oid> = collect_bisect_refs(repo, "skip")?.into_iter().collect();
+
+    let tags_by_commit = collect_tags_by_commit(repo);
+    let mut suspects = Vec::new();
+    let mut first_bad_commit = None;
+    let mut only_skipped_left = false;
+    let mut estimated_steps = 0;
+
+    if let (Some(bad_oid), false) = (bad, good.is_empty()) {
+        let suspect_oids = list_suspects(repo, bad_oid, &good)?;
+        let untested: Vec<Oid> = suspect_oids
+            .iter()
+            .copied()
+            .filter(|oid| *oid != bad_oid && !skipped.contains(oid))
+            .collect();
+        if untested.is_empty() {
+            if suspect_oids.len() == 1 {
+                first_bad_commit =
+                    Some(commit_to_info(&repo.find_commit(bad_oid)?, &tags_by_commit));
+            } else {
+                only_skipped_left = true;
+            }
+        }
+        estimated_steps = estimate_steps(suspect_oids.len());
+        for oid in suspect_oids {
+            suspects.push(commit_to_info(&repo.find_commit(oid)?, &tags_by_commit));
+        }
+    }
+
+    Ok(BisectStatus {
+        in_progress: true,
+        original_head,
+        current,
+        bad: bad.map(|o| o.to_string()),
+        good: good.iter().map(|o| o.to_string()).collect(),
+        skipped: skipped.iter().map(|o| o.to_string()).collect(),
+        suspects,
+        estimated_steps,
+        first_bad_commit,
+        only_skipped_left,
+    })
+}
+
+// Checks out the next commit to test, or records the result once bisection is done.
+fn bisect_next(repo: &Repository) -> Result<BisectStatus, GitClientError> {
+    let status = bisect_status(repo)?;
+    if let Some(first_bad) = &status.first_bad_commit {
+        let oid = Oid::from_str(&first_bad.id)?;
+        append_log(
+            repo,
+            &format!("# first bad commit: {}", describe(repo, oid)),
+        )?;
+        return Ok(status);
+    }
+    if status.only_skipped_left || status.suspects.is_empty() {
+        return Ok(status);
+    }
+
+    let suspects = status
+        .suspects
+        .iter()
+        .map(|c| Oid::from_str(&c.id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut skipped: HashSet<Oid> = status
+        .skipped
+        .iter()
+        .map(|id| Oid::from_str(id))
+        .collect::<Result<_, _>>()?;
+    if let Some(bad) = &status.bad {
+        skipped.insert(Oid::from_str(bad)?);
+    }
+
+    let Some(next) = find_best_suspect(repo, &suspects, &skipped)? else {
+        return Ok(status);
+    };
+
+    let commit = repo.find_commit(next)?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    repo.set_head_detached(next)?;
+    std::fs::write(
+        repo.path().join("BISECT_EXPECTED_REV"),
+        format!("{}\n", next),
+    )?;
+
+    bisect_status(repo)
+}
+
+pub fn bisect_start(
+    repo: &Repository,
+    bad: Option<&str>,
+    good: &[String],
+) -> Result<BisectStatus, GitClientError> {
+    if is_bisecting(repo) {
+        return Err(GitClientError::Operation(
+            "A bisect is already in progress".to_string(),
+        ));
+    }
+    ensure_no_tracked_changes(repo)?;
+
+    let head = repo.head()?;
+    let start = if head.is_branch() {
+        head.shorthand().unwrap_or("HEAD").to_string()
+    } else {
+        head.target()
+            .map(|o| o.to_string())
+            .ok_or_else(|| GitClientError::Operation("HEAD has no target commit".to_string()))?
+    };
+
+    let bad_oid = resolve_commit(repo, bad.unwrap_or("HEAD"))?;
+    let good_oids = good
+        .iter()
+        .map(|spec| resolve_commit(repo, spec))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let git_dir = repo.path();
+    std::fs::write(git_dir.join("BISECT_START"), format!("{}\n", start))?;
+    std::fs::write(git_dir.join("BISECT_TERMS"), "bad\ngood\n")?;
+    std::fs::write(git_dir.join("BISECT_NAMES"), "\n")?;
+    std::fs::write(git_dir.join("BISECT_LOG"), "")?;
+
+    let mut start_args = vec![bad_oid.to_string()];
+    start_args.extend(good_oids.iter().map(|o| o.to_string()));
+    append_log(
+        repo,
+        &format!("git bisect start '{}'", start_args.join("' '")),
+    )?;
+
+    let terms = read_terms(git_dir);
+    mark_commit(repo, &terms, "bad", bad_oid)?;
+    for oid in good_oids {
+        mark_commit(repo, &terms, "good", oid)?;
+    }
+
+    bisect_next(repo)
+}
+
+pub fn bisect_mark(
+    repo: &Repository,
+    term: &str,
+    commit: Option<&str>,
+) -> Result<BisectStatus, GitClientError> {
+    if !is_bisecting(repo) {
+        return Err(GitClientError::Operation(
+            "No bisect in progress".to_string(),
+        ));
+    }
+    let oid = resolve_commit(repo, commit.unwrap_or("HEAD"))?;
+    mark_commit(repo, &read_terms(repo.path()), term, oid)?;
+    bisect_next(repo)
+}
+
+pub fn bisect_reset(repo: &Repository) -> Result<(), GitClientError> {
+    if !is_bisecting(repo) {
+        return Err(GitClientError::Operation(
+            "No bisect in progress".to_string(),
+        ));
+    }
+
+    let git_dir = repo.path().to_path_buf();
+    let start = std::fs::read_to_string(git_dir.join("BISECT_START"))?
+        .trim()
+        .to_string();
+
+    let branch_ref = format!("refs/heads/{}", start);
+    if let Ok(reference) = repo.find_reference(&branch_ref) {
+        let commit = reference.peel_to_commit()?;
+        repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        repo.set_head(&branch_ref)?;
+    } else {
+        let oid = resolve_commit(repo, &start)?;
+        let commit = repo.find_commit(oid)?;
+        repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))?;
+        repo.set_head_detached(oid)?;
+    }
+
+    let mut bisect_refs = Vec::new();
+    for reference in repo.references_glob("refs/bisect/*")? {
+        if let Some(name) = reference?.name() {
+            bisect_refs.push(name.to_string());
+        }
+    }
+    for name in bisect_refs {
+        repo.find_reference(&name)?.delete()?;
+    }
+
+    for file in BISECT_FILES {
+        let path = git_dir.join(file);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+// Runs the test command in `workdir`, killing it if the job is cancelled
+fn run_test_command(
+    command: &str,
+    workdir: &Path,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<ExitStatus, GitClientError> {
+    // The shell runs in its own group, so cancelling also stops the test it
+    // started
+    let mut child = spawn_in_process_group(shell_command(command).current_dir(workdir))?;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if observer.is_some_and(|o| o.is_cancelled()) {
+            kill_process_group(&mut child);
+            return Err(GitClientError::Operation(
+                "Bisect run cancelled".to_string(),
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// Runs `command` on each bisect step like `git bisect run`: exit code 0 marks
+// the commit good, 125 skips it, 1-127 marks it bad and anything else aborts.
+// Progress reports the number of commits tested so far.
+pub fn bisect_run(
+    repo: &Repository,
+    command: &str,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<BisectRunResult, GitClientError> {
+    let mut status = bisect_status(repo)?;
+    if !status.in_progress {
+        return Err(GitClientError::Operation(
+            "No bisect in progress".to_string(),
+        ));
+    }
+    if status.bad.is_none() || status.good.is_empty() {
+        return Err(GitClientError::Operation(
+            "Mark at least one good and one bad commit before running".to_string(),
+        ));
+    }
+
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| GitClientError::Operation("Cannot bisect a bare repository".to_string()))?
+        .to_path_buf();
+    let mut steps = Vec::new();
+    // Every step removes at least one suspect, so this bounds the loop
+    let max_steps = status.suspects.len() + 1;
+
+    while status.first_bad_commit.is_none() && !status.only_skipped_left && steps.len() < max_steps
+    {
+        let commit = status
+            .current
+            .clone()
+            .ok_or_else(|| GitClientError::Operation("HEAD has no target commit".to_string()))?;
+        if let Some(observer) = observer {
+            observer.progress(&TransferProgress {
+                stage: "bisecting".to_string(),
+                current: steps.len(),
+                message: Some(format!("Testing {}", &commit[..7])),
+                ..Default::default()
+            });
+        }
+        append_log(repo, &format!("# running {}", command))?;
+
+        let exit_code = run_test_command(command, &workdir, observer)?.code();
+        let term = match exit_code {
+            Some(0) => "good",
+            Some(125) => "skip",
+            Some(code) if (1..128).contains(&code) => "bad",
+            _ => {
+                return Err(GitClientError::Operation(format!(
+                    "Bisect run aborted: '{}' exited with {:?}",
+                    command, exit_code
+                )))
+            }
+        };
+
+        steps.push(BisectRunStep {
+            commit: commit.clone(),
+            exit_code,
+            result: term.to_string(),
+        });
+        status = bisect_mark(repo, term, Some(&commit))?;
+    }
+
+    Ok(BisectRunResult { steps, status })
+}
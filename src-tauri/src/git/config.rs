@@ -0,0 +1,716 @@
+use crate::error::GitClientError;
+use crate::git::sparse::wildmatch;
+use git2::{Config, ConfigLevel, Repository};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+// git stops following includes this deep
+const MAX_INCLUDE_DEPTH: usize = 10;
+
+// The levels in the order git reads them, later ones overriding earlier
+const LEVEL_ORDER: [&str; 5] = ["system", "xdg", "global", "local", "worktree"];
+
+// Value types of well-known keys, by section and variable name; any
+// subsection is ignored, so `http.<url>.sslVerify` is a bool too
+const KNOWN_TYPES: [(&str, &str); 33] = [
+    ("commit.gpgsign", "bool"),
+    ("commit.template", "path"),
+    ("core.attributesfile", "path"),
+    ("core.bare", "bool"),
+    ("core.compression", "int"),
+    ("core.excludesfile", "path"),
+    ("core.filemode", "bool"),
+    ("core.hookspath", "path"),
+    ("core.ignorecase", "bool"),
+    ("core.logallrefupdates", "bool"),
+    ("core.symlinks", "bool"),
+    ("diff.renamelimit", "int"),
+    ("fetch.parallel", "int"),
+    ("fetch.prune", "bool"),
+    ("fetch.prunetags", "bool"),
+    ("gc.auto", "int"),
+    ("http.postbuffer", "int"),
+    ("http.sslcainfo", "path"),
+    ("http.sslcapath", "path"),
+    ("http.sslverify", "bool"),
+    ("include.path", "path"),
+    ("includeif.path", "path"),
+    ("merge.renamelimit", "int"),
+    ("pack.threads", "int"),
+    ("push.autosetupremote", "bool"),
+    ("push.followtags", "bool"),
+    ("rebase.autosquash", "bool"),
+    ("rebase.autostash", "bool"),
+    ("rerere.enabled", "bool"),
+    ("submodule.recurse", "bool"),
+    ("tag.gpgsign", "bool"),
+    ("user.useconfigonly", "bool"),
+    ("worktree.guessremote", "bool"),
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ConfigEntryInfo {
+    pub name: String,
+    // None for a key without `=`, which counts as true
+    pub value: Option<String>,
+    // "system", "xdg", "global", "local" or "worktree"
+    pub level: String,
+    // The level's config file; None for entries from included files
+    pub file: Option<String>,
+    // How many includes deep the entry was found
+    pub include_depth: u32,
+}
+
+// An `include.path` or `includeIf.<condition>.path` entry and whether it
+// applies to the repository
+#[derive(Debug, Serialize, Clone)]
+pub struct ConfigInclude {
+    pub level: String,
+    // The file the include is written in
+    pub file: String,
+    // The includeIf condition, like `gitdir:~/work/`; None for include.path
+    pub condition: Option<String>,
+    pub path: String,
+    pub resolved_path: String,
+    pub exists: bool,
+    pub active: bool,
+    // What the included file itself sets, when it applies
+    pub entries: Vec<ConfigEntryInfo>,
+}
+
+pub fn level_name(level: ConfigLevel) -> &'static str {
+    match level {
+        ConfigLevel::ProgramData => "programdata",
+        ConfigLevel::System => "system",
+        ConfigLevel::XDG => "xdg",
+        ConfigLevel::Global => "global",
+        ConfigLevel::Local => "local",
+        ConfigLevel::Worktree => "worktree",
+        ConfigLevel::App => "app",
+        ConfigLevel::Highest => "highest",
+    }
+}
+
+fn parse_level(level: &str) -> Result<ConfigLevel, GitClientError> {
+    match level {
+        "system" => Ok(ConfigLevel::System),
+        "xdg" => Ok(ConfigLevel::XDG),
+        "global" => Ok(ConfigLevel::Global),
+        "local" => Ok(ConfigLevel::Local),
+        "worktree" => Ok(ConfigLevel::Worktree),
+        other => Err(GitClientError::Operation(format!(
+            "Invalid config level: {}. Expected 'system', 'xdg', 'global', 'local' or 'worktree'.",
+            other
+        ))),
+    }
+}
+
+// The file git reads for a level, whether or not it exists yet
+fn level_file(repo: &Repository, level: ConfigLevel) -> Option<PathBuf> {
+    match level {
+        ConfigLevel::System => {
+            Some(Config::find_system().unwrap_or_else(|_| "/etc/gitconfig".into()))
+        }
+        ConfigLevel::XDG => Config::find_xdg().ok().or_else(|| {
+            let config_home = std::env::var_os("XDG_CONFIG_HOME")
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| dirs::home_dir().map(|home| home.join(".config")))?;
+            Some(config_home.join("git").join("config"))
+        }),
+        ConfigLevel::Global => Config::find_global()
+            .ok()
+            .or_else(|| dirs::home_dir().map(|home| home.join(".gitconfig"))),
+        ConfigLevel::Local => Some(repo.path().join("config")),
+        ConfigLevel::Worktree => Some(repo.path().join("config.worktree")),
+        _ => None,
+    }
+}
+
+// The config of one level, for reading and writing only that level. Levels
+// whose file doesn't exist yet are opened by path, creating the file on the
+// first write, like git does; see `writable_level_config`.
+fn level_config(repo: &Repository, level: ConfigLevel) -> Result<Config, GitClientError> {
+    if level == ConfigLevel::Worktree
+        && !repo
+            .config()?
+            .get_bool("extensions.worktreeConfig")
+            .unwrap_or(false)
+    {
+        return Err(GitClientError::Operation(
+            "Worktree config is only read with extensions.worktreeConfig enabled".to_string(),
+        ));
+    }
+    match repo.config()?.open_level(level) {
+        Ok(config) => Ok(config),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            let file = level_file(repo, level).ok_or(GitClientError::Git(e))?;
+            Ok(Config::open(&file)?)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+// A level's config for changing it. The directory of a level file that doesn't
+// exist yet, like ~/.config/git, is created so the first write can succeed;
+// only reading a level never creates anything.
+fn writable_level_config(repo: &Repository, level: ConfigLevel) -> Result<Config, GitClientError> {
+    let config = level_config(repo, level)?;
+    if let Some(dir) = level_file(repo, level).as_deref().and_then(Path::parent) {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(config)
+}
+
+fn entry_info(repo: &Repository, entry: &git2::ConfigEntry) -> ConfigEntryInfo {
+    let include_depth = entry.include_depth();
+    ConfigEntryInfo {
+        name: entry.name().unwrap_or_default().to_string(),
+        value: entry.value().map(|v| v.to_string()),
+        level: level_name(entry.level()).to_string(),
+        file: (include_depth == 0)
+            .then(|| level_file(repo, entry.level()))
+            .flatten()
+            .map(|file| file.display().to_string()),
+        include_depth,
+    }
+}
+
+// libgit2 lists the highest level first; entries are shown in the order git
+// reads them instead, so the last value of a key is the one that applies
+fn sort_by_level(entries: &mut [ConfigEntryInfo]) {
+    entries.sort_by_key(|e| LEVEL_ORDER.iter().position(|level| *level == e.level));
+}
+
+// Every config entry, in the order git reads them, optionally only those of
+// one level
+pub fn list_config(
+    repo: &Repository,
+    level: Option<&str>,
+) -> Result<Vec<ConfigEntryInfo>, GitClientError> {
+    let config = match level {
+        Some(level) => level_config(repo, parse_level(level)?)?,
+        None => repo.config()?,
+    };
+    let mut entries = Vec::new();
+    let mut iter = config.entries(None)?;
+    while let Some(entry) = iter.next() {
+        entries.push(entry_info(repo, entry?));
+    }
+    sort_by_level(&mut entries);
+    Ok(entries)
+}
+
+// Checks that `key` is a valid config key: a section and variable name of
+// letters, digits and `-`, the variable starting with a letter, with an
+// optional subsection between them
+pub fn validate_config_key(key: &str) -> Result<(), GitClientError> {
+    let invalid = |reason: &str| {
+        Err(GitClientError::Operation(format!(
+            "Invalid config key {:?}: {}",
+            key, reason
+        )))
+    };
+    let (Some(first), Some(last)) = (key.find('.'), key.rfind('.')) else {
+        return invalid("expected section.name");
+    };
+    let section = &key[..first];
+    let variable = &key[last + 1..];
+    if section.is_empty()
+        || !section
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return invalid("the section may only contain letters, digits and '-'");
+    }
+    if !variable.starts_with(|c: char| c.is_ascii_alphabetic())
+        || !variable
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+    {
+        return invalid(
+            "the name must start with a letter and contain only letters, digits and '-'",
+        );
+    }
+    if key[first..last].contains(['\n', '\0']) {
+        return invalid("the subsection may not contain newlines");
+    }
+    Ok(())
+}
+
+// The type of a well-known key, by its section and variable name
+fn known_type(key: &str) -> Option<&'static str> {
+    let (section, _) = key.split_once('.')?;
+    let (_, variable) = key.rsplit_once('.')?;
+    let name = format!("{}.{}", section, variable).to_lowercase();
+    KNOWN_TYPES
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, value_type)| *value_type)
+}
+
+// git's boolean values; a key without a value is true
+pub(crate) fn parse_config_bool(value: Option<&str>) -> Option<bool> {
+    match value.map(|v| v.trim().to_lowercase()).as_deref() {
+        None | Some("true" | "yes" | "on" | "1") => Some(true),
+        Some("false" | "no" | "off" | "0" | "") => Some(false),
+        _ => None,
+    }
+}
+
+// Integers with an optional k, m or g suffix, like git accepts them
+fn parse_config_int(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (number, factor) = match value.chars().last()?.to_ascii_lowercase() {
+        'k' => (&value[..value.len() - 1], 1024),
+        'm' => (&value[..value.len() - 1], 1024 * 1024),
+        'g' => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+    number.parse::<i64>().ok()?.checked_mul(factor)
+}
+
+// Checks `value` against the given type, or the key's known type, and
+// returns it the way git would write it: booleans as true or false and
+// integers without suffix
+fn normalize_value(
+    key: &str,
+    value: &str,
+    value_type: Option<&str>,
+) -> Result<String, GitClientError> {
+    let invalid = |expected: &str| {
+        Err(GitClientError::Operation(format!(
+            "Invalid value for {}: {:?} is not {}",
+            key, value, expected
+        )))
+    };
+    if value.contains('\0') {
+        return invalid("a valid string");
+    }
+    match value_type.or_else(|| known_type(key)) {
+        Some("bool") => match parse_config_bool(Some(value)) {
+            Some(b) => Ok(b.to_string()),
+            None => invalid("a boolean"),
+        },
+        Some("int") => match parse_config_int(value) {
+            Some(n) => Ok(n.to_string()),
+            None => invalid("an integer"),
+        },
+        Some("path") => {
+            if value.trim().is_empty() || value.contains('\n') {
+                return invalid("a path");
+            }
+            Ok(value.to_string())
+        }
+        Some("string") | None => Ok(value.to_string()),
+        Some(other) => Err(GitClientError::Operation(format!(
+            "Invalid value type: {}. Expected 'bool', 'int', 'path' or 'string'.",
+            other
+        ))),
+    }
+}
+
+// Matches values literally in libgit2's value regexes
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if "\\.^$|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// The values of `key`, in the order git reads them, from every level or
+// only from `level`
+pub fn get_config_values(
+    repo: &Repository,
+    key: &str,
+    level: Option<&str>,
+) -> Result<Vec<ConfigEntryInfo>, GitClientError> {
+    validate_config_key(key)?;
+    let config = match level {
+        Some(level) => level_config(repo, parse_level(level)?)?,
+        None => repo.config()?,
+    };
+    let mut values = Vec::new();
+    let mut iter = config.multivar(key, None)?;
+    while let Some(entry) = iter.next() {
+        values.push(entry_info(repo, entry?));
+    }
+    sort_by_level(&mut values);
+    Ok(values)
+}
+
+fn count_values(config: &Config, key: &str) -> Result<usize, GitClientError> {
+    let mut count = 0;
+    let mut iter = config.multivar(key, None)?;
+    while let Some(entry) = iter.next() {
+        if entry?.include_depth() == 0 {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+// Sets `key` at `level`, like `git config --<level> key value`. Keys with
+// several values at the level are refused; their values are added and
+// removed one at a time.
+pub fn set_config_value(
+    repo: &Repository,
+    level: &str,
+    key: &str,
+    value: &str,
+    value_type: Option<&str>,
+) -> Result<(), GitClientError> {
+    validate_config_key(key)?;
+    let value = normalize_value(key, value, value_type)?;
+    let mut config = writable_level_config(repo, parse_level(level)?)?;
+    let count = count_values(&config, key)?;
+    if count > 1 {
+        return Err(GitClientError::Operation(format!(
+            "{} has {} values in the {} config; add or remove them individually",
+            key, count, level
+        )));
+    }
+    config.set_str(key, &value)?;
+    Ok(())
+}
+
+// Adds another value to a multi-valued key, like `git config --add`
+pub fn add_config_value(
+    repo: &Repository,
+    level: &str,
+    key: &str,
+    value: &str,
+    value_type: Option<&str>,
+) -> Result<(), GitClientError> {
+    validate_config_key(key)?;
+    let value = normalize_value(key, value, value_type)?;
+    let mut config = writable_level_config(repo, parse_level(level)?)?;
+    // A pattern no existing value matches appends a new one
+    config.set_multivar(key, "$^", &value)?;
+    Ok(())
+}
+
+// Removes `key` from `level`: only the entries with `value` when given,
+// otherwise all of them, like `git config --unset-all`
+pub fn unset_config_value(
+    repo: &Repository,
+    level: &str,
+    key: &str,
+    value: Option<&str>,
+) -> Result<(), GitClientError> {
+    validate_config_key(key)?;
+    let mut config = writable_level_config(repo, parse_level(level)?)?;
+    let pattern = match value {
+        Some(value) => format!("^{}$", escape_regex(value)),
+        None => ".*".to_string(),
+    };
+    match config.remove_multivar(key, &pattern) {
+        Ok(()) => Ok(()),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            Err(GitClientError::Operation(match value {
+                Some(value) => format!("{} has no value {:?} in the {} config", key, value, level),
+                None => format!("{} is not set in the {} config", key, level),
+            }))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+// Include paths are relative to the file they are written in
+fn resolve_include_path(path: &str, file: &Path) -> PathBuf {
+    if let (Some(rest), Some(home)) = (path.strip_prefix("~/"), dirs::home_dir()) {
+        return home.join(rest);
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        file.parent().unwrap_or(Path::new("")).join(path)
+    }
+}
+
+fn gitdir_matches(pattern: &str, file: &Path, repo: &Repository, ignore_case: bool) -> bool {
+    let mut pattern =
+        if let (Some(rest), Some(home)) = (pattern.strip_prefix("~/"), dirs::home_dir()) {
+            format!("{}/{}", home.display(), rest)
+        } else if let Some(rest) = pattern.strip_prefix("./") {
+            let dir = file.parent().unwrap_or(Path::new(""));
+            format!("{}/{}", dir.display(), rest)
+        } else {
+            pattern.to_string()
+        };
+    if !pattern.starts_with('/') {
+        pattern = format!("**/{}", pattern);
+    }
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+    let git_dir = repo
+        .path()
+        .to_string_lossy()
+        .trim_end_matches('/')
+        .to_string();
+    let real_dir = std::fs::canonicalize(repo.path())
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| git_dir.clone());
+    [git_dir, real_dir].iter().any(|dir| {
+        if ignore_case {
+            wildmatch(
+                pattern.to_lowercase().as_bytes(),
+                dir.to_lowercase().as_bytes(),
+            )
+        } else {
+            wildmatch(pattern.as_bytes(), dir.as_bytes())
+        }
+    })
+}
+
+// Whether an includeIf condition holds for the repository, as git decides
+// it; unknown conditions never do
+fn condition_holds(condition: &str, file: &Path, repo: &Repository) -> bool {
+    if let Some(pattern) = condition.strip_prefix("gitdir:") {
+        return gitdir_matches(pattern, file, repo, false);
+    }
+    if let Some(pattern) = condition.strip_prefix("gitdir/i:") {
+        return gitdir_matches(pattern, file, repo, true);
+    }
+    if let Some(pattern) = condition.strip_prefix("onbranch:") {
+        let Some(branch) = repo
+            .head()
+            .ok()
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(|s| s.to_string()))
+        else {
+            return false;
+        };
+        let mut pattern = pattern.to_string();
+        if pattern.ends_with('/') {
+            pattern.push_str("**");
+        }
+        return wildmatch(pattern.as_bytes(), branch.as_bytes());
+    }
+    if let Some(pattern) = condition.strip_prefix("hasconfig:remote.*.url:") {
+        let Ok(config) = repo.config() else {
+            return false;
+        };
+        let Ok(mut urls) = config.entries(Some(r"^remote\..*\.url$")) else {
+            return false;
+        };
+        while let Some(Ok(entry)) = urls.next() {
+            if entry
+                .value()
+                .is_some_and(|url| wildmatch(pattern.as_bytes(), url.as_bytes()))
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// Collects the includes written in `file` and, for the ones that apply,
+// what the included files set and include in turn
+fn collect_includes(
+    repo: &Repository,
+    level: ConfigLevel,
+    file: &Path,
+    depth: usize,
+    includes: &mut Vec<ConfigInclude>,
+) -> Result<(), GitClientError> {
+    if depth > MAX_INCLUDE_DEPTH || !file.exists() {
+        return Ok(());
+    }
+    let config = Config::open(file)?;
+    let mut found = Vec::new();
+    let mut iter = config.entries(Some(r"^include(if\..*)?\.path$"))?;
+    while let Some(entry) = iter.next() {
+        let entry = entry?;
+        let (Some(name), Some(path)) = (entry.name(), entry.value()) else {
+            continue;
+        };
+        if entry.include_depth() > 0 {
+            continue;
+        }
+        // Section names come lowercased
+        let condition = name
+            .strip_prefix("includeif.")
+            .and_then(|rest| rest.strip_suffix(".path"))
+            .map(|c| c.to_string());
+        found.push((condition, path.to_string()));
+    }
+
+    for (condition, path) in found {
+        let resolved = resolve_include_path(&path, file);
+        let active = condition
+            .as_deref()
+            .is_none_or(|c| condition_holds(c, file, repo));
+        let entries = if active && resolved.exists() {
+            let included = Config::open(&resolved)?;
+            let mut entries = Vec::new();
+            let mut iter = included.entries(None)?;
+            while let Some(entry) = iter.next() {
+                let entry = entry?;
+                if entry.include_depth() == 0 {
+                    entries.push(ConfigEntryInfo {
+                        name: entry.name().unwrap_or_default().to_string(),
+                        value: entry.value().map(|v| v.to_string()),
+                        level: level_name(level).to_string(),
+                        file: Some(resolved.display().to_string()),
+                        include_depth: depth as u32 + 1,
+                    });
+                }
+            }
+            entries
+        } else {
+            Vec::new()
+        };
+        includes.push(ConfigInclude {
+            level: level_name(level).to_string(),
+            file: file.display().to_string(),
+            condition,
+            path,
+            resolved_path: resolved.display().to_string(),
+            exists: resolved.exists(),
+            active,
+            entries,
+        });
+        if active {
+            collect_includes(repo, level, &resolved, depth + 1, includes)?;
+        }
+    }
+    Ok(())
+}
+
+// Every include and includeIf in the config files git reads for the
+// repository, whether each applies, and what the applying ones set
+pub fn list_config_includes(repo: &Repository) -> Result<Vec<ConfigInclude>, GitClientError> {
+    let mut includes = Vec::new();
+    let levels = [
+        ConfigLevel::System,
+        ConfigLevel::XDG,
+        ConfigLevel::Global,
+        ConfigLevel::Local,
+        ConfigLevel::Worktree,
+    ];
+    for level in levels {
+        if let Some(file) = level_file(repo, level) {
+            collect_includes(repo, level, &file, 0, &mut includes)?;
+        }
+    }
+    Ok(includes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_keys() {
+        for key in [
+            "core.bare",
+            "remote.origin.url",
+            "http.https://example.com/path.sslVerify",
+            "includeIf.gitdir:~/work/.path",
+            "my-section.my-name2",
+        ] {
+            assert!(validate_config_key(key).is_ok(), "{}", key);
+        }
+        for key in [
+            "nosection",
+            ".name",
+            "section.",
+            "sec_tion.name",
+            "core.1name",
+            "core.-name",
+            "core.na_me",
+            "remote.origin.",
+            "branch.a\nb.remote",
+        ] {
+            assert!(validate_config_key(key).is_err(), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn parses_integers_with_suffixes() {
+        assert_eq!(parse_config_int("42"), Some(42));
+        assert_eq!(parse_config_int(" -3 "), Some(-3));
+        assert_eq!(parse_config_int("2k"), Some(2048));
+        assert_eq!(parse_config_int("2K"), Some(2048));
+        assert_eq!(parse_config_int("3m"), Some(3 * 1024 * 1024));
+        assert_eq!(parse_config_int("1g"), Some(1024 * 1024 * 1024));
+        assert_eq!(parse_config_int("9223372036854775807"), Some(i64::MAX));
+        assert_eq!(parse_config_int("9223372036854775807k"), None);
+        assert_eq!(parse_config_int("9223372036854775808"), None);
+        assert_eq!(parse_config_int("k"), None);
+        assert_eq!(parse_config_int("1t"), None);
+        assert_eq!(parse_config_int(""), None);
+    }
+
+    #[test]
+    fn normalizes_values_by_type() {
+        assert_eq!(normalize_value("core.bare", "yes", None).unwrap(), "true");
+        assert_eq!(normalize_value("core.bare", "Off", None).unwrap(), "false");
+        assert!(normalize_value("core.bare", "maybe", None).is_err());
+        // Known types ignore the subsection
+        assert_eq!(
+            normalize_value("http.https://example.com.sslVerify", "0", None).unwrap(),
+            "false"
+        );
+        assert_eq!(
+            normalize_value("http.postBuffer", "1m", None).unwrap(),
+            "1048576"
+        );
+        assert!(normalize_value("pack.threads", "many", None).is_err());
+        assert!(normalize_value("core.hooksPath", " ", None).is_err());
+        assert_eq!(
+            normalize_value("foo.bar", "7k", Some("int")).unwrap(),
+            "7168"
+        );
+        assert_eq!(
+            normalize_value("foo.bar", "yes", Some("string")).unwrap(),
+            "yes"
+        );
+        assert_eq!(normalize_value("user.name", "yes", None).unwrap(), "yes");
+        assert!(normalize_value("user.name", "a\0b", None).is_err());
+        assert!(normalize_value("foo.bar", "x", Some("color")).is_err());
+    }
+
+    #[test]
+    fn matches_gitdir_conditions() {
+        let dir = std::env::temp_dir().join(format!("forked-config-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = Repository::init(dir.join("work").join("project")).unwrap();
+        let work = dir.canonicalize().unwrap().join("work");
+        let config_file = work.join(".gitconfig");
+        let upper = work.display().to_string().to_uppercase();
+
+        for (condition, expected) in [
+            (format!("gitdir:{}/", work.display()), true),
+            (format!("gitdir:{}/project/.git", work.display()), true),
+            (format!("gitdir:{}/other/", work.display()), false),
+            // Relative patterns match anywhere
+            ("gitdir:project/.git".to_string(), true),
+            ("gitdir:work/".to_string(), true),
+            ("gitdir:elsewhere/".to_string(), false),
+            // Relative to the file the include is written in
+            ("gitdir:./project/".to_string(), true),
+            ("gitdir:./other/".to_string(), false),
+            (format!("gitdir:{}/", upper), false),
+            (format!("gitdir/i:{}/", upper), true),
+            ("gitdir/i:PROJECT/.GIT".to_string(), true),
+            ("unknown:whatever".to_string(), false),
+        ] {
+            assert_eq!(
+                condition_holds(&condition, &config_file, &repo),
+                expected,
+                "{}",
+                condition
+            );
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
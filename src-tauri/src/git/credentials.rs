@@ -1,58 +1,461 @@
-use git2::{Cred, FetchOptions, PushOptions, RemoteCallbacks};
-use std::cell::Cell;
-use std::path::PathBuf;
+use crate::git::known_hosts::{verify_host_key, HostKeyInfo};
+use crate::git::lfs::{push_lfs_objects, refresh_lfs_index, smudge_lfs_files};
+use crate::git::sparse::{reapply_sparse_checkout, sparse_aware_statuses};
+use crate::git::ssh_config::{host_config_for_url, open_with_ssh_config};
+use crate::git::system_git::{fetch_with_git, push_with_git};
+use crate::git::tls::{check_certificate, url_proxy, with_tls_settings, CertificateInfo};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use git2::{
+    AutotagOption, CertificateCheckStatus, Config, Cred, FetchOptions, FetchPrune, Oid,
+    ProxyOptions, PushOptions, RemoteCallbacks,
+};
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::rc::Rc;
 
-pub fn get_callbacks<'a>() -> RemoteCallbacks<'a> {
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct TransferProgress {
+    // "receiving", "resolving", "pushing" or "bisecting"
+    pub stage: String,
+    pub current: usize,
+    pub total: usize,
+    pub bytes: usize,
+    // Latest line of progress text sent by the remote
+    pub message: Option<String>,
+}
+
+// Receives updates from the callbacks of a network operation
+pub trait RemoteObserver {
+    fn progress(&self, progress: &TransferProgress);
+
+    // Checked from the transfer callbacks; returning true aborts the operation
+    fn is_cancelled(&self) -> bool {
+        false
+    }
+
+    // Asks the user for credentials the remote wants. None declines, and is
+    // all there is when nobody can be asked, e.g. for background fetches.
+    fn request_credential(&self, _request: &CredentialRequest) -> Option<CredentialResponse> {
+        None
+    }
+}
+
+// The sideband channel carries the remote's terminal output, with `\r`
+// separating updates of the same line.
+fn last_sideband_line(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data)
+        .split(['\r', '\n'])
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(|line| line.to_string())
+}
+
+// Splits captured sideband output into the lines a terminal would end up
+// showing, dropping the intermediate states of progress lines
+fn sideband_lines(data: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(data)
+        .split('\n')
+        .filter_map(|line| line.split('\r').map(str::trim).rfind(|s| !s.is_empty()))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn observe_progress<'a>(
+    callbacks: &mut RemoteCallbacks<'a>,
+    observer: &'a dyn RemoteObserver,
+    sideband: Option<&'a RefCell<Vec<u8>>>,
+) {
+    let state = Rc::new(RefCell::new(TransferProgress::default()));
+
+    let transfer_state = state.clone();
+    callbacks.transfer_progress(move |stats| {
+        let mut progress = transfer_state.borrow_mut();
+        if stats.received_objects() < stats.total_objects() {
+            progress.stage = "receiving".to_string();
+            progress.current = stats.received_objects();
+            progress.total = stats.total_objects();
+        } else {
+            progress.stage = "resolving".to_string();
+            progress.current = stats.indexed_deltas();
+            progress.total = stats.total_deltas();
+        }
+        progress.bytes = stats.received_bytes();
+        observer.progress(&progress);
+        !observer.is_cancelled()
+    });
+
+    let sideband_state = state.clone();
+    callbacks.sideband_progress(move |data| {
+        if let Some(log) = sideband {
+            log.borrow_mut().extend_from_slice(data);
+        }
+        if let Some(line) = last_sideband_line(data) {
+            let mut progress = sideband_state.borrow_mut();
+            progress.message = Some(line);
+            observer.progress(&progress);
+        }
+        !observer.is_cancelled()
+    });
+
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        let mut progress = state.borrow_mut();
+        progress.stage = "pushing".to_string();
+        progress.current = current;
+        progress.total = total;
+        progress.bytes = bytes;
+        observer.progress(&progress);
+    });
+}
+
+// Prompts for credentials count too, so a user who keeps entering wrong
+// ones eventually gets an error instead of endless prompts
+const MAX_CREDENTIAL_ATTEMPTS: u32 = 10;
+
+// Key files tried after the SSH agent, in order, unless the ssh config
+// names identity files for the host
+const DEFAULT_SSH_KEYS: [&str; 3] = ["id_ed25519", "id_rsa", "id_ecdsa"];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CredentialRequest {
+    // "userpass", "ssh_passphrase", "ssh_key", "host_key" or "certificate"
+    pub kind: String,
+    pub url: String,
+    pub username: Option<String>,
+    // The key a passphrase is needed for
+    pub key_path: Option<String>,
+    // Private keys found in ~/.ssh, to choose from for "ssh_key"
+    pub available_keys: Vec<String>,
+    // The previous answer was rejected by the remote
+    pub retry: bool,
+    // The server's key, for "host_key"
+    pub host_key: Option<HostKeyInfo>,
+    // The server's TLS certificate, for "certificate"
+    pub certificate: Option<CertificateInfo>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CredentialResponse {
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub key_path: Option<String>,
+    pub passphrase: Option<String>,
+    // Accept the host key and add it to known_hosts
+    pub trust_host_key: bool,
+    // Accept the TLS certificate for the rest of the session
+    pub trust_certificate: bool,
+}
+
+#[derive(Clone)]
+struct UserPass {
+    url: String,
+    username: String,
+    password: String,
+}
+
+// What the credential callback has handed out during one operation
+#[derive(Default)]
+struct CredentialAttempts {
+    count: u32,
+    // Keys not offered yet, listed on the first SSH request
+    ssh_keys: Option<Vec<PathBuf>>,
+    // The passphrase-protected key offered last
+    encrypted_key: Option<PathBuf>,
+    filled: bool,
+    // The username and password offered last, until the remote asks again
+    userpass: Option<UserPass>,
+}
+
+thread_local! {
+    // The username and password handed out last by a credential callback on
+    // this thread, until the operation using them finishes
+    static OFFERED: RefCell<Option<UserPass>> = const { RefCell::new(None) };
+}
+
+// Forgets credentials a previous operation on this thread left behind
+pub(crate) fn clear_offered_credential() {
+    OFFERED.take();
+}
+
+// Like git, lets the credential helpers store the username and password an
+// operation was given only once it has succeeded. A failed operation, even
+// one failing after authenticating, e.g. with a 403, a missing repository or
+// a dropped connection, may have failed because of them.
+pub(crate) fn settle_offered_credential<T>(result: &Result<T, git2::Error>) {
+    if let (Ok(_), Some(accepted)) = (result, OFFERED.take()) {
+        store_credential("approve", &accepted);
+    }
+}
+
+fn run_git_credential(action: &str, input: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["credential", action])
+        // Never fall back to prompting on a terminal nobody is watching
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            if let Some(ref mut stdin) = child.stdin {
+                let _ = stdin.write_all(input.as_bytes());
+            }
+            child.wait_with_output()
+        })
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn credential_fill(url: &str, username: Option<&str>) -> Option<UserPass> {
+    let mut input = format!("url={}\n", url);
+    if let Some(username) = username {
+        input.push_str(&format!("username={}\n", username));
+    }
+    input.push('\n');
+    let stdout = run_git_credential("fill", &input)?;
+    let mut cred_username = String::new();
+    let mut cred_password = String::new();
+    for line in stdout.lines() {
+        if let Some(val) = line.strip_prefix("username=") {
+            cred_username = val.to_string();
+        } else if let Some(val) = line.strip_prefix("password=") {
+            cred_password = val.to_string();
+        }
+    }
+    (!cred_username.is_empty() && !cred_password.is_empty()).then(|| UserPass {
+        url: url.to_string(),
+        username: cred_username,
+        password: cred_password,
+    })
+}
+
+// `git credential approve` or `reject`
+fn store_credential(action: &str, cred: &UserPass) {
+    let input = format!(
+        "url={}\nusername={}\npassword={}\n\n",
+        cred.url, cred.username, cred.password
+    );
+    run_git_credential(action, &input);
+}
+
+fn ssh_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("~"))
+        .join(".ssh")
+}
+
+fn default_ssh_keys() -> Vec<PathBuf> {
+    let ssh_dir = ssh_dir();
+    DEFAULT_SSH_KEYS
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .filter(|key| key.exists())
+        .collect()
+}
+
+// Private keys in ~/.ssh, recognised by the public key next to them
+fn list_ssh_keys() -> Vec<PathBuf> {
+    let mut keys: Vec<PathBuf> = std::fs::read_dir(ssh_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pub"))
+        .map(|path| path.with_extension(""))
+        .filter(|key| key.is_file())
+        .collect();
+    keys.sort();
+    keys
+}
+
+fn public_key_path(key: &Path) -> PathBuf {
+    key.with_file_name(format!(
+        "{}.pub",
+        key.file_name().unwrap_or_default().to_string_lossy()
+    ))
+}
+
+fn ssh_key_cred(user: &str, key: &Path, passphrase: Option<&str>) -> Result<Cred, git2::Error> {
+    let pubkey = public_key_path(key);
+    let pubkey_ref = if pubkey.exists() {
+        Some(pubkey.as_path())
+    } else {
+        None
+    };
+    Cred::ssh_key(user, pubkey_ref, key, passphrase)
+}
+
+// PEM keys mark encryption in a header; OpenSSH keys name their cipher
+// right after the magic bytes
+fn key_is_encrypted(key: &Path) -> bool {
+    let Ok(contents) = std::fs::read_to_string(key) else {
+        return false;
+    };
+    if contents.contains("ENCRYPTED") {
+        return true;
+    }
+    let body: String = contents
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .collect();
+    let Ok(data) = STANDARD.decode(body) else {
+        return false;
+    };
+    let Some(rest) = data.strip_prefix(b"openssh-key-v1\0".as_slice()) else {
+        return false;
+    };
+    let Some(len) = rest.get(..4) else {
+        return false;
+    };
+    let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    rest.get(4..4 + len).is_some_and(|cipher| cipher != b"none")
+}
+
+pub fn get_callbacks<'a>(observer: Option<&'a dyn RemoteObserver>) -> RemoteCallbacks<'a> {
+    callbacks_with_sideband(observer, None)
+}
+
+// Like `get_callbacks`, additionally appending the remote's sideband output
+// to `sideband`
+fn callbacks_with_sideband<'a>(
+    observer: Option<&'a dyn RemoteObserver>,
+    sideband: Option<&'a RefCell<Vec<u8>>>,
+) -> RemoteCallbacks<'a> {
     let mut callbacks = RemoteCallbacks::new();
 
-    let attempt = Cell::new(0u32);
+    match (observer, sideband) {
+        (Some(observer), _) => observe_progress(&mut callbacks, observer, sideband),
+        (None, Some(log)) => {
+            callbacks.sideband_progress(move |data| {
+                log.borrow_mut().extend_from_slice(data);
+                true
+            });
+        }
+        (None, None) => {}
+    }
+
+    let cancelled = move || observer.is_some_and(|o| o.is_cancelled());
+    let ask =
+        move |request: CredentialRequest| observer.and_then(|o| o.request_credential(&request));
+
+    let mut attempts = CredentialAttempts::default();
 
     callbacks.credentials(move |url, username, allowed| {
-        let tries = attempt.get();
-        if tries >= 5 {
+        if cancelled() {
+            return Err(git2::Error::from_str("cancelled"));
+        }
+        let ssh_host = host_config_for_url(url);
+
+        // libgit2 asks for the SSH user first when the URL has none
+        if allowed.contains(git2::CredentialType::USERNAME) {
+            let user = ssh_host.as_ref().and_then(|h| h.user.as_deref());
+            return Cred::username(user.unwrap_or("git"));
+        }
+
+        let tries = attempts.count;
+        if tries >= MAX_CREDENTIAL_ATTEMPTS {
             return Err(git2::Error::from_str(
                 "too many credential attempts — no valid credentials found",
             ));
         }
-        attempt.set(tries + 1);
+        attempts.count += 1;
 
         let user = username.unwrap_or("git");
 
         if allowed.contains(git2::CredentialType::SSH_KEY) {
-            // Try SSH agent first (attempt 0)
-            if tries == 0 {
+            let ssh_host = ssh_host.unwrap_or_default();
+
+            // Try SSH agent first (attempt 0). With IdentitiesOnly, only
+            // when a configured identity is held by the agent alone, with
+            // just its public key on disk. libgit2 can't narrow the agent
+            // down to that identity, so every agent key is offered then.
+            let agent_identity = ssh_host
+                .identity_files
+                .iter()
+                .any(|key| !key.exists() && public_key_path(key).exists());
+            if tries == 0 && (!ssh_host.identities_only || agent_identity) {
                 if let Ok(cred) = Cred::ssh_key_from_agent(user) {
                     return Ok(cred);
                 }
             }
 
-            // Try common SSH key files from disk
-            let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("~"));
-            let ssh_dir = home.join(".ssh");
-            let key_names = ["id_ed25519", "id_rsa", "id_ecdsa"];
-
-            // Map attempts 1..=3 to key files (attempt 0 was ssh-agent)
-            let key_index = if tries == 0 { 0 } else { tries as usize - 1 };
-            if key_index < key_names.len() {
-                let key_name = key_names[key_index];
-                let privkey = ssh_dir.join(key_name);
-                let pubkey = ssh_dir.join(format!("{}.pub", key_name));
-
-                if privkey.exists() {
-                    let pubkey_ref = if pubkey.exists() {
-                        Some(pubkey.as_path())
-                    } else {
-                        None
-                    };
-                    if let Ok(cred) = Cred::ssh_key(user, pubkey_ref, &privkey, None) {
-                        return Ok(cred);
-                    }
+            // Asked again after a passphrase was given: it was wrong, or the
+            // remote doesn't accept that key
+            let retry = attempts.encrypted_key.take();
+            let keys = attempts.ssh_keys.get_or_insert_with(|| {
+                if ssh_host.identity_files.is_empty() {
+                    default_ssh_keys()
+                } else {
+                    ssh_host
+                        .identity_files
+                        .iter()
+                        .filter(|key| key.exists())
+                        .cloned()
+                        .collect()
                 }
+            });
+            if let Some(key) = &retry {
+                keys.insert(0, key.clone());
+            }
+            while !keys.is_empty() {
+                let key = keys.remove(0);
+                if !key_is_encrypted(&key) {
+                    return ssh_key_cred(user, &key, None);
+                }
+                let response = ask(CredentialRequest {
+                    kind: "ssh_passphrase".to_string(),
+                    url: url.to_string(),
+                    username: Some(user.to_string()),
+                    key_path: Some(key.display().to_string()),
+                    available_keys: Vec::new(),
+                    retry: retry.as_ref() == Some(&key),
+                    host_key: None,
+                    certificate: None,
+                });
+                if let Some(passphrase) = response.and_then(|r| r.passphrase) {
+                    attempts.encrypted_key = Some(key.clone());
+                    return ssh_key_cred(user, &key, Some(&passphrase));
+                }
+            }
+
+            // None of the default keys worked; let the user pick one
+            let response = ask(CredentialRequest {
+                kind: "ssh_key".to_string(),
+                url: url.to_string(),
+                username: Some(user.to_string()),
+                key_path: None,
+                available_keys: list_ssh_keys()
+                    .iter()
+                    .map(|k| k.display().to_string())
+                    .collect(),
+                retry: tries > 0,
+                host_key: None,
+                certificate: None,
+            });
+            if let Some(key) = response.as_ref().and_then(|r| r.key_path.as_deref()) {
+                let key = PathBuf::from(key);
+                let passphrase = response.as_ref().and_then(|r| r.passphrase.as_deref());
+                if passphrase.is_some() {
+                    attempts.encrypted_key = Some(key.clone());
+                }
+                return ssh_key_cred(user, &key, passphrase);
             }
 
             Err(git2::Error::from_str("no SSH credentials found"))
         } else if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            // Being asked again means the last username and password failed
+            let rejected = attempts.userpass.take();
+            if let Some(rejected) = &rejected {
+                OFFERED.take();
+                store_credential("reject", rejected);
+            }
+
             // Try git2's built-in credential helper first
             if tries == 0 {
                 if let Ok(config) = git2::Config::open_default() {
@@ -64,36 +467,43 @@ pub fn get_callbacks<'a>() -> RemoteCallbacks<'a> {
 
             // Fallback: shell out to `git credential fill` which properly
             // handles system credential helpers (osxkeychain, manager-core, etc.)
-            let input = format!("url={}\n\n", url);
-            if let Ok(output) = Command::new("git")
-                .args(["credential", "fill"])
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::null())
-                .spawn()
-                .and_then(|mut child| {
-                    use std::io::Write;
-                    if let Some(ref mut stdin) = child.stdin {
-                        let _ = stdin.write_all(input.as_bytes());
-                    }
-                    child.wait_with_output()
-                })
-            {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let mut cred_username = String::new();
-                let mut cred_password = String::new();
-                for line in stdout.lines() {
-                    if let Some(val) = line.strip_prefix("username=") {
-                        cred_username = val.to_string();
-                    } else if let Some(val) = line.strip_prefix("password=") {
-                        cred_password = val.to_string();
-                    }
-                }
-                if !cred_username.is_empty() && !cred_password.is_empty() {
-                    return Cred::userpass_plaintext(&cred_username, &cred_password);
+            if !attempts.filled {
+                attempts.filled = true;
+                if let Some(filled) = credential_fill(url, username) {
+                    let cred = Cred::userpass_plaintext(&filled.username, &filled.password);
+                    OFFERED.set(Some(filled.clone()));
+                    attempts.userpass = Some(filled);
+                    return cred;
                 }
             }
 
+            let response = ask(CredentialRequest {
+                kind: "userpass".to_string(),
+                url: url.to_string(),
+                username: username.map(|u| u.to_string()),
+                key_path: None,
+                available_keys: Vec::new(),
+                retry: rejected.is_some() || tries > 0,
+                host_key: None,
+                certificate: None,
+            });
+            if let Some(CredentialResponse {
+                username: Some(username),
+                password: Some(password),
+                ..
+            }) = response
+            {
+                let cred = Cred::userpass_plaintext(&username, &password);
+                let offered = UserPass {
+                    url: url.to_string(),
+                    username,
+                    password,
+                };
+                OFFERED.set(Some(offered.clone()));
+                attempts.userpass = Some(offered);
+                return cred;
+            }
+
             Err(git2::Error::from_str(
                 "no credentials found — configure a Git credential helper or use SSH",
             ))
@@ -104,12 +514,37 @@ pub fn get_callbacks<'a>() -> RemoteCallbacks<'a> {
         }
     });
 
-    callbacks.push_update_reference(|refname, status| {
-        if let Some(msg) = status {
-            return Err(git2::Error::from_str(&format!(
-                "Failed to push {}: {}",
-                refname, msg
-            )));
+    // SSH host keys are checked against known_hosts here; libgit2 would
+    // only reject unknown ones without saying why. TLS certificates are
+    // verified by libgit2, unless http.sslVerify is off or the user trusted
+    // them; see `with_tls_settings`.
+    callbacks.certificate_check(move |cert, hostname| {
+        if let Some(x509) = cert.as_x509() {
+            return Ok(check_certificate(x509, hostname));
+        }
+        let Some(hostkey) = cert.as_hostkey() else {
+            return Ok(CertificateCheckStatus::CertificatePassthrough);
+        };
+        verify_host_key(hostkey, hostname, |info| {
+            let response = ask(CredentialRequest {
+                kind: "host_key".to_string(),
+                url: hostname.to_string(),
+                username: None,
+                key_path: None,
+                available_keys: Vec::new(),
+                retry: false,
+                host_key: Some(info),
+                certificate: None,
+            });
+            response.is_some_and(|r| r.trust_host_key)
+        })
+    });
+
+    // libgit2 ignores the result of the push transfer callback, so a push can
+    // only be stopped before the pack is sent
+    callbacks.push_negotiation(move |_updates| {
+        if cancelled() {
+            return Err(git2::Error::from_str("cancelled"));
         }
         Ok(())
     });
@@ -117,58 +552,476 @@ pub fn get_callbacks<'a>() -> RemoteCallbacks<'a> {
     callbacks
 }
 
-pub fn get_fetch_options<'a>() -> FetchOptions<'a> {
+// Proxies for `url` as git finds them: `remote.<name>.proxy`,
+// `http.<url>.proxy`, `http.proxy`, and the http_proxy, https_proxy and
+// no_proxy environment variables
+pub fn proxy_options(config: &Config, url: &str) -> ProxyOptions<'static> {
+    let mut proxy_opts = ProxyOptions::new();
+    match url_proxy(config, url) {
+        Some(proxy) if proxy.is_empty() => {}
+        Some(proxy) => {
+            proxy_opts.url(&proxy);
+        }
+        None => {
+            proxy_opts.auto();
+        }
+    }
+    proxy_opts
+}
+
+pub fn get_fetch_options<'a>(
+    config: &Config,
+    url: &str,
+    observer: Option<&'a dyn RemoteObserver>,
+) -> FetchOptions<'a> {
     let mut fetch_opts = FetchOptions::new();
-    fetch_opts.remote_callbacks(get_callbacks());
+    fetch_opts.remote_callbacks(get_callbacks(observer));
+    fetch_opts.proxy_options(proxy_options(config, url));
     fetch_opts
 }
 
-pub fn get_push_options<'a>() -> PushOptions<'a> {
+pub fn get_push_options<'a>(
+    config: &Config,
+    url: &str,
+    observer: Option<&'a dyn RemoteObserver>,
+) -> PushOptions<'a> {
     let mut push_opts = PushOptions::new();
-    push_opts.remote_callbacks(get_callbacks());
+    push_opts.remote_callbacks(get_callbacks(observer));
+    push_opts.proxy_options(proxy_options(config, url));
     push_opts
 }
 
-pub fn fetch_remote(repo: &git2::Repository, remote_name: &str) -> Result<(), git2::Error> {
-    let mut remote = repo.find_remote(remote_name)?;
-    let refspecs: Vec<String> = remote
-        .fetch_refspecs()?
-        .iter()
-        .filter_map(|s| s.map(|s| s.to_string()))
-        .collect();
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct FetchRemoteOptions {
+    // Fetch every configured remote instead of the named one
+    pub all_remotes: bool,
+    // None follows `remote.<name>.prune` and `fetch.prune`
+    pub prune: Option<bool>,
+    // "all", "auto" or "none"; None follows `remote.<name>.tagOpt`
+    pub tags: Option<String>,
+    // Fetched instead of the remote's configured refspecs
+    pub refspec: Option<String>,
+    // Run the installed git instead of libgit2; set from the settings
+    #[serde(skip)]
+    pub system_git: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RefUpdate {
+    pub remote: String,
+    pub name: String,
+    // "new", "fast_forward", "forced" or "deleted"
+    pub kind: String,
+    pub old_oid: Option<String>,
+    pub new_oid: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct FetchResult {
+    pub updates: Vec<RefUpdate>,
+    // Remotes that failed while fetching all remotes, as "<remote>: <error>"
+    pub errors: Vec<String>,
+}
+
+pub(crate) fn classify_update(
+    repo: &git2::Repository,
+    remote: &str,
+    name: String,
+    old: Oid,
+    new: Oid,
+) -> RefUpdate {
+    let kind = if old.is_zero() {
+        "new"
+    } else if new.is_zero() {
+        "deleted"
+    } else if repo.graph_descendant_of(new, old).unwrap_or(false) {
+        "fast_forward"
+    } else {
+        "forced"
+    };
+    let oid = |id: Oid| (!id.is_zero()).then(|| id.to_string());
+    RefUpdate {
+        remote: remote.to_string(),
+        name,
+        kind: kind.to_string(),
+        old_oid: oid(old),
+        new_oid: oid(new),
+    }
+}
 
+fn fetch_one(
+    repo: &git2::Repository,
+    remote_name: &str,
+    options: &FetchRemoteOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<Vec<RefUpdate>, git2::Error> {
+    if options.system_git {
+        return fetch_with_git(repo, remote_name, options, observer);
+    }
+    let ssh_repo = open_with_ssh_config(repo)?;
+    let mut remote = ssh_repo.as_ref().unwrap_or(repo).find_remote(remote_name)?;
+    let refspecs: Vec<String> = match &options.refspec {
+        Some(refspec) => vec![refspec.clone()],
+        None => remote
+            .fetch_refspecs()?
+            .iter()
+            .filter_map(|s| s.map(|s| s.to_string()))
+            .collect(),
+    };
     let refspec_strs: Vec<&str> = refspecs.iter().map(|s| s.as_str()).collect();
 
-    remote.fetch(&refspec_strs, Some(&mut get_fetch_options()), None)?;
-    Ok(())
+    // Pruned refs are reported with a zero new OID
+    let updated = RefCell::new(Vec::new());
+    let mut callbacks = get_callbacks(observer);
+    callbacks.update_tips(|name, old, new| {
+        updated.borrow_mut().push((name.to_string(), old, new));
+        true
+    });
+
+    let config = repo.config()?;
+    let url = remote.url().unwrap_or_default().to_string();
+    let mut fetch_opts = FetchOptions::new();
+    fetch_opts.remote_callbacks(callbacks);
+    fetch_opts.proxy_options(proxy_options(&config, &url));
+    fetch_opts.prune(match options.prune {
+        Some(true) => FetchPrune::On,
+        Some(false) => FetchPrune::Off,
+        None => FetchPrune::Unspecified,
+    });
+    fetch_opts.download_tags(match options.tags.as_deref() {
+        Some("all") => AutotagOption::All,
+        Some("auto") => AutotagOption::Auto,
+        Some("none") => AutotagOption::None,
+        None => AutotagOption::Unspecified,
+        Some(other) => {
+            return Err(git2::Error::from_str(&format!(
+                "Invalid tag option: {}. Expected 'all', 'auto' or 'none'.",
+                other
+            )))
+        }
+    });
+
+    with_tls_settings(&config, &url, observer, || {
+        remote.fetch(&refspec_strs, Some(&mut fetch_opts), None)
+    })?;
+    drop(fetch_opts);
+
+    Ok(updated
+        .into_inner()
+        .into_iter()
+        .map(|(name, old, new)| classify_update(repo, remote_name, name, old, new))
+        .collect())
+}
+
+pub fn fetch_remote(
+    repo: &git2::Repository,
+    remote_name: &str,
+    options: &FetchRemoteOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<FetchResult, git2::Error> {
+    if !options.all_remotes {
+        return Ok(FetchResult {
+            updates: fetch_one(repo, remote_name, options, observer)?,
+            errors: Vec::new(),
+        });
+    }
+    if options.refspec.is_some() {
+        return Err(git2::Error::from_str(
+            "A refspec can only be fetched from a single remote",
+        ));
+    }
+
+    let mut result = FetchResult::default();
+    let remotes = repo.remotes()?;
+    let names: Vec<&str> = remotes.iter().flatten().collect();
+    for name in &names {
+        match fetch_one(repo, name, options, observer) {
+            Ok(updates) => result.updates.extend(updates),
+            Err(e) if observer.is_some_and(|o| o.is_cancelled()) => return Err(e),
+            Err(e) => result.errors.push(format!("{}: {}", name, e.message())),
+        }
+    }
+    if !names.is_empty() && result.errors.len() == names.len() {
+        return Err(git2::Error::from_str(&result.errors.join("\n")));
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PushRemoteOptions {
+    // Branch to update on the remote; defaults to the local branch's name
+    pub remote_branch: Option<String>,
+    // Overwrite the remote branch only if it still points at `expected_oid`,
+    // or at our remote-tracking branch for it when that isn't given
+    pub force_with_lease: bool,
+    pub expected_oid: Option<String>,
+    // Unconditional force push; refused unless `confirm_force` is also set
+    pub force: bool,
+    pub confirm_force: bool,
+    // Push all local tags as well
+    pub tags: bool,
+    // Delete the remote branch instead of pushing to it
+    pub delete: bool,
+    // Strings for server-side hooks, like `git push --push-option`
+    pub push_options: Vec<String>,
+    // Run the installed git instead of libgit2; set from the settings
+    #[serde(skip)]
+    pub system_git: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PushRefStatus {
+    pub refname: String,
+    // "ok", "rejected_non_fast_forward", "rejected_stale", "rejected_hook"
+    // or "rejected"
+    pub status: String,
+    // The reason given for a rejection
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct PushResult {
+    pub updates: Vec<PushRefStatus>,
+    // Text the remote printed during the push, e.g. hook output or links
+    // for opening a pull request
+    pub remote_messages: Vec<String>,
+}
+
+// Maps the reason reported by receive-pack to a rejection kind
+pub(crate) fn classify_rejection(message: &str) -> &'static str {
+    if message.contains("non-fast-forward") || message.contains("fetch first") {
+        "rejected_non_fast_forward"
+    } else if message.contains("stale info") {
+        "rejected_stale"
+    } else if message.contains("hook declined") {
+        "rejected_hook"
+    } else {
+        "rejected"
+    }
+}
+
+pub(crate) fn rejection(refname: &str, status: &str, message: String) -> PushRefStatus {
+    PushRefStatus {
+        refname: refname.to_string(),
+        status: status.to_string(),
+        message: Some(message),
+    }
+}
+
+// The OID a force-with-lease push expects the remote branch to have; zero
+// when we have never seen the branch, so the push may only create it
+pub(crate) fn lease_oid(
+    repo: &git2::Repository,
+    remote_name: &str,
+    remote_branch: &str,
+    options: &PushRemoteOptions,
+) -> Result<Oid, git2::Error> {
+    if let Some(expected) = &options.expected_oid {
+        return Oid::from_str(expected);
+    }
+    let tracking = format!("refs/remotes/{}/{}", remote_name, remote_branch);
+    match repo.refname_to_id(&tracking) {
+        Ok(oid) => Ok(oid),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(Oid::zero()),
+        Err(e) => Err(e),
+    }
 }
 
 pub fn push_remote(
     repo: &git2::Repository,
     remote_name: &str,
     branch_name: &str,
-) -> Result<(), git2::Error> {
-    let mut remote = repo.find_remote(remote_name)?;
-    let refspec = format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name);
+    options: &PushRemoteOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<PushResult, git2::Error> {
+    if options.force && !options.confirm_force {
+        return Err(git2::Error::from_str(
+            "Force pushing can overwrite commits on the remote and needs to be confirmed",
+        ));
+    }
 
-    remote.push(&[&refspec], Some(&mut get_push_options()))?;
+    let result = if options.system_git {
+        push_with_git(repo, remote_name, branch_name, options, observer)?
+    } else {
+        push_with_libgit2(repo, remote_name, branch_name, options, observer)?
+    };
 
-    let mut local_branch = repo.find_branch(branch_name, git2::BranchType::Local)?;
-    if local_branch.upstream().is_err() {
-        let upstream_name = format!("{}/{}", remote_name, branch_name);
-        local_branch.set_upstream(Some(&upstream_name))?;
+    let remote_branch = options.remote_branch.as_deref().unwrap_or(branch_name);
+    let remote_ref = format!("refs/heads/{}", remote_branch);
+    let branch_pushed = result
+        .updates
+        .iter()
+        .any(|u| u.refname == remote_ref && u.status == "ok");
+    if branch_pushed && !options.delete {
+        let mut local_branch = repo.find_branch(branch_name, git2::BranchType::Local)?;
+        if local_branch.upstream().is_err() {
+            let upstream_name = format!("{}/{}", remote_name, remote_branch);
+            local_branch.set_upstream(Some(&upstream_name))?;
+        }
     }
 
-    Ok(())
+    Ok(result)
+}
+
+fn push_with_libgit2(
+    repo: &git2::Repository,
+    remote_name: &str,
+    branch_name: &str,
+    options: &PushRemoteOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<PushResult, git2::Error> {
+    let ssh_repo = open_with_ssh_config(repo)?;
+    let mut remote = ssh_repo.as_ref().unwrap_or(repo).find_remote(remote_name)?;
+    let remote_branch = options.remote_branch.as_deref().unwrap_or(branch_name);
+    let remote_ref = format!("refs/heads/{}", remote_branch);
+
+    let mut refspecs = vec![if options.delete {
+        format!(":{}", remote_ref)
+    } else {
+        let force = options.force || options.force_with_lease;
+        format!(
+            "{}refs/heads/{}:{}",
+            if force { "+" } else { "" },
+            branch_name,
+            remote_ref
+        )
+    }];
+    if options.tags {
+        for tag in repo.tag_names(None)?.iter().flatten() {
+            refspecs.push(format!("refs/tags/{}:refs/tags/{}", tag, tag));
+        }
+    }
+
+    if !options.delete {
+        let pushed_refs: Vec<(String, String)> = refspecs
+            .iter()
+            .filter_map(|refspec| refspec.trim_start_matches('+').split_once(':'))
+            .map(|(local, remote)| (local.to_string(), remote.to_string()))
+            .collect();
+        let original = repo.find_remote(remote_name)?;
+        let lfs_url = original
+            .pushurl()
+            .or_else(|| original.url())
+            .unwrap_or_default();
+        push_lfs_objects(repo, remote_name, lfs_url, &pushed_refs)
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+    }
+
+    let lease = if options.force_with_lease && !options.force {
+        Some(lease_oid(repo, remote_name, remote_branch, options)?)
+    } else {
+        None
+    };
+    let config = repo.config()?;
+    let url = remote
+        .pushurl()
+        .or_else(|| remote.url())
+        .unwrap_or_default()
+        .to_string();
+    // Set when the lease check fails; libgit2 drops callback error messages
+    let stale = Cell::new(None);
+    let sideband = RefCell::new(Vec::new());
+    let statuses = RefCell::new(Vec::new());
+
+    let mut callbacks = callbacks_with_sideband(observer, Some(&sideband));
+    let cancelled = move || observer.is_some_and(|o| o.is_cancelled());
+    callbacks.push_negotiation(|updates| {
+        if cancelled() {
+            return Err(git2::Error::from_str("cancelled"));
+        }
+        // The remote's current value is only known once connected, so the
+        // lease is checked here rather than before pushing
+        if let Some(expected) = lease {
+            let update = updates
+                .iter()
+                .find(|u| u.dst_refname() == Some(remote_ref.as_str()));
+            if let Some(update) = update.filter(|u| u.src() != expected) {
+                stale.set(Some(update.src()));
+                return Err(git2::Error::from_str("stale info"));
+            }
+        }
+        Ok(())
+    });
+    // Rejections are reported in the result rather than failing the push, so
+    // the refs that did update and the remote's explanation aren't lost
+    callbacks.push_update_reference(|refname, status| {
+        statuses.borrow_mut().push(match status {
+            None => PushRefStatus {
+                refname: refname.to_string(),
+                status: "ok".to_string(),
+                message: None,
+            },
+            Some(msg) => rejection(refname, classify_rejection(msg), msg.to_string()),
+        });
+        Ok(())
+    });
+
+    let mut push_opts = PushOptions::new();
+    push_opts.remote_callbacks(callbacks);
+    push_opts.proxy_options(proxy_options(&config, &url));
+    let hook_options: Vec<&str> = options.push_options.iter().map(|o| o.as_str()).collect();
+    if !hook_options.is_empty() {
+        push_opts.remote_push_options(&hook_options);
+    }
+
+    let refspec_strs: Vec<&str> = refspecs.iter().map(|s| s.as_str()).collect();
+    let pushed = with_tls_settings(&config, &url, observer, || {
+        remote.push(&refspec_strs, Some(&mut push_opts))
+    });
+    drop(push_opts);
+
+    let mut result = PushResult {
+        updates: statuses.into_inner(),
+        remote_messages: sideband_lines(&sideband.into_inner()),
+    };
+    if let (Some(actual), Some(expected)) = (stale.get(), lease) {
+        let describe = |oid: Oid| {
+            if oid.is_zero() {
+                "nothing".to_string()
+            } else {
+                oid.to_string()
+            }
+        };
+        result.updates.push(rejection(
+            &remote_ref,
+            "rejected_stale",
+            format!(
+                "the remote branch points at {} but {} was expected. Fetch and review the remote changes first.",
+                describe(actual),
+                describe(expected)
+            ),
+        ));
+        return Ok(result);
+    }
+    match pushed {
+        Ok(()) => {}
+        // libgit2 refuses non-fast-forward updates itself, before the server
+        // gets to see them
+        Err(e) if e.code() == git2::ErrorCode::NotFastForward => {
+            result.updates.push(rejection(
+                &remote_ref,
+                "rejected_non_fast_forward",
+                "the remote branch has commits that aren't in the local branch. Pull or fetch and integrate them first.".to_string(),
+            ));
+            return Ok(result);
+        }
+        Err(e) => return Err(e),
+    }
+
+    Ok(result)
 }
 
 pub fn pull_remote(
     repo: &git2::Repository,
     remote_name: &str,
     branch_name: &str,
+    fetch_options: &FetchRemoteOptions,
+    observer: Option<&dyn RemoteObserver>,
 ) -> Result<PullResult, git2::Error> {
     // First fetch
-    fetch_remote(repo, remote_name)?;
+    fetch_remote(repo, remote_name, fetch_options, observer)?;
 
     // Get the fetch head
     let fetch_head = repo.find_reference("FETCH_HEAD")?;
@@ -185,12 +1038,28 @@ pub fn pull_remote(
         });
     }
 
+    // Smudged LFS files whose stat data changed would otherwise look modified
+    refresh_lfs_index(repo).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+    // Checked on this handle right before the worktree is updated, so changes
+    // made while fetching are caught too
+    if is_worktree_dirty(repo)? {
+        return Err(git2::Error::from_str(
+            "Worktree has uncommitted changes. Commit, stash, or discard them before pulling.",
+        ));
+    }
+
     if analysis.is_fast_forward() {
         let refname = format!("refs/heads/{}", branch_name);
         let mut reference = repo.find_reference(&refname)?;
         reference.set_target(fetch_commit.id(), "Fast-forward pull")?;
+        // The branch already points at the new commit, so a safe checkout
+        // would see nothing to update; the worktree was checked clean above
         let mut checkout_opts = git2::build::CheckoutBuilder::default();
+        checkout_opts.force();
         repo.checkout_head(Some(&mut checkout_opts))?;
+        reapply_sparse_checkout(repo)
+            .and_then(|()| smudge_lfs_files(repo))
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
 
         return Ok(PullResult {
             success: true,
@@ -259,12 +1128,11 @@ pub fn pull_remote(
 pub fn is_worktree_dirty(repo: &git2::Repository) -> Result<bool, git2::Error> {
     let mut status_opts = git2::StatusOptions::new();
     status_opts.include_untracked(true).recurse_untracked_dirs(true);
-    let statuses = repo.statuses(Some(&mut status_opts))?;
-
-    Ok(statuses.iter().any(|entry| !entry.status().is_empty()))
+    // Files left out by a sparse checkout don't count as deleted
+    Ok(!sparse_aware_statuses(repo, &mut status_opts)?.is_empty())
 }
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Clone)]
 pub struct PullResult {
@@ -273,27 +1141,3 @@ pub struct PullResult {
     pub conflicts: Vec<String>,
     pub message: String,
 }
-
-#[derive(Debug, Serialize, Clone)]
-pub struct RemoteInfo {
-    pub name: String,
-    pub url: String,
-    pub push_url: Option<String>,
-}
-
-pub fn list_remotes(repo: &git2::Repository) -> Result<Vec<RemoteInfo>, git2::Error> {
-    let remotes = repo.remotes()?;
-    let mut result = Vec::new();
-
-    for name in remotes.iter().flatten() {
-        if let Ok(remote) = repo.find_remote(name) {
-            result.push(RemoteInfo {
-                name: name.to_string(),
-                url: remote.url().unwrap_or("").to_string(),
-                push_url: remote.pushurl().map(|s| s.to_string()),
-            });
-        }
-    }
-
-    Ok(result)
-}
@@ -1,5 +1,7 @@
 use crate::error::GitClientError;
-use git2::{DiffOptions, Oid, Repository};
+use crate::git::lfs::{lfs_change, LfsDiff};
+use crate::git::submodule::{diff_submodule, SubmoduleDiff};
+use git2::{DiffDelta, DiffOptions, FileMode, Oid, Repository};
 use serde::Serialize;
 use std::cell::RefCell;
 
@@ -10,6 +12,9 @@ pub struct FileDiff {
     pub status: String,
     pub hunks: Vec<DiffHunk>,
     pub is_binary: bool,
+    pub submodule: Option<SubmoduleDiff>,
+    // Pointer details for LFS content; hunks are omitted in that case
+    pub lfs: Option<LfsDiff>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -44,6 +49,24 @@ pub struct DiffStats {
     pub deletions: usize,
 }
 
+fn submodule_change(repo: &Repository, delta: &DiffDelta) -> Option<SubmoduleDiff> {
+    let is_submodule = |mode: FileMode| mode == FileMode::Commit;
+    if !is_submodule(delta.old_file().mode()) && !is_submodule(delta.new_file().mode()) {
+        return None;
+    }
+    let path = delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())?;
+    let commit_id = |id: Oid| if id.is_zero() { None } else { Some(id) };
+    Some(diff_submodule(
+        repo,
+        &path.to_string_lossy(),
+        commit_id(delta.old_file().id()),
+        commit_id(delta.new_file().id()),
+    ))
+}
+
 pub fn get_file_diff(
     repo: &Repository,
     path: &str,
@@ -68,6 +91,8 @@ pub fn get_file_diff(
         status: "unknown".to_string(),
         hunks: Vec::new(),
         is_binary: false,
+        submodule: None,
+        lfs: None,
     });
 
     let current_hunk: RefCell<Option<DiffHunk>> = RefCell::new(None);
@@ -88,6 +113,8 @@ pub fn get_file_diff(
                 .path()
                 .map(|p| p.to_string_lossy().to_string());
             fd.is_binary = delta.new_file().is_binary() || delta.old_file().is_binary();
+            fd.submodule = submodule_change(repo, &delta);
+            fd.lfs = lfs_change(repo, &delta, !staged);
             true
         },
         Some(&mut |_delta, _binary| {
@@ -128,7 +155,11 @@ pub fn get_file_diff(
         file_diff.borrow_mut().hunks.push(h);
     }
 
-    Ok(file_diff.into_inner())
+    let mut file_diff = file_diff.into_inner();
+    if file_diff.lfs.is_some() {
+        file_diff.hunks.clear();
+    }
+    Ok(file_diff)
 }
 
 pub fn get_commit_diff(repo: &Repository, oid_str: &str) -> Result<CommitDiff, GitClientError> {
@@ -185,6 +216,8 @@ pub fn get_commit_diff(repo: &Repository, oid_str: &str) -> Result<CommitDiff, G
                 },
                 hunks: Vec::new(),
                 is_binary: delta.new_file().is_binary() || delta.old_file().is_binary(),
+                submodule: submodule_change(repo, &delta),
+                lfs: lfs_change(repo, &delta, false),
             });
             true
         },
@@ -242,7 +275,10 @@ pub fn get_commit_diff(repo: &Repository, oid_str: &str) -> Result<CommitDiff, G
         files.borrow_mut().push(file);
     }
 
-    let final_files = files.into_inner();
+    let mut final_files = files.into_inner();
+    for file in final_files.iter_mut().filter(|f| f.lfs.is_some()) {
+        file.hunks.clear();
+    }
     let files_count = final_files.len();
     let ins = total_insertions.into_inner();
     let del = total_deletions.into_inner();
@@ -1,7 +1,8 @@
 use crate::error::GitClientError;
+use crate::git::split::ensure_no_split_in_progress;
 use git2::build::CheckoutBuilder;
-use git2::{Oid, Repository, Sort, StatusOptions};
-use serde::Serialize;
+use git2::{Oid, Repository, Signature, Sort, StatusOptions, Time};
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 
@@ -235,7 +236,7 @@ pub struct CommitStats {
     pub deletions: usize,
 }
 
-fn collect_tags_by_commit(repo: &Repository) -> HashMap<String, Vec<String>> {
+pub(crate) fn collect_tags_by_commit(repo: &Repository) -> HashMap<String, Vec<String>> {
     let mut tags_by_commit: HashMap<String, Vec<String>> = HashMap::new();
 
     if let Ok(tag_names) = repo.tag_names(None) {
@@ -262,7 +263,10 @@ fn collect_tags_by_commit(repo: &Repository) -> HashMap<String, Vec<String>> {
     tags_by_commit
 }
 
-fn commit_to_info(commit: &git2::Commit, tags_by_commit: &HashMap<String, Vec<String>>) -> CommitInfo {
+pub(crate) fn commit_to_info(
+    commit: &git2::Commit,
+    tags_by_commit: &HashMap<String, Vec<String>>,
+) -> CommitInfo {
     let id = commit.id().to_string();
     let short_id = id.chars().take(7).collect();
     let tag_names = tags_by_commit.get(&id).cloned().unwrap_or_default();
@@ -427,61 +431,152 @@ pub fn get_commit_history_with_graph(
     Ok(entries)
 }
 
-pub fn create_commit(repo: &Repository, message: &str) -> Result<CommitInfo, GitClientError> {
+#[derive(Debug, Deserialize, Clone)]
+pub struct CommitIdentity {
+    pub name: String,
+    pub email: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct CommitTime {
+    pub seconds: i64,
+    // Offset from UTC in minutes
+    pub offset_minutes: i32,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CommitOptions {
+    // Overrides the configured user, e.g. when committing someone else's patch
+    pub author: Option<CommitIdentity>,
+    pub author_time: Option<CommitTime>,
+    pub committer_time: Option<CommitTime>,
+    pub allow_empty: bool,
+    // Commit even though the configured user isn't the one the repository's
+    // identity profile asks for
+    pub confirm_identity: bool,
+}
+
+fn build_signature<'a>(
+    name: &str,
+    email: &str,
+    time: Option<CommitTime>,
+) -> Result<Signature<'a>, GitClientError> {
+    let signature = match time {
+        Some(t) => Signature::new(name, email, &Time::new(t.seconds, t.offset_minutes))?,
+        None => Signature::now(name, email)?,
+    };
+    Ok(signature)
+}
+
+fn commit_signatures<'a>(
+    repo: &Repository,
+    options: &CommitOptions,
+) -> Result<(Signature<'a>, Signature<'a>), GitClientError> {
+    let configured = repo.signature()?;
+    let configured_name = configured.name().unwrap_or("").to_string();
+    let configured_email = configured.email().unwrap_or("").to_string();
+
+    let committer = match options.committer_time {
+        Some(_) => build_signature(&configured_name, &configured_email, options.committer_time)?,
+        None => configured.to_owned(),
+    };
+
+    let author = match &options.author {
+        Some(identity) => {
+            if identity.name.trim().is_empty() || identity.email.trim().is_empty() {
+                return Err(GitClientError::Operation(
+                    "Author name and email must not be empty".to_string(),
+                ));
+            }
+            build_signature(
+                identity.name.trim(),
+                identity.email.trim(),
+                options.author_time,
+            )?
+        }
+        None if options.author_time.is_some() => {
+            build_signature(&configured_name, &configured_email, options.author_time)?
+        }
+        None => committer.to_owned(),
+    };
+
+    Ok((author, committer))
+}
+
+pub fn create_commit(
+    repo: &Repository,
+    message: &str,
+    options: &CommitOptions,
+) -> Result<CommitInfo, GitClientError> {
     let mut index = repo.index()?;
+    // Pick up changes staged outside the app (e.g. from a terminal)
+    index.read(false)?;
     let tree_id = index.write_tree()?;
     let tree = repo.find_tree(tree_id)?;
 
-    let signature = repo.signature()?;
-
     let parent_commit = match repo.head() {
         Ok(head) => Some(head.peel_to_commit()?),
         Err(_) => None,
     };
 
+    if !options.allow_empty {
+        let has_staged_changes = match &parent_commit {
+            Some(parent) => parent.tree_id() != tree_id,
+            None => !tree.is_empty(),
+        };
+        if !has_staged_changes {
+            return Err(GitClientError::Operation(
+                "No changes staged for commit".to_string(),
+            ));
+        }
+    }
+
+    let (author, committer) = commit_signatures(repo, options)?;
+
     let parents: Vec<&git2::Commit> = parent_commit.iter().collect();
 
-    let oid = repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        message,
-        &tree,
-        &parents,
-    )?;
+    let oid = repo.commit(Some("HEAD"), &author, &committer, message, &tree, &parents)?;
 
     let commit = repo.find_commit(oid)?;
     let tags_by_commit = collect_tags_by_commit(repo);
     Ok(commit_to_info(&commit, &tags_by_commit))
 }
 
-pub fn squash_commits(
-    repo: &Repository,
-    commit_ids: &[String],
-    message: &str,
-) -> Result<(), GitClientError> {
-    if commit_ids.len() < 2 {
-        return Err(GitClientError::Operation(
-            "Select at least two commits to squash".to_string(),
-        ));
-    }
+pub(crate) struct HistoryRewrite {
+    pub head_name: String,
+    // HEAD first, following first parents back to the root commit
+    pub first_parent_chain: Vec<Oid>,
+}
 
+pub(crate) fn ensure_clean_worktree(repo: &Repository, action: &str) -> Result<(), GitClientError> {
     let mut status_options = StatusOptions::new();
     status_options
         .include_untracked(true)
         .recurse_untracked_dirs(true);
     let statuses = repo.statuses(Some(&mut status_options))?;
     if !statuses.is_empty() {
-        return Err(GitClientError::Operation(
-            "Cannot squash commits with uncommitted changes".to_string(),
-        ));
+        return Err(GitClientError::Operation(format!(
+            "Cannot {} with uncommitted changes",
+            action
+        )));
     }
+    Ok(())
+}
+
+pub(crate) fn prepare_history_rewrite(
+    repo: &Repository,
+    action: &str,
+) -> Result<HistoryRewrite, GitClientError> {
+    ensure_no_split_in_progress(repo, action)?;
+    ensure_clean_worktree(repo, action)?;
 
     let head = repo.head()?;
     if !head.is_branch() {
-        return Err(GitClientError::Operation(
-            "Cannot squash commits in detached HEAD state".to_string(),
-        ));
+        return Err(GitClientError::Operation(format!(
+            "Cannot {} in detached HEAD state",
+            action
+        )));
     }
     let head_name = head
         .name()
@@ -491,18 +586,6 @@ pub fn squash_commits(
         .target()
         .ok_or_else(|| GitClientError::Operation("HEAD has no target commit".to_string()))?;
 
-    let mut selected_oids = Vec::with_capacity(commit_ids.len());
-    let mut selected_set = HashSet::with_capacity(commit_ids.len());
-    for id in commit_ids {
-        let oid = Oid::from_str(id)
-            .map_err(|e| GitClientError::Operation(format!("Invalid commit ID '{}': {}", id, e)))?;
-        if !selected_set.insert(oid) {
-            return Err(GitClientError::Operation(
-                "Duplicate commits in selection".to_string(),
-            ));
-        }
-        selected_oids.push(oid);
-    }
     let mut first_parent_chain: Vec<Oid> = Vec::new();
     let mut cursor = Some(head_oid);
     while let Some(oid) = cursor {
@@ -515,6 +598,68 @@ pub fn squash_commits(
         };
     }
 
+    Ok(HistoryRewrite {
+        head_name,
+        first_parent_chain,
+    })
+}
+
+pub(crate) fn ensure_no_merge_commits(
+    repo: &Repository,
+    oids: &[Oid],
+    action: &str,
+) -> Result<(), GitClientError> {
+    for &oid in oids {
+        let commit = repo.find_commit(oid)?;
+        if commit.parent_count() > 1 {
+            return Err(GitClientError::Operation(format!(
+                "Cannot {} when merge commits exist between selection and HEAD",
+                action
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn update_rewritten_head(
+    repo: &Repository,
+    head_name: &str,
+    new_head: Oid,
+    log_message: &str,
+) -> Result<(), GitClientError> {
+    let mut branch_ref = repo.find_reference(head_name)?;
+    branch_ref.set_target(new_head, log_message)?;
+    repo.set_head(head_name)?;
+    repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+    Ok(())
+}
+
+pub fn squash_commits(
+    repo: &Repository,
+    commit_ids: &[String],
+    message: &str,
+) -> Result<(), GitClientError> {
+    if commit_ids.len() < 2 {
+        return Err(GitClientError::Operation(
+            "Select at least two commits to squash".to_string(),
+        ));
+    }
+
+    let rewrite = prepare_history_rewrite(repo, "squash commits")?;
+    let first_parent_chain = &rewrite.first_parent_chain;
+
+    let mut selected_oids = Vec::with_capacity(commit_ids.len());
+    let mut selected_set = HashSet::with_capacity(commit_ids.len());
+    for id in commit_ids {
+        let oid = Oid::from_str(id)
+            .map_err(|e| GitClientError::Operation(format!("Invalid commit ID '{}': {}", id, e)))?;
+        if !selected_set.insert(oid) {
+            return Err(GitClientError::Operation(
+                "Duplicate commits in selection".to_string(),
+            ));
+        }
+        selected_oids.push(oid);
+    }
     let mut index_by_oid: HashMap<Oid, usize> = HashMap::with_capacity(first_parent_chain.len());
     for (idx, oid) in first_parent_chain.iter().copied().enumerate() {
         index_by_oid.insert(oid, idx);
@@ -546,15 +691,11 @@ pub fn squash_commits(
         .last()
         .ok_or_else(|| GitClientError::Operation("No commits selected".to_string()))?;
 
-    for &oid in &first_parent_chain[0..=oldest_selected_index] {
-        let commit = repo.find_commit(oid)?;
-        if commit.parent_count() > 1 {
-            return Err(GitClientError::Operation(
-                "Cannot squash commits when merge commits exist between selection and HEAD"
-                    .to_string(),
-            ));
-        }
-    }
+    ensure_no_merge_commits(
+        repo,
+        &first_parent_chain[0..=oldest_selected_index],
+        "squash commits",
+    )?;
 
     let oldest_selected = repo.find_commit(first_parent_chain[oldest_selected_index])?;
     let base_parent = if oldest_selected.parent_count() > 0 {
@@ -639,10 +780,235 @@ pub fn squash_commits(
         GitClientError::Operation("Failed to rewrite commits for squash".to_string())
     })?;
 
-    let mut branch_ref = repo.find_reference(&head_name)?;
-    branch_ref.set_target(rewritten_head, "squash commits")?;
-    repo.set_head(&head_name)?;
-    repo.checkout_head(Some(CheckoutBuilder::new().force()))?;
+    update_rewritten_head(repo, &rewrite.head_name, rewritten_head, "squash commits")
+}
 
-    Ok(())
+const AUTOSQUASH_PREFIXES: [&str; 3] = ["fixup! ", "squash! ", "amend! "];
+
+pub fn create_fixup_commit(
+    repo: &Repository,
+    target_id: &str,
+    kind: &str,
+    message: &str,
+) -> Result<CommitInfo, GitClientError> {
+    let oid = Oid::from_str(target_id)
+        .map_err(|e| GitClientError::Operation(format!("Invalid commit ID: {}", e)))?;
+    let target = repo.find_commit(oid)?;
+
+    let head_oid = repo.head()?.peel_to_commit()?.id();
+    if head_oid != oid && !repo.graph_descendant_of(head_oid, oid)? {
+        return Err(GitClientError::Operation(
+            "Target commit must be an ancestor of HEAD".to_string(),
+        ));
+    }
+
+    let summary = target.summary().unwrap_or("").to_string();
+    let body = message.trim();
+    let full_message = match kind {
+        "fixup" => format!("fixup! {}", summary),
+        "squash" if body.is_empty() => format!("squash! {}", summary),
+        "squash" => format!("squash! {}\n\n{}", summary, body),
+        "amend" => {
+            if body.is_empty() {
+                return Err(GitClientError::Operation(
+                    "An amend! commit needs the replacement message".to_string(),
+                ));
+            }
+            format!("amend! {}\n\n{}", summary, body)
+        }
+        _ => {
+            return Err(GitClientError::Operation(format!(
+                "Invalid fixup kind: {}. Expected 'fixup', 'squash' or 'amend'.",
+                kind
+            )))
+        }
+    };
+
+    // amend! may only reword the target, so it does not need staged changes
+    let options = CommitOptions {
+        allow_empty: kind == "amend",
+        ..CommitOptions::default()
+    };
+    create_commit(repo, &full_message, &options)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FoldKind {
+    Fixup,
+    Squash,
+    Amend,
+}
+
+fn parse_fold_subject(summary: &str) -> Option<(FoldKind, &str)> {
+    let kind = if summary.starts_with("fixup! ") {
+        FoldKind::Fixup
+    } else if summary.starts_with("squash! ") {
+        FoldKind::Squash
+    } else if summary.starts_with("amend! ") {
+        FoldKind::Amend
+    } else {
+        return None;
+    };
+
+    // "fixup! fixup! foo" targets "foo"
+    let mut subject = summary;
+    while let Some(rest) = AUTOSQUASH_PREFIXES
+        .iter()
+        .find_map(|prefix| subject.strip_prefix(prefix))
+    {
+        subject = rest;
+    }
+    Some((kind, subject.trim()))
+}
+
+fn message_body(message: &str) -> &str {
+    match message.split_once('\n') {
+        Some((_, rest)) => rest.trim(),
+        None => "",
+    }
+}
+
+// Applies the change `commit` introduced on top of `onto_tree`, like a cherry-pick.
+pub(crate) fn pick_tree(
+    repo: &Repository,
+    commit: &git2::Commit,
+    onto_tree: Oid,
+) -> Result<Oid, GitClientError> {
+    try_pick_tree(repo, commit, onto_tree)?.ok_or_else(|| {
+        GitClientError::Operation(format!(
+            "Conflict while applying {} \"{}\"",
+            &commit.id().to_string()[..7],
+            commit.summary().unwrap_or("")
+        ))
+    })
+}
+
+// Like `pick_tree`, but gives None when the change conflicts
+pub(crate) fn try_pick_tree(
+    repo: &Repository,
+    commit: &git2::Commit,
+    onto_tree: Oid,
+) -> Result<Option<Oid>, GitClientError> {
+    let base_tree = if commit.parent_count() > 0 {
+        commit.parent(0)?.tree()?
+    } else {
+        repo.find_tree(repo.treebuilder(None)?.write()?)?
+    };
+    if base_tree.id() == onto_tree {
+        return Ok(Some(commit.tree_id()));
+    }
+
+    let ours = repo.find_tree(onto_tree)?;
+    let mut index = repo.merge_trees(&base_tree, &ours, &commit.tree()?, None)?;
+    if index.has_conflicts() {
+        return Ok(None);
+    }
+    Ok(Some(index.write_tree_to(repo)?))
+}
+
+pub fn autosquash(repo: &Repository) -> Result<(), GitClientError> {
+    let rewrite = prepare_history_rewrite(repo, "autosquash")?;
+    let chain_old_to_new: Vec<Oid> = rewrite.first_parent_chain.iter().rev().copied().collect();
+
+    // Resolve each fixup!/squash!/amend! commit to the oldest earlier commit it
+    // names, by subject first and then by commit ID prefix, as git does.
+    let mut subjects: HashMap<String, usize> = HashMap::new();
+    let mut root_target: Vec<Option<usize>> = vec![None; chain_old_to_new.len()];
+    let mut folds: HashMap<usize, Vec<(usize, FoldKind)>> = HashMap::new();
+
+    for (idx, oid) in chain_old_to_new.iter().enumerate() {
+        let commit = repo.find_commit(*oid)?;
+        let summary = commit.summary().unwrap_or("").to_string();
+
+        if let Some((kind, subject)) = parse_fold_subject(&summary) {
+            let target = subjects.get(subject).copied().or_else(|| {
+                if subject.len() < 4 {
+                    return None;
+                }
+                chain_old_to_new[..idx]
+                    .iter()
+                    .position(|candidate| candidate.to_string().starts_with(subject))
+            });
+            if let Some(target) = target {
+                let root = root_target[target].unwrap_or(target);
+                root_target[idx] = Some(root);
+                folds.entry(root).or_default().push((idx, kind));
+            }
+        }
+
+        subjects.entry(summary).or_insert(idx);
+    }
+
+    let Some(&base_index) = folds.keys().min() else {
+        return Err(GitClientError::Operation(
+            "No fixup!, squash! or amend! commits to autosquash".to_string(),
+        ));
+    };
+
+    let newest_to_base = chain_old_to_new.len() - 1 - base_index;
+    ensure_no_merge_commits(
+        repo,
+        &rewrite.first_parent_chain[0..=newest_to_base],
+        "autosquash",
+    )?;
+
+    let base = repo.find_commit(chain_old_to_new[base_index])?;
+    let mut current_parent = if base.parent_count() > 0 {
+        Some(base.parent(0)?)
+    } else {
+        None
+    };
+    let signature = repo.signature()?;
+    let mut new_head_oid = None;
+
+    for idx in base_index..chain_old_to_new.len() {
+        if root_target[idx].is_some() {
+            continue;
+        }
+
+        let original = repo.find_commit(chain_old_to_new[idx])?;
+        let onto_tree = match &current_parent {
+            Some(parent) => parent.tree_id(),
+            None => repo.treebuilder(None)?.write()?,
+        };
+        let mut tree_id = pick_tree(repo, &original, onto_tree)?;
+        let mut message = original.message().unwrap_or("").to_string();
+        let folded = folds.get(&idx).map(Vec::as_slice).unwrap_or(&[]);
+
+        for &(fold_idx, kind) in folded {
+            let fold_commit = repo.find_commit(chain_old_to_new[fold_idx])?;
+            tree_id = pick_tree(repo, &fold_commit, tree_id)?;
+            let body = message_body(fold_commit.message().unwrap_or(""));
+            match kind {
+                FoldKind::Fixup => {}
+                FoldKind::Squash if !body.is_empty() => {
+                    message = format!("{}\n\n{}\n", message.trim_end(), body);
+                }
+                FoldKind::Squash => {}
+                FoldKind::Amend if !body.is_empty() => {
+                    message = format!("{}\n", body);
+                }
+                FoldKind::Amend => {}
+            }
+        }
+
+        let tree = repo.find_tree(tree_id)?;
+        let author = original.author();
+        let committer = if folded.is_empty() {
+            original.committer()
+        } else {
+            signature.clone()
+        };
+        let parent_refs: Vec<&git2::Commit> = current_parent.iter().collect();
+        let rewritten_oid =
+            repo.commit(None, &author, &committer, &message, &tree, &parent_refs)?;
+        current_parent = Some(repo.find_commit(rewritten_oid)?);
+        new_head_oid = Some(rewritten_oid);
+    }
+
+    let rewritten_head = new_head_oid.ok_or_else(|| {
+        GitClientError::Operation("Failed to rewrite commits for autosquash".to_string())
+    })?;
+
+    update_rewritten_head(repo, &rewrite.head_name, rewritten_head, "autosquash")
 }
@@ -0,0 +1,176 @@
+use crate::error::GitClientError;
+use crate::git::config::set_config_value;
+use crate::git::history::CommitOptions;
+use crate::git::sparse::wildmatch;
+use crate::git::ssh_config::wildcard_match;
+use crate::settings::{IdentityProfile, IdentityRule, IdentitySettings};
+use git2::Repository;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RepoIdentity {
+    // The profile the first matching rule chooses, if any
+    pub profile: Option<IdentityProfile>,
+    // user.name and user.email as the repository's config resolves them
+    pub user_name: Option<String>,
+    pub user_email: Option<String>,
+    // Whether the configured identity is the profile's; true without a profile
+    pub matches: bool,
+}
+
+fn expand_home(pattern: &str) -> String {
+    match (pattern.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}/{}", home.display(), rest),
+        _ => pattern.to_string(),
+    }
+}
+
+// Directories match the repository's working directory and everything in
+// them; with wildcards, like includeIf's gitdir, a trailing `/` stands for
+// everything below
+fn directory_matches(pattern: &str, repo: &Repository) -> bool {
+    let pattern = expand_home(pattern.trim());
+    let dir = repo.workdir().unwrap_or_else(|| repo.path());
+    let dirs: Vec<PathBuf> = [Some(dir.to_path_buf()), std::fs::canonicalize(dir).ok()]
+        .into_iter()
+        .flatten()
+        .collect();
+    if !pattern.contains(['*', '?', '[']) {
+        return dirs.iter().any(|dir| dir.starts_with(&pattern));
+    }
+    // A trailing `/` matches the directory itself as well
+    let below = pattern.ends_with('/');
+    let pattern = if below {
+        format!("{}**", pattern)
+    } else {
+        pattern
+    };
+    dirs.iter().any(|dir| {
+        let mut dir = dir.to_string_lossy().trim_end_matches('/').to_string();
+        if below {
+            dir.push('/');
+        }
+        wildmatch(pattern.as_bytes(), dir.as_bytes())
+    })
+}
+
+fn remote_url_matches(pattern: &str, repo: &Repository) -> bool {
+    let Ok(remotes) = repo.remotes() else {
+        return false;
+    };
+    remotes
+        .iter()
+        .flatten()
+        .filter_map(|name| repo.find_remote(name).ok())
+        .any(|remote| {
+            [remote.url(), remote.pushurl()]
+                .into_iter()
+                .flatten()
+                .any(|url| wildcard_match(pattern.trim().as_bytes(), url.as_bytes()))
+        })
+}
+
+fn rule_matches(rule: &IdentityRule, repo: &Repository) -> bool {
+    match rule.kind.as_str() {
+        "remote_url" => remote_url_matches(&rule.pattern, repo),
+        "directory" => directory_matches(&rule.pattern, repo),
+        _ => false,
+    }
+}
+
+// The profile of the first rule matching the repository
+pub fn matching_identity_profile<'a>(
+    repo: &Repository,
+    settings: &'a IdentitySettings,
+) -> Option<&'a IdentityProfile> {
+    let rule = settings.rules.iter().find(|r| rule_matches(r, repo))?;
+    settings.profiles.iter().find(|p| p.name == rule.profile)
+}
+
+fn identity_matches(profile: &IdentityProfile, name: &str, email: &str) -> bool {
+    name.trim() == profile.user_name.trim()
+        && email.trim().eq_ignore_ascii_case(profile.user_email.trim())
+}
+
+pub fn repo_identity(
+    repo: &Repository,
+    settings: &IdentitySettings,
+) -> Result<RepoIdentity, GitClientError> {
+    let config = repo.config()?;
+    let user_name = config.get_string("user.name").ok();
+    let user_email = config.get_string("user.email").ok();
+    let profile = matching_identity_profile(repo, settings).cloned();
+    let matches = profile.as_ref().is_none_or(|profile| {
+        identity_matches(
+            profile,
+            user_name.as_deref().unwrap_or_default(),
+            user_email.as_deref().unwrap_or_default(),
+        )
+    });
+    Ok(RepoIdentity {
+        profile,
+        user_name,
+        user_email,
+        matches,
+    })
+}
+
+// Refuses to commit as a configured user other than the one `profile` asks
+// for, unless `confirm_identity` is set
+pub fn check_commit_identity(
+    repo: &Repository,
+    options: &CommitOptions,
+    profile: &IdentityProfile,
+) -> Result<(), GitClientError> {
+    if options.confirm_identity {
+        return Ok(());
+    }
+    // Read from the config rather than `repo.signature()`, which fails
+    // without explaining the profile when the user isn't configured at all
+    let config = repo.config()?;
+    let name = config.get_string("user.name").unwrap_or_default();
+    let email = config.get_string("user.email").unwrap_or_default();
+    if identity_matches(profile, &name, &email) {
+        return Ok(());
+    }
+    let problem = if name.trim().is_empty() || email.trim().is_empty() {
+        "user.name or user.email isn't set".to_string()
+    } else {
+        format!("the commit would be made as {} <{}>", name, email)
+    };
+    Err(GitClientError::Operation(format!(
+        "The identity profile {} ({} <{}>) applies to this repository, but {}. Apply the profile or confirm committing anyway.",
+        profile.name, profile.user_name, profile.user_email, problem
+    )))
+}
+
+fn unset_local(repo: &Repository, key: &str) -> Result<(), GitClientError> {
+    match repo
+        .config()?
+        .open_level(git2::ConfigLevel::Local)?
+        .remove(key)
+    {
+        Err(e) if e.code() != git2::ErrorCode::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+// Writes the profile to the repository's local config. A profile without a
+// signing key or format removes the local ones, so the global ones apply.
+pub fn apply_identity_profile(
+    repo: &Repository,
+    profile: &IdentityProfile,
+) -> Result<(), GitClientError> {
+    set_config_value(repo, "local", "user.name", profile.user_name.trim(), None)?;
+    set_config_value(repo, "local", "user.email", profile.user_email.trim(), None)?;
+    match &profile.signing_key {
+        Some(key) => set_config_value(repo, "local", "user.signingkey", key, None)?,
+        None => unset_local(repo, "user.signingkey")?,
+    }
+    match &profile.signing_format {
+        Some(format) => set_config_value(repo, "local", "gpg.format", format, None)?,
+        None => unset_local(repo, "gpg.format")?,
+    }
+    Ok(())
+}
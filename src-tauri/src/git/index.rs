@@ -1,4 +1,7 @@
 use crate::error::GitClientError;
+use crate::git::lfs::{is_lfs_tracked, lfs_matches_index, smudge_lfs_files, stage_lfs_file};
+use crate::git::sparse::{reapply_sparse_checkout, sparse_skipped_paths};
+use crate::git::split::ensure_no_split_in_progress;
 use git2::{Oid, Repository, ResetType, StatusOptions};
 use std::path::{Component, Path};
 
@@ -9,7 +12,9 @@ pub fn stage_file(repo: &Repository, path: &str) -> Result<(), GitClientError> {
     let workdir = repo.workdir().ok_or(GitClientError::NoRepository)?;
     let full_path = workdir.join(file_path);
 
-    if full_path.exists() {
+    if is_lfs_tracked(repo, path) {
+        stage_lfs_file(repo, &mut index, path)?;
+    } else if full_path.exists() {
         index.add_path(file_path)?;
     } else {
         // File was deleted, remove from index
@@ -30,8 +35,32 @@ pub fn unstage_file(repo: &Repository, path: &str) -> Result<(), GitClientError>
 }
 
 pub fn stage_all(repo: &Repository) -> Result<(), GitClientError> {
+    // Files left out by a sparse checkout are missing, not deleted
+    let skipped = sparse_skipped_paths(repo)?;
     let mut index = repo.index()?;
-    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)?;
+    // LFS-tracked files are staged as pointers afterwards
+    let mut lfs_paths = Vec::new();
+    let mut filter = |path: &Path, _: &[u8]| -> i32 {
+        let path = path.to_string_lossy();
+        if skipped.contains(path.as_ref()) {
+            1
+        } else if is_lfs_tracked(repo, &path) {
+            lfs_paths.push(path.to_string());
+            1
+        } else {
+            0
+        }
+    };
+    index.add_all(
+        ["*"].iter(),
+        git2::IndexAddOption::DEFAULT,
+        Some(&mut filter),
+    )?;
+    for path in &lfs_paths {
+        if !lfs_matches_index(repo, path) {
+            stage_lfs_file(repo, &mut index, path)?;
+        }
+    }
     index.write()?;
     Ok(())
 }
@@ -76,6 +105,7 @@ pub fn discard_changes(repo: &Repository, path: &str) -> Result<(), GitClientErr
     checkout_opts.remove_untracked(true);
 
     repo.checkout_head(Some(&mut checkout_opts))?;
+    smudge_lfs_files(repo)?;
     Ok(())
 }
 
@@ -108,6 +138,9 @@ pub fn discard_all_changes(repo: &Repository) -> Result<(), GitClientError> {
     checkout_opts.remove_untracked(true);
 
     repo.checkout_head(Some(&mut checkout_opts))?;
+    // The checkout restored files a sparse checkout leaves out
+    reapply_sparse_checkout(repo)?;
+    smudge_lfs_files(repo)?;
     Ok(())
 }
 
@@ -148,6 +181,7 @@ pub fn reset_to_commit(
     commit_id: &str,
     mode: &str,
 ) -> Result<(), GitClientError> {
+    ensure_no_split_in_progress(repo, "reset")?;
     let oid = Oid::from_str(commit_id)
         .map_err(|e| GitClientError::Operation(format!("Invalid commit ID: {}", e)))?;
     let commit = repo.find_commit(oid)?;
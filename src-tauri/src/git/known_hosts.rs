@@ -0,0 +1,297 @@
+use crate::git::ssh_config::{host_config_for_host, match_pattern_list};
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD};
+use base64::Engine;
+use git2::cert::CertHostkey;
+use git2::CertificateCheckStatus;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const SYSTEM_KNOWN_HOSTS: &str = "/etc/ssh/ssh_known_hosts";
+
+// A host key known_hosts doesn't vouch for, for the user to decide on
+#[derive(Debug, Serialize, Clone)]
+pub struct HostKeyInfo {
+    // The name the key is recorded under: `host`, or `[host]:port`
+    pub host: String,
+    pub key_type: String,
+    // `SHA256:` and the base64 digest, as `ssh-keygen -l` shows it
+    pub fingerprint: String,
+    // "unknown", or "changed" when known_hosts has a different key
+    pub status: String,
+    // The fingerprints known_hosts has for the host, when the key changed
+    pub known_fingerprints: Vec<String>,
+}
+
+enum HostKeyStatus {
+    Known,
+    Unknown,
+    Changed(Vec<String>),
+    Revoked,
+}
+
+pub fn fingerprint(key: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(key)))
+}
+
+fn host_entry_name(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+fn hashed_name(salt: &[u8], name: &str) -> Option<Vec<u8>> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(salt).ok()?;
+    mac.update(name.as_bytes());
+    Some(mac.finalize().into_bytes().to_vec())
+}
+
+// The hosts field is either a single `|1|salt|hash` entry, as written with
+// HashKnownHosts, or a comma-separated pattern list
+fn hosts_match(hosts: &str, name: &str) -> bool {
+    if let Some(hashed) = hosts.strip_prefix("|1|") {
+        let Some((salt, hash)) = hashed.split_once('|') else {
+            return false;
+        };
+        let (Ok(salt), Ok(hash)) = (STANDARD.decode(salt), STANDARD.decode(hash)) else {
+            return false;
+        };
+        return hashed_name(&salt, name).is_some_and(|expected| expected == hash);
+    }
+    match_pattern_list(hosts.split(','), name)
+}
+
+fn check_host_key(files: &[PathBuf], name: &str, key_type: &str, key: &[u8]) -> HostKeyStatus {
+    let mut known = false;
+    let mut other_keys = Vec::new();
+    for file in files {
+        let Ok(contents) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let mut hosts = fields.next().unwrap_or_default();
+            let marker = hosts.strip_prefix('@');
+            if marker.is_some() {
+                hosts = fields.next().unwrap_or_default();
+            }
+            let (Some(entry_type), Some(entry_key)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            // Certificate authorities sign host certificates, which libssh2
+            // doesn't use
+            if marker == Some("cert-authority") || !hosts_match(hosts, name) {
+                continue;
+            }
+            let Ok(entry_key) = STANDARD.decode(entry_key) else {
+                continue;
+            };
+            if entry_key == key {
+                if marker == Some("revoked") {
+                    return HostKeyStatus::Revoked;
+                }
+                known = true;
+            } else if entry_type == key_type && marker.is_none() {
+                other_keys.push(fingerprint(&entry_key));
+            }
+        }
+    }
+    if known {
+        HostKeyStatus::Known
+    } else if other_keys.is_empty() {
+        HostKeyStatus::Unknown
+    } else {
+        HostKeyStatus::Changed(other_keys)
+    }
+}
+
+// Appends the key to a known_hosts file, hashing the host name if the ssh
+// config asks for it
+fn add_known_host(
+    file: &Path,
+    name: &str,
+    key_type: &str,
+    key: &[u8],
+    hash: bool,
+) -> std::io::Result<()> {
+    let hosts = if hash {
+        // Not secret, only distinct per entry
+        let seed = format!(
+            "{}{:?}{}",
+            name,
+            std::time::SystemTime::now(),
+            std::process::id()
+        );
+        let salt = &Sha256::digest(seed.as_bytes())[..20];
+        let digest = hashed_name(salt, name).unwrap_or_default();
+        format!("|1|{}|{}", STANDARD.encode(salt), STANDARD.encode(digest))
+    } else {
+        name.to_string()
+    };
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let needs_newline = std::fs::read(file)
+        .map(|contents| contents.last().is_some_and(|c| *c != b'\n'))
+        .unwrap_or(false);
+    let mut out = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?;
+    if needs_newline {
+        writeln!(out)?;
+    }
+    writeln!(out, "{} {} {}", hosts, key_type, STANDARD.encode(key))
+}
+
+// Checks the host key of an SSH server against the known_hosts files named
+// in the ssh config. Keys known_hosts doesn't vouch for go to `confirm`;
+// with StrictHostKeyChecking `no` or `accept-new`, keys of new hosts are
+// accepted without asking, and with `yes` they are refused. Accepted keys
+// are remembered in the user's known_hosts.
+pub(crate) fn verify_host_key(
+    cert: &CertHostkey,
+    hostname: &str,
+    confirm: impl Fn(HostKeyInfo) -> bool,
+) -> Result<CertificateCheckStatus, git2::Error> {
+    let (Some(key), Some(key_type)) = (cert.hostkey(), cert.hostkey_type()) else {
+        return Err(git2::Error::from_str("The SSH server sent no host key"));
+    };
+    let key_type = key_type.name();
+    let config = host_config_for_host(hostname);
+    let lookup = config.host_key_alias.as_deref().unwrap_or(hostname);
+    let name = host_entry_name(lookup, config.port);
+
+    let mut files = config.user_known_hosts_files.clone();
+    files.push(PathBuf::from(SYSTEM_KNOWN_HOSTS));
+    let known_fingerprints = match check_host_key(&files, &name, key_type, key) {
+        HostKeyStatus::Known => return Ok(CertificateCheckStatus::CertificateOk),
+        HostKeyStatus::Revoked => {
+            return Err(git2::Error::from_str(&format!(
+                "The host key for {} has been revoked",
+                name
+            )))
+        }
+        HostKeyStatus::Unknown => None,
+        HostKeyStatus::Changed(known) => Some(known),
+    };
+
+    let info = HostKeyInfo {
+        host: name.clone(),
+        key_type: key_type.to_string(),
+        fingerprint: fingerprint(key),
+        status: if known_fingerprints.is_some() {
+            "changed"
+        } else {
+            "unknown"
+        }
+        .to_string(),
+        known_fingerprints: known_fingerprints.clone().unwrap_or_default(),
+    };
+    // `yes` refuses every key known_hosts doesn't vouch for without asking
+    let trusted = match config.strict_host_key_checking.as_deref() {
+        Some("yes") => false,
+        Some("no" | "accept-new") if known_fingerprints.is_none() => true,
+        _ => confirm(info.clone()),
+    };
+    if !trusted {
+        return Err(git2::Error::from_str(&format!(
+            "The {} host key for {} is not trusted ({} {})",
+            info.status, name, key_type, info.fingerprint
+        )));
+    }
+
+    if let Some(file) = config.user_known_hosts_files.first() {
+        add_known_host(file, &name, key_type, key, config.hash_known_hosts).map_err(|e| {
+            git2::Error::from_str(&format!("Failed to update {}: {}", file.display(), e))
+        })?;
+    }
+    Ok(CertificateCheckStatus::CertificateOk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Written by `ssh-keygen -H`
+    const HASHED_GITHUB: &str = "|1|pH5tEfbbDH73srN2/8tJYefvSWs=|exUCaPchT9nZOUz80ZJEbMz488s=";
+    const HASHED_PORT: &str = "|1|83VGX3A4mryPl70zx0ci9/Ia6iI=|1aNFFfKzAdFmlMLazrzhiAlBdRQ=";
+
+    #[test]
+    fn matches_host_patterns() {
+        assert!(hosts_match("gitlab.com,github.com", "github.com"));
+        assert!(hosts_match("*.example.com", "git.example.com"));
+        assert!(hosts_match(
+            "[git.example.com]:2222",
+            "[git.example.com]:2222"
+        ));
+        assert!(!hosts_match("git.example.com", "[git.example.com]:2222"));
+        assert!(!hosts_match(
+            "*.example.com,!secret.example.com",
+            "secret.example.com"
+        ));
+    }
+
+    #[test]
+    fn matches_hashed_hosts() {
+        assert!(hosts_match(HASHED_GITHUB, "github.com"));
+        assert!(!hosts_match(HASHED_GITHUB, "gitlab.com"));
+        assert!(hosts_match(HASHED_PORT, "[host.example]:2222"));
+        assert!(!hosts_match(HASHED_PORT, "host.example"));
+        assert!(!hosts_match(
+            "|1|not base64|exUCaPchT9nZOUz80ZJEbMz488s=",
+            "github.com"
+        ));
+        assert!(!hosts_match(
+            "|1|pH5tEfbbDH73srN2/8tJYefvSWs=",
+            "github.com"
+        ));
+    }
+
+    #[test]
+    fn checks_keys_against_known_hosts() {
+        let dir = std::env::temp_dir().join(format!("forked-known-hosts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("known_hosts");
+        let _ = std::fs::remove_file(&file);
+        let (key, other, revoked) = (b"key".as_slice(), b"other".as_slice(), b"bad".as_slice());
+        let files = [file.clone()];
+
+        add_known_host(&file, "github.com", "ssh-ed25519", key, true).unwrap();
+        add_known_host(&file, "[git.example.com]:2222", "ssh-ed25519", key, false).unwrap();
+        let mut out = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&file)
+            .unwrap();
+        writeln!(out, "@revoked * ssh-ed25519 {}", STANDARD.encode(revoked)).unwrap();
+
+        let status = |name: &str, key: &[u8]| check_host_key(&files, name, "ssh-ed25519", key);
+        assert!(matches!(status("github.com", key), HostKeyStatus::Known));
+        assert!(matches!(
+            status("[git.example.com]:2222", key),
+            HostKeyStatus::Known
+        ));
+        assert!(matches!(
+            status("git.example.com", key),
+            HostKeyStatus::Unknown
+        ));
+        assert!(matches!(
+            status("github.com", revoked),
+            HostKeyStatus::Revoked
+        ));
+        match status("github.com", other) {
+            HostKeyStatus::Changed(known) => assert_eq!(known, vec![fingerprint(key)]),
+            _ => panic!("expected a changed key"),
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
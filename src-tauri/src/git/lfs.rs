@@ -0,0 +1,942 @@
+use crate::error::GitClientError;
+use crate::git::worktree::common_dir;
+use git2::{
+    AttrCheckFlags, AttrValue, DiffDelta, Index, IndexEntry, IndexTime, Oid, Repository, Status,
+    StatusOptions,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+
+// Pointer files are tiny; anything larger is real content
+const MAX_POINTER_SIZE: u64 = 1024;
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct LfsPointer {
+    // Hex SHA-256 of the object's content
+    pub oid: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LfsDiff {
+    pub old: Option<LfsPointer>,
+    pub new: Option<LfsPointer>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LfsTrackedPattern {
+    pub pattern: String,
+    // The .gitattributes file declaring it
+    pub source: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LfsStatus {
+    // Output of `git lfs version`, None when git-lfs isn't installed
+    pub version: Option<String>,
+    pub patterns: Vec<LfsTrackedPattern>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LfsLock {
+    pub id: String,
+    pub path: String,
+    pub owner: Option<String>,
+    pub locked_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawLockOwner {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct RawLock {
+    id: String,
+    path: String,
+    owner: Option<RawLockOwner>,
+    locked_at: Option<String>,
+}
+
+impl From<RawLock> for LfsLock {
+    fn from(raw: RawLock) -> Self {
+        Self {
+            id: raw.id,
+            path: raw.path,
+            owner: raw.owner.map(|o| o.name),
+            locked_at: raw.locked_at,
+        }
+    }
+}
+
+pub fn parse_lfs_pointer(data: &[u8]) -> Option<LfsPointer> {
+    if data.len() as u64 > MAX_POINTER_SIZE {
+        return None;
+    }
+    let text = std::str::from_utf8(data).ok()?;
+    let mut lines = text.lines();
+    if lines.next()? != POINTER_VERSION {
+        return None;
+    }
+    let mut oid = None;
+    let mut size = None;
+    for line in lines {
+        if let Some(value) = line.strip_prefix("oid sha256:") {
+            oid = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("size ") {
+            size = value.parse().ok();
+        }
+    }
+    let oid = oid.filter(|o| o.len() == 64 && o.bytes().all(|b| b.is_ascii_hexdigit()))?;
+    Some(LfsPointer { oid, size: size? })
+}
+
+fn pointer_text(pointer: &LfsPointer) -> String {
+    format!(
+        "{}\noid sha256:{}\nsize {}\n",
+        POINTER_VERSION, pointer.oid, pointer.size
+    )
+}
+
+pub fn is_lfs_tracked(repo: &Repository, path: &str) -> bool {
+    repo.get_attr(Path::new(path), "filter", AttrCheckFlags::default())
+        .map(|value| AttrValue::from_string(value) == AttrValue::String("lfs"))
+        .unwrap_or(false)
+}
+
+fn lfs_version() -> Option<String> {
+    let output = Command::new("git")
+        .args(["lfs", "version"])
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn workdir(repo: &Repository) -> Result<&Path, GitClientError> {
+    repo.workdir().ok_or_else(|| {
+        GitClientError::Operation("Git LFS needs a repository with a working tree".to_string())
+    })
+}
+
+fn run_lfs(repo: &Repository, args: &[&str], stdin: Stdio) -> Result<Vec<u8>, GitClientError> {
+    let output = Command::new("git")
+        .arg("lfs")
+        .args(args)
+        .current_dir(workdir(repo)?)
+        .stdin(stdin)
+        .output()
+        .map_err(|e| GitClientError::Operation(format!("Failed to run git-lfs: {}", e)))?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(GitClientError::Operation(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+fn require_lfs() -> Result<(), GitClientError> {
+    match lfs_version() {
+        Some(_) => Ok(()),
+        None => Err(GitClientError::Operation(
+            "Git LFS is not installed".to_string(),
+        )),
+    }
+}
+
+// Where git-lfs keeps downloaded and cleaned objects
+fn local_objects_dir(repo: &Repository) -> PathBuf {
+    common_dir(repo).join("lfs").join("objects")
+}
+
+fn local_object_path(repo: &Repository, oid: &str) -> PathBuf {
+    local_objects_dir(repo)
+        .join(&oid[0..2])
+        .join(&oid[2..4])
+        .join(oid)
+}
+
+fn hash_file(path: &Path) -> Result<LfsPointer, GitClientError> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut file, &mut hasher)?;
+    Ok(LfsPointer {
+        oid: format!("{:x}", hasher.finalize()),
+        size,
+    })
+}
+
+// Files modified this recently may change again within the same timestamp,
+// so their hashes aren't cached
+const RACY_INTERVAL: Duration = Duration::from_secs(2);
+
+struct CachedHash {
+    size: u64,
+    modified: SystemTime,
+    pointer: LfsPointer,
+}
+
+// Hashes of working tree files by path, reused while the file's size and
+// modification time stay the same, as git does with the index's stat data
+fn hash_cache() -> &'static Mutex<HashMap<PathBuf, CachedHash>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedHash>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_hash(path: &Path, metadata: &std::fs::Metadata) -> Option<LfsPointer> {
+    let Ok(modified) = metadata.modified() else {
+        return hash_file(path).ok();
+    };
+    if let Some(cached) = hash_cache().lock().get(path) {
+        if cached.size == metadata.len() && cached.modified == modified {
+            return Some(cached.pointer.clone());
+        }
+    }
+    let pointer = hash_file(path).ok()?;
+    let settled = SystemTime::now()
+        .duration_since(modified)
+        .is_ok_and(|age| age >= RACY_INTERVAL);
+    if settled && pointer.size == metadata.len() {
+        let cached = CachedHash {
+            size: metadata.len(),
+            modified,
+            pointer: pointer.clone(),
+        };
+        hash_cache().lock().insert(path.to_path_buf(), cached);
+    }
+    Some(pointer)
+}
+
+// The pointer the clean filter would produce for a working tree file. A file
+// that is still a pointer (never smudged) is its own pointer.
+fn workdir_pointer(repo: &Repository, path: &str) -> Option<LfsPointer> {
+    let full_path = repo.workdir()?.join(path);
+    let metadata = std::fs::metadata(&full_path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    if metadata.len() <= MAX_POINTER_SIZE {
+        if let Some(pointer) = std::fs::read(&full_path)
+            .ok()
+            .and_then(|data| parse_lfs_pointer(&data))
+        {
+            return Some(pointer);
+        }
+    }
+    cached_hash(&full_path, &metadata)
+}
+
+fn blob_pointer(repo: &Repository, id: Oid) -> Option<LfsPointer> {
+    if id.is_zero() {
+        return None;
+    }
+    let blob = repo.find_blob(id).ok()?;
+    parse_lfs_pointer(blob.content())
+}
+
+// True when the working tree file of an LFS-tracked path cleans to the pointer
+// in the index, i.e. it only looks modified because libgit2 doesn't run the
+// clean filter
+pub fn lfs_matches_index(repo: &Repository, path: &str) -> bool {
+    let Ok(index) = repo.index() else {
+        return false;
+    };
+    let Some(entry) = index.get_path(Path::new(path), 0) else {
+        return false;
+    };
+    match blob_pointer(repo, entry.id) {
+        Some(pointer) => workdir_pointer(repo, path).as_ref() == Some(&pointer),
+        None => false,
+    }
+}
+
+// Pointer details for either side of a delta that involves LFS content. The
+// new side is read from the working tree when `new_in_workdir` is set.
+pub fn lfs_change(repo: &Repository, delta: &DiffDelta, new_in_workdir: bool) -> Option<LfsDiff> {
+    let path = delta
+        .new_file()
+        .path()
+        .or_else(|| delta.old_file().path())?
+        .to_string_lossy()
+        .to_string();
+    let old = blob_pointer(repo, delta.old_file().id());
+    if old.is_none() && !is_lfs_tracked(repo, &path) {
+        return None;
+    }
+    let new = if new_in_workdir {
+        workdir_pointer(repo, &path)
+    } else {
+        blob_pointer(repo, delta.new_file().id())
+    };
+    if old.is_none() && new.is_none() {
+        return None;
+    }
+    Some(LfsDiff { old, new })
+}
+
+// Runs the clean filter over a working tree file and returns the pointer to
+// store. Without git-lfs the object is hashed and copied into the local LFS
+// store ourselves.
+fn clean_file(repo: &Repository, path: &str) -> Result<Vec<u8>, GitClientError> {
+    let full_path = workdir(repo)?.join(path);
+    if lfs_version().is_some() {
+        return run_lfs(
+            repo,
+            &["clean", "--", path],
+            Stdio::from(File::open(&full_path)?),
+        );
+    }
+
+    let pointer = hash_file(&full_path)?;
+    let object = local_object_path(repo, &pointer.oid);
+    if !object.exists() {
+        if let Some(dir) = object.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = object.with_extension("tmp");
+        std::fs::copy(&full_path, &tmp)?;
+        std::fs::rename(&tmp, &object)?;
+    }
+    Ok(pointer_text(&pointer).into_bytes())
+}
+
+fn index_time(time: std::io::Result<SystemTime>) -> IndexTime {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| IndexTime::new(d.as_secs() as i32, d.subsec_nanos()))
+        .unwrap_or_else(|| IndexTime::new(0, 0))
+}
+
+// Stores the working tree file's stat data in its index entry, so libgit2
+// trusts the entry instead of hashing the smudged content against the pointer
+fn record_stat(index: &mut Index, path: &str, full_path: &Path) -> Result<(), GitClientError> {
+    let Some(mut entry) = index.get_path(Path::new(path), 0) else {
+        return Ok(());
+    };
+    let metadata = std::fs::metadata(full_path)?;
+    entry.mtime = index_time(metadata.modified());
+    entry.file_size = metadata.len() as u32;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        entry.ctime = IndexTime::new(metadata.ctime() as i32, metadata.ctime_nsec() as u32);
+        entry.dev = metadata.dev() as u32;
+        entry.ino = metadata.ino() as u32;
+        entry.uid = metadata.uid();
+        entry.gid = metadata.gid();
+    }
+    #[cfg(not(unix))]
+    {
+        entry.ctime = index_time(metadata.created());
+    }
+    index.add(&entry)?;
+    Ok(())
+}
+
+// Marks LFS files whose content matches their pointer as unmodified in the
+// index. Run before a safe checkout, which would otherwise refuse to touch them.
+pub fn refresh_lfs_index(repo: &Repository) -> Result<(), GitClientError> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(());
+    };
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let paths: Vec<String> = repo
+        .statuses(Some(&mut opts))?
+        .iter()
+        .filter(|entry| entry.status().contains(Status::WT_MODIFIED))
+        .filter_map(|entry| entry.path().map(|p| p.to_string()))
+        .filter(|path| is_lfs_tracked(repo, path) && lfs_matches_index(repo, path))
+        .collect();
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let mut index = repo.index()?;
+    for path in &paths {
+        record_stat(&mut index, path, &workdir.join(path))?;
+    }
+    index.write()?;
+    Ok(())
+}
+
+// Stages an LFS-tracked file as a pointer rather than its content
+pub fn stage_lfs_file(
+    repo: &Repository,
+    index: &mut Index,
+    path: &str,
+) -> Result<(), GitClientError> {
+    if !workdir(repo)?.join(path).exists() {
+        index.remove_path(Path::new(path))?;
+        return Ok(());
+    }
+    let full_path = workdir(repo)?.join(path);
+    let pointer = clean_file(repo, path)?;
+    let mode = index
+        .get_path(Path::new(path), 0)
+        .map(|entry| entry.mode)
+        .unwrap_or(0o100644);
+    let entry = IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode,
+        uid: 0,
+        gid: 0,
+        file_size: pointer.len() as u32,
+        id: Oid::zero(),
+        flags: 0,
+        flags_extended: 0,
+        path: path.as_bytes().to_vec(),
+    };
+    index.add_frombuffer(&entry, &pointer)?;
+    record_stat(index, path, &full_path)
+}
+
+// Fetches the content for a pointer, from git-lfs when installed or else from
+// the local LFS store. None if the content isn't available.
+fn smudge(
+    repo: &Repository,
+    path: &str,
+    pointer: &LfsPointer,
+    installed: bool,
+) -> Result<Option<Vec<u8>>, GitClientError> {
+    if installed {
+        let mut child = Command::new("git")
+            .args(["lfs", "smudge", "--", path])
+            .current_dir(workdir(repo)?)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| GitClientError::Operation(format!("Failed to run git-lfs: {}", e)))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(pointer_text(pointer).as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(GitClientError::Operation(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        return Ok(Some(output.stdout));
+    }
+
+    let object = local_object_path(repo, &pointer.oid);
+    if object.is_file() {
+        Ok(Some(std::fs::read(object)?))
+    } else {
+        Ok(None)
+    }
+}
+
+// Replaces pointer files left in the working tree by a checkout with their
+// content, since libgit2 doesn't run the smudge filter. Files whose content
+// can't be fetched keep their pointer.
+pub fn smudge_lfs_files(repo: &Repository) -> Result<(), GitClientError> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(());
+    };
+    let mut index = repo.index()?;
+    let mut installed = None;
+    let mut smudged = Vec::new();
+    let entries: Vec<IndexEntry> = index.iter().collect();
+    for entry in entries {
+        if (entry.flags >> 12) & 0x3 != 0 {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry.path).to_string();
+        let full_path = workdir.join(&path);
+        let small_file = std::fs::symlink_metadata(&full_path)
+            .is_ok_and(|m| m.is_file() && m.len() <= MAX_POINTER_SIZE);
+        if !small_file || !is_lfs_tracked(repo, &path) {
+            continue;
+        }
+        let Some(pointer) = std::fs::read(&full_path)
+            .ok()
+            .and_then(|data| parse_lfs_pointer(&data))
+        else {
+            continue;
+        };
+
+        let installed = *installed.get_or_insert_with(|| lfs_version().is_some());
+        match smudge(repo, &path, &pointer, installed) {
+            Ok(Some(content)) => {
+                std::fs::write(&full_path, content)?;
+                smudged.push((path, full_path));
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Could not fetch LFS content for {}: {e}", path),
+        }
+    }
+
+    if !smudged.is_empty() {
+        for (path, full_path) in &smudged {
+            record_stat(&mut index, path, full_path)?;
+        }
+        index.write()?;
+    }
+    Ok(())
+}
+
+// LFS objects in the local store that commits reachable from `tips`, but not
+// from the remote's tracking branches, point to; by path, for messages
+fn unpushed_objects(
+    repo: &Repository,
+    remote_name: &str,
+    tips: &[Oid],
+) -> Result<Vec<(String, LfsPointer)>, GitClientError> {
+    let mut walk = repo.revwalk()?;
+    for tip in tips {
+        walk.push(*tip)?;
+    }
+    walk.hide_glob(&format!("refs/remotes/{}/*", remote_name))?;
+
+    let mut seen = HashSet::new();
+    let mut objects = Vec::new();
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+        for delta in diff.deltas() {
+            let Some(pointer) = blob_pointer(repo, delta.new_file().id()) else {
+                continue;
+            };
+            if local_object_path(repo, &pointer.oid).is_file() && seen.insert(pointer.oid.clone()) {
+                let path = delta.new_file().path().unwrap_or(Path::new(""));
+                objects.push((path.to_string_lossy().to_string(), pointer));
+            }
+        }
+    }
+    Ok(objects)
+}
+
+// Uploads the LFS objects a push is about to reference, as git-lfs's pre-push
+// hook does for git, since libgit2 doesn't run hooks. `refs` are the local
+// and remote ref names being pushed. Without git-lfs nothing can upload the
+// objects, so the push is refused rather than leaving the remote with
+// pointers to content only this clone has.
+pub fn push_lfs_objects(
+    repo: &Repository,
+    remote_name: &str,
+    url: &str,
+    refs: &[(String, String)],
+) -> Result<(), GitClientError> {
+    // Only objects in the local store need uploading, so repositories without
+    // LFS skip walking the pushed history
+    if !local_objects_dir(repo).is_dir() || tracked_patterns(repo)?.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = String::new();
+    let mut tips = Vec::new();
+    for (local_ref, remote_ref) in refs {
+        let reference = repo.find_reference(local_ref)?;
+        // Tags of trees or blobs carry no commits to scan
+        let (Some(target), Ok(commit)) = (reference.target(), reference.peel_to_commit()) else {
+            continue;
+        };
+        tips.push(commit.id());
+        lines.push_str(&format!(
+            "{} {} {} {}\n",
+            local_ref,
+            target,
+            remote_ref,
+            Oid::zero()
+        ));
+    }
+    let objects = unpushed_objects(repo, remote_name, &tips)?;
+    if objects.is_empty() {
+        return Ok(());
+    }
+
+    if lfs_version().is_none() {
+        let paths: Vec<&str> = objects.iter().map(|(path, _)| path.as_str()).collect();
+        return Err(GitClientError::Operation(format!(
+            "Git LFS is not installed, so the LFS content of {} can't be uploaded. Install git-lfs before pushing.",
+            paths.join(", ")
+        )));
+    }
+    let mut child = Command::new("git")
+        .args(["lfs", "pre-push", remote_name, url])
+        .current_dir(repo.workdir().unwrap_or(repo.path()))
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| GitClientError::Operation(format!("Failed to run git-lfs: {}", e)))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(lines.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(GitClientError::Operation(format!(
+            "Failed to upload LFS objects: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+fn tracked_patterns(repo: &Repository) -> Result<Vec<LfsTrackedPattern>, GitClientError> {
+    let index = repo.index()?;
+    let mut sources: Vec<String> = index
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .filter(|path| path == ".gitattributes" || path.ends_with("/.gitattributes"))
+        .collect();
+    if !sources.iter().any(|s| s == ".gitattributes")
+        && repo
+            .workdir()
+            .is_some_and(|w| w.join(".gitattributes").is_file())
+    {
+        sources.insert(0, ".gitattributes".to_string());
+    }
+
+    let mut patterns = Vec::new();
+    for source in sources {
+        let contents = match repo.workdir().map(|w| w.join(&source)) {
+            Some(file) if file.is_file() => std::fs::read_to_string(file)?,
+            _ => continue,
+        };
+        for line in contents.lines() {
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next().filter(|p| !p.starts_with('#')) else {
+                continue;
+            };
+            if fields.any(|attr| attr == "filter=lfs") {
+                patterns.push(LfsTrackedPattern {
+                    pattern: pattern.to_string(),
+                    source: source.clone(),
+                });
+            }
+        }
+    }
+    Ok(patterns)
+}
+
+pub fn get_lfs_status(repo: &Repository) -> Result<LfsStatus, GitClientError> {
+    Ok(LfsStatus {
+        version: lfs_version(),
+        patterns: tracked_patterns(repo)?,
+    })
+}
+
+pub fn list_lfs_locks(repo: &Repository) -> Result<Vec<LfsLock>, GitClientError> {
+    require_lfs()?;
+    let output = run_lfs(repo, &["locks", "--json"], Stdio::null())?;
+    let locks: Vec<RawLock> = serde_json::from_slice(&output)
+        .map_err(|e| GitClientError::Operation(format!("Unexpected git-lfs output: {}", e)))?;
+    Ok(locks.into_iter().map(LfsLock::from).collect())
+}
+
+pub fn lock_lfs_file(repo: &Repository, path: &str) -> Result<LfsLock, GitClientError> {
+    require_lfs()?;
+    let output = run_lfs(repo, &["lock", "--json", "--", path], Stdio::null())?;
+    let lock: RawLock = serde_json::from_slice(&output)
+        .map_err(|e| GitClientError::Operation(format!("Unexpected git-lfs output: {}", e)))?;
+    Ok(lock.into())
+}
+
+// `force` releases a lock held by someone else
+pub fn unlock_lfs_file(repo: &Repository, path: &str, force: bool) -> Result<(), GitClientError> {
+    require_lfs()?;
+    let mut args = vec!["unlock", "--json"];
+    if force {
+        args.push("--force");
+    }
+    args.extend(["--", path]);
+    run_lfs(repo, &args, Stdio::null())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::repository::get_status;
+    use git2::Signature;
+    use std::ops::Deref;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+    // A repository in a temporary directory that is removed when it's dropped
+    struct TestRepo {
+        repo: Repository,
+        dir: PathBuf,
+    }
+
+    impl Deref for TestRepo {
+        type Target = Repository;
+
+        fn deref(&self) -> &Repository {
+            &self.repo
+        }
+    }
+
+    impl Drop for TestRepo {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "forked-lfs-{}-{}-{}",
+            name,
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    // A repository that tracks *.bin with LFS. Without git-lfs installed, the
+    // local object store stands in for the LFS server.
+    fn lfs_repo() -> TestRepo {
+        let dir = temp_dir("test");
+        let repo = Repository::init(&dir).unwrap();
+        std::fs::write(dir.join(".gitattributes"), "*.bin filter=lfs -text\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(".gitattributes")).unwrap();
+        index.write().unwrap();
+        commit(&repo, "Track binaries with LFS");
+        TestRepo { repo, dir }
+    }
+
+    // Runs `f` with a stand-in git-lfs first on PATH, so tests don't depend
+    // on whether the real one is installed. The script runs from `dir`.
+    #[cfg(unix)]
+    fn with_git_lfs<T>(script: &str, f: impl FnOnce(&Path) -> T) -> T {
+        use std::os::unix::fs::PermissionsExt;
+
+        // PATH is process-wide
+        static PATH_LOCK: Mutex<()> = Mutex::new(());
+        let _guard = PATH_LOCK.lock();
+        let dir = temp_dir("bin");
+        std::fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("git-lfs");
+        std::fs::write(&program, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let original = std::env::var_os("PATH").unwrap_or_default();
+        let mut paths = vec![dir.clone()];
+        paths.extend(std::env::split_paths(&original));
+        std::env::set_var("PATH", std::env::join_paths(paths).unwrap());
+        let result = f(&dir);
+        std::env::set_var("PATH", original);
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    // Commits an LFS file whose content is only in the local object store
+    fn commit_local_object(repo: &Repository) -> (String, Oid) {
+        let workdir = repo.workdir().unwrap();
+        std::fs::write(workdir.join("data.bin"), vec![1u8; 2048]).unwrap();
+        stage(repo, "data.bin");
+        let head = commit(repo, "Add data");
+        (repo.head().unwrap().name().unwrap().to_string(), head)
+    }
+
+    fn commit(repo: &Repository, message: &str) -> Oid {
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    fn stage(repo: &Repository, path: &str) {
+        let mut index = repo.index().unwrap();
+        stage_lfs_file(repo, &mut index, path).unwrap();
+        index.write().unwrap();
+    }
+
+    fn index_pointer(repo: &Repository, path: &str) -> Option<LfsPointer> {
+        let entry = repo.index().unwrap().get_path(Path::new(path), 0)?;
+        blob_pointer(repo, entry.id)
+    }
+
+    #[test]
+    fn parses_pointer() {
+        let text = format!("{}\noid sha256:{}\nsize 12345\n", POINTER_VERSION, OID);
+        assert_eq!(
+            parse_lfs_pointer(text.as_bytes()),
+            Some(LfsPointer {
+                oid: OID.to_string(),
+                size: 12345
+            })
+        );
+        let pointer = parse_lfs_pointer(text.as_bytes()).unwrap();
+        assert_eq!(pointer_text(&pointer), text);
+    }
+
+    #[test]
+    fn rejects_malformed_pointers() {
+        let missing_size = format!("{}\noid sha256:{}\n", POINTER_VERSION, OID);
+        let short_oid = format!("{}\noid sha256:abc123\nsize 1\n", POINTER_VERSION);
+        let other_version = format!(
+            "version https://example.com/v2\noid sha256:{}\nsize 1\n",
+            OID
+        );
+        let mut oversized = format!("{}\noid sha256:{}\nsize 1\n", POINTER_VERSION, OID);
+        oversized.push_str(&"x ".repeat(600));
+        for text in [missing_size, short_oid, other_version, oversized] {
+            assert_eq!(parse_lfs_pointer(text.as_bytes()), None, "{}", text);
+        }
+        assert_eq!(parse_lfs_pointer(b"plain file content"), None);
+    }
+
+    #[test]
+    fn clean_and_smudge_round_trip() {
+        let repo = lfs_repo();
+        let workdir = repo.workdir().unwrap().to_path_buf();
+        let content: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(workdir.join("data.bin"), &content).unwrap();
+
+        stage(&repo, "data.bin");
+        let pointer = index_pointer(&repo, "data.bin").expect("index holds a pointer");
+        assert_eq!(pointer.size, content.len() as u64);
+        assert_eq!(pointer.oid, format!("{:x}", Sha256::digest(&content)));
+        assert_eq!(
+            std::fs::read(local_object_path(&repo, &pointer.oid)).unwrap(),
+            content
+        );
+
+        // A checkout leaves the pointer in the working tree
+        std::fs::write(workdir.join("data.bin"), pointer_text(&pointer)).unwrap();
+        smudge_lfs_files(&repo).unwrap();
+        assert_eq!(std::fs::read(workdir.join("data.bin")).unwrap(), content);
+        assert!(lfs_matches_index(&repo, "data.bin"));
+    }
+
+    #[test]
+    fn status_ignores_unchanged_lfs_content() {
+        let repo = lfs_repo();
+        let workdir = repo.workdir().unwrap().to_path_buf();
+        std::fs::write(workdir.join("data.bin"), vec![7u8; 2048]).unwrap();
+        stage(&repo, "data.bin");
+        commit(&repo, "Add data");
+
+        let status = get_status(&repo).unwrap();
+        assert!(status.unstaged.is_empty(), "{:?}", status.unstaged);
+        assert!(status.staged.is_empty(), "{:?}", status.staged);
+
+        std::fs::write(workdir.join("data.bin"), vec![8u8; 4096]).unwrap();
+        let status = get_status(&repo).unwrap();
+        assert_eq!(status.unstaged.len(), 1);
+        assert_eq!(status.unstaged[0].path, "data.bin");
+        assert!(!lfs_matches_index(&repo, "data.bin"));
+    }
+
+    #[test]
+    fn push_skips_history_without_local_objects() {
+        let repo = lfs_repo();
+        let branch = repo.head().unwrap().name().unwrap().to_string();
+        let refs = [(branch.clone(), branch)];
+        assert!(!local_objects_dir(&repo).exists());
+        assert!(push_lfs_objects(&repo, "origin", "", &refs).is_ok());
+    }
+
+    #[test]
+    fn finds_objects_the_remote_lacks() {
+        let repo = lfs_repo();
+        let (branch, head) = commit_local_object(&repo);
+        let objects = unpushed_objects(&repo, "origin", &[head]).unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].0, "data.bin");
+
+        // Nothing is left to upload once the remote has the commit
+        let tracking = branch.replace("refs/heads/", "refs/remotes/origin/");
+        repo.reference(&tracking, head, true, "test").unwrap();
+        let objects = unpushed_objects(&repo, "origin", &[head]).unwrap();
+        assert!(objects.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn push_without_git_lfs_refuses_local_objects() {
+        let repo = lfs_repo();
+        let (branch, head) = commit_local_object(&repo);
+        let refs = [(branch.clone(), branch.clone())];
+        with_git_lfs("exit 1", |_| {
+            let error = push_lfs_objects(&repo, "origin", "", &refs).unwrap_err();
+            assert!(error.to_string().contains("data.bin"), "{}", error);
+
+            let tracking = branch.replace("refs/heads/", "refs/remotes/origin/");
+            repo.reference(&tracking, head, true, "test").unwrap();
+            assert!(push_lfs_objects(&repo, "origin", "", &refs).is_ok());
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn push_with_git_lfs_runs_pre_push() {
+        let repo = lfs_repo();
+        let (branch, head) = commit_local_object(&repo);
+        let refs = [(branch.clone(), "refs/heads/upstream".to_string())];
+        let script = r#"case "$1" in
+version) echo "git-lfs/0.0.0" ;;
+pre-push) echo "$2 $3" > "$(dirname "$0")/args" && cat > "$(dirname "$0")/stdin" ;;
+*) exit 1 ;;
+esac"#;
+        let (args, stdin) = with_git_lfs(script, |dir| {
+            push_lfs_objects(&repo, "origin", "https://example.com/repo.git", &refs).unwrap();
+            (
+                std::fs::read_to_string(dir.join("args")).unwrap(),
+                std::fs::read_to_string(dir.join("stdin")).unwrap(),
+            )
+        });
+        assert_eq!(args, "origin https://example.com/repo.git\n");
+        assert_eq!(
+            stdin,
+            format!("{} {} refs/heads/upstream {}\n", branch, head, Oid::zero())
+        );
+
+        // Upload failures fail the push
+        let script = r#"case "$1" in
+version) echo "git-lfs/0.0.0" ;;
+*) cat > /dev/null; echo "upload rejected" >&2; exit 1 ;;
+esac"#;
+        let error = with_git_lfs(script, |_| {
+            push_lfs_objects(&repo, "origin", "", &refs).unwrap_err()
+        });
+        assert!(
+            error
+                .to_string()
+                .ends_with("Failed to upload LFS objects: upload rejected"),
+            "{}",
+            error
+        );
+    }
+}
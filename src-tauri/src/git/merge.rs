@@ -1,4 +1,6 @@
 use crate::error::GitClientError;
+use crate::git::lfs::{refresh_lfs_index, smudge_lfs_files};
+use crate::git::sparse::reapply_sparse_checkout;
 use git2::{MergeOptions, Repository};
 use serde::Serialize;
 
@@ -139,6 +141,7 @@ pub fn create_branch(repo: &Repository, name: &str, source_branch: Option<&str>)
 pub fn checkout_branch(repo: &Repository, name: &str) -> Result<(), GitClientError> {
     let (object, reference) = repo.revparse_ext(name)?;
 
+    refresh_lfs_index(repo)?;
     repo.checkout_tree(&object, None).map_err(|e| {
         if e.code() == git2::ErrorCode::Conflict {
             GitClientError::Operation(
@@ -156,6 +159,8 @@ pub fn checkout_branch(repo: &Repository, name: &str) -> Result<(), GitClientErr
         None => repo.set_head_detached(object.id())?,
     }
 
+    reapply_sparse_checkout(repo)?;
+    smudge_lfs_files(repo)?;
     Ok(())
 }
 
@@ -191,6 +196,8 @@ pub fn merge_branch(repo: &Repository, name: &str) -> Result<MergeResult, GitCli
         let mut reference = repo.find_reference(&refname)?;
         reference.set_target(merge_commit.id(), "Fast-forward merge")?;
         repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))?;
+        reapply_sparse_checkout(repo)?;
+        smudge_lfs_files(repo)?;
 
         return Ok(MergeResult {
             success: true,
@@ -1,13 +1,43 @@
+pub mod bisect;
+pub mod config;
 pub mod credentials;
 pub mod diff;
 pub mod history;
+pub mod identity;
 pub mod index;
+pub mod known_hosts;
+pub mod lfs;
 pub mod merge;
+pub mod process;
+pub mod remote;
 pub mod repository;
+pub mod scan;
+pub mod sparse;
+pub mod split;
+pub mod ssh_config;
+pub mod submodule;
+pub mod system_git;
+pub mod tls;
+pub mod worktree;
 
+pub use bisect::*;
+pub use config::*;
 pub use credentials::*;
 pub use diff::*;
 pub use history::*;
+pub use identity::*;
 pub use index::*;
+pub use known_hosts::*;
+pub use lfs::*;
 pub use merge::*;
+pub use process::*;
+pub use remote::*;
 pub use repository::*;
+pub use scan::*;
+pub use sparse::*;
+pub use split::*;
+pub use ssh_config::*;
+pub use submodule::*;
+pub use system_git::*;
+pub use tls::*;
+pub use worktree::*;
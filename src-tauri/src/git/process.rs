@@ -0,0 +1,30 @@
+use std::process::{Child, Command};
+
+// Starts the command in its own process group, so that killing it also stops
+// the processes it starts
+pub fn spawn_in_process_group(command: &mut Command) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    command.spawn()
+}
+
+// Kills a child started by `spawn_in_process_group` along with everything it
+// started, and reaps it
+pub fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    // SAFETY: killpg only sends a signal to the group the child leads
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/T", "/F", "/PID", &child.id().to_string()])
+            .output();
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
@@ -0,0 +1,244 @@
+use crate::error::GitClientError;
+use crate::git::credentials::{get_callbacks, proxy_options, RemoteObserver};
+use crate::git::ssh_config::{open_with_ssh_config, resolve_ssh_url};
+use crate::git::tls::with_tls_settings;
+use git2::{Direction, Remote, Repository};
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RemoteInfo {
+    pub name: String,
+    pub url: String,
+    pub push_url: Option<String>,
+    pub fetch_refspecs: Vec<String>,
+    pub push_refspecs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RemoteRef {
+    pub name: String,
+    pub oid: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RemoteConnectionTest {
+    pub url: String,
+    // The branch the remote's HEAD points at, if it advertises one
+    pub default_branch: Option<String>,
+    pub refs: Vec<RemoteRef>,
+}
+
+fn remote_info(name: &str, remote: &Remote) -> Result<RemoteInfo, GitClientError> {
+    let refspecs = |specs: git2::string_array::StringArray| {
+        specs
+            .iter()
+            .flatten()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+    };
+    Ok(RemoteInfo {
+        name: name.to_string(),
+        url: remote.url().unwrap_or("").to_string(),
+        push_url: remote.pushurl().map(|s| s.to_string()),
+        fetch_refspecs: refspecs(remote.fetch_refspecs()?),
+        push_refspecs: refspecs(remote.push_refspecs()?),
+    })
+}
+
+fn validate_name(name: &str) -> Result<(), GitClientError> {
+    if Remote::is_valid_name(name) {
+        Ok(())
+    } else {
+        Err(GitClientError::Operation(format!(
+            "'{}' is not a valid remote name",
+            name
+        )))
+    }
+}
+
+fn validate_url(url: &str) -> Result<&str, GitClientError> {
+    let url = url.trim();
+    if url.is_empty() {
+        Err(GitClientError::Operation(
+            "Remote URL cannot be empty".to_string(),
+        ))
+    } else {
+        Ok(url)
+    }
+}
+
+pub fn list_remotes(repo: &Repository) -> Result<Vec<RemoteInfo>, GitClientError> {
+    let remotes = repo.remotes()?;
+    let mut result = Vec::new();
+
+    for name in remotes.iter().flatten() {
+        if let Ok(remote) = repo.find_remote(name) {
+            result.push(remote_info(name, &remote)?);
+        }
+    }
+
+    Ok(result)
+}
+
+// Adds a remote with the default `+refs/heads/*:refs/remotes/<name>/*` fetch
+// refspec unless one is given
+pub fn add_remote(
+    repo: &Repository,
+    name: &str,
+    url: &str,
+    fetch_refspec: Option<&str>,
+) -> Result<RemoteInfo, GitClientError> {
+    validate_name(name)?;
+    let url = validate_url(url)?;
+    if repo.find_remote(name).is_ok() {
+        return Err(GitClientError::Operation(format!(
+            "Remote '{}' already exists",
+            name
+        )));
+    }
+    let remote = match fetch_refspec {
+        Some(refspec) => repo.remote_with_fetch(name, url, refspec)?,
+        None => repo.remote(name, url)?,
+    };
+    remote_info(name, &remote)
+}
+
+// Renames the remote along with its remote-tracking branches and the
+// `branch.<name>.remote` settings pointing at it. Returns the non-default
+// fetch refspecs that could not be updated and need editing by hand.
+pub fn rename_remote(
+    repo: &Repository,
+    old_name: &str,
+    new_name: &str,
+) -> Result<Vec<String>, GitClientError> {
+    validate_name(new_name)?;
+    repo.find_remote(old_name)?;
+    let problems = repo.remote_rename(old_name, new_name)?;
+    Ok(problems.iter().flatten().map(|s| s.to_string()).collect())
+}
+
+// Removes the remote, its remote-tracking branches and any upstream
+// configuration referring to it
+pub fn remove_remote(repo: &Repository, name: &str) -> Result<(), GitClientError> {
+    repo.find_remote(name)?;
+    repo.remote_delete(name)?;
+    Ok(())
+}
+
+pub fn set_remote_url(
+    repo: &Repository,
+    name: &str,
+    url: &str,
+) -> Result<RemoteInfo, GitClientError> {
+    let url = validate_url(url)?;
+    repo.find_remote(name)?;
+    repo.remote_set_url(name, url)?;
+    remote_info(name, &repo.find_remote(name)?)
+}
+
+// None removes the separate push URL, so pushes go to the fetch URL again
+pub fn set_remote_push_url(
+    repo: &Repository,
+    name: &str,
+    push_url: Option<&str>,
+) -> Result<RemoteInfo, GitClientError> {
+    let push_url = push_url.map(validate_url).transpose()?;
+    repo.find_remote(name)?;
+    repo.remote_set_pushurl(name, push_url)?;
+    remote_info(name, &repo.find_remote(name)?)
+}
+
+fn replace_fetch_refspecs(
+    repo: &Repository,
+    name: &str,
+    refspecs: &[String],
+) -> Result<(), GitClientError> {
+    let key = format!("remote.{}.fetch", name);
+    let mut config = repo.config()?;
+    match config.remove_multivar(&key, ".*") {
+        Ok(()) => {}
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    for refspec in refspecs {
+        repo.remote_add_fetch(name, refspec)?;
+    }
+    Ok(())
+}
+
+// Replaces the remote's fetch refspecs. libgit2 validates each one; on an
+// invalid refspec the previous set is restored.
+pub fn set_remote_fetch_refspecs(
+    repo: &Repository,
+    name: &str,
+    refspecs: &[String],
+) -> Result<RemoteInfo, GitClientError> {
+    let previous = remote_info(name, &repo.find_remote(name)?)?.fetch_refspecs;
+    let refspecs: Vec<String> = refspecs
+        .iter()
+        .map(|r| r.trim().to_string())
+        .filter(|r| !r.is_empty())
+        .collect();
+
+    if let Err(e) = replace_fetch_refspecs(repo, name, &refspecs) {
+        replace_fetch_refspecs(repo, name, &previous)?;
+        return Err(e);
+    }
+    remote_info(name, &repo.find_remote(name)?)
+}
+
+// Connects to a configured remote, or to a URL, and lists its refs like
+// `git ls-remote`. With `push` the push URL is tested instead. Host keys,
+// certificates and credentials are asked for through `observer`.
+pub fn test_remote_connection(
+    repo: &Repository,
+    remote: &str,
+    push: bool,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<RemoteConnectionTest, GitClientError> {
+    let ssh_repo = open_with_ssh_config(repo)?;
+    let repo = ssh_repo.as_ref().unwrap_or(repo);
+    let mut remote = match repo.find_remote(remote) {
+        Ok(remote) => remote,
+        Err(_) => repo.remote_anonymous(&resolve_ssh_url(validate_url(remote)?))?,
+    };
+    let direction = if push {
+        Direction::Push
+    } else {
+        Direction::Fetch
+    };
+    let url = match direction {
+        Direction::Push => remote.pushurl().or_else(|| remote.url()),
+        Direction::Fetch => remote.url(),
+    }
+    .unwrap_or("")
+    .to_string();
+
+    let config = repo.config()?;
+    let (default_branch, refs) = with_tls_settings(&config, &url, observer, || {
+        let connection = remote.connect_auth(
+            direction,
+            Some(get_callbacks(observer)),
+            Some(proxy_options(&config, &url)),
+        )?;
+        let default_branch = connection
+            .default_branch()
+            .ok()
+            .and_then(|b| b.as_str().map(|s| s.to_string()));
+        let refs = connection
+            .list()?
+            .iter()
+            .map(|head| RemoteRef {
+                name: head.name().to_string(),
+                oid: head.oid().to_string(),
+            })
+            .collect();
+        Ok((default_branch, refs))
+    })?;
+
+    Ok(RemoteConnectionTest {
+        url,
+        default_branch,
+        refs,
+    })
+}
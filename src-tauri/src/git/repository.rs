@@ -1,6 +1,14 @@
 use crate::error::GitClientError;
-use git2::{Repository, StatusOptions};
-use serde::Serialize;
+use crate::git::credentials::{get_callbacks, get_fetch_options, proxy_options, RemoteObserver};
+use crate::git::lfs::{is_lfs_tracked, lfs_matches_index, smudge_lfs_files};
+use crate::git::sparse::sparse_aware_statuses;
+use crate::git::ssh_config::{open_with_ssh_config, resolve_ssh_url};
+use crate::git::submodule::update_submodules;
+use crate::git::tls::with_tls_settings;
+use git2::build::CheckoutBuilder;
+use git2::{Repository, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::Path;
 
 #[derive(Debug, Serialize, Clone)]
@@ -10,6 +18,7 @@ pub struct RepoInfo {
     pub is_bare: bool,
     pub head_name: Option<String>,
     pub head_oid: Option<String>,
+    pub is_shallow: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -22,6 +31,8 @@ pub struct FileStatus {
     pub is_deleted: bool,
     pub is_renamed: bool,
     pub is_conflicted: bool,
+    pub is_submodule: bool,
+    pub is_lfs: bool,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -45,21 +56,198 @@ pub fn init_repo(path: &str) -> Result<Repository, GitClientError> {
     Repository::init(path).map_err(GitClientError::Git)
 }
 
-pub fn clone_repo(url: &str, path: &str) -> Result<Repository, GitClientError> {
-    use crate::git::credentials::get_fetch_options;
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct CloneOptions {
+    // Number of commits of history to fetch; None clones everything
+    pub depth: Option<u32>,
+    // Branch to check out instead of the remote's default branch
+    pub branch: Option<String>,
+    // Only fetch the checked out branch
+    pub single_branch: bool,
+    pub bare: bool,
+    // Bare clone that copies every ref, like `git clone --mirror`
+    pub mirror: bool,
+    pub no_checkout: bool,
+    pub recurse_submodules: bool,
+}
+
+// libgit2's GIT_FETCH_DEPTH_UNSHALLOW
+const UNSHALLOW_DEPTH: i32 = i32::MAX;
 
+fn fetch_depth(depth: u32) -> i32 {
+    i32::try_from(depth).unwrap_or(UNSHALLOW_DEPTH)
+}
+
+fn remote_default_branch(
+    url: &str,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<String, GitClientError> {
+    let mut remote = git2::Remote::create_detached(resolve_ssh_url(url))?;
+    let config = git2::Config::open_default()?;
+    let head = with_tls_settings(&config, url, observer, || {
+        let connection = remote.connect_auth(
+            git2::Direction::Fetch,
+            Some(get_callbacks(observer)),
+            Some(proxy_options(&config, url)),
+        )?;
+        let head = connection.default_branch()?;
+        Ok(head.as_str().unwrap_or_default().to_string())
+    })?;
+    head.strip_prefix("refs/heads/")
+        .map(|b| b.to_string())
+        .ok_or_else(|| GitClientError::Operation("Remote has no default branch".to_string()))
+}
+
+pub fn clone_repo(
+    url: &str,
+    path: &str,
+    options: &CloneOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<Repository, GitClientError> {
+    if options.mirror && (options.single_branch || options.branch.is_some()) {
+        return Err(GitClientError::Operation(
+            "A mirror clone copies every branch".to_string(),
+        ));
+    }
+
+    // Cleaning up after a failed clone empties the target, so it must hold
+    // nothing of the user's to begin with
     let path = Path::new(path);
+    if path.exists() && path.read_dir()?.next().is_some() {
+        return Err(GitClientError::InvalidPath(format!(
+            "{} already exists and is not empty",
+            path.display()
+        )));
+    }
+    let existed = path.exists();
+
+    let branch = match &options.branch {
+        Some(branch) => Some(branch.clone()),
+        None if options.single_branch => Some(remote_default_branch(url, observer)?),
+        None => None,
+    };
+
+    let config = git2::Config::open_default()?;
+    let mut fetch_opts = get_fetch_options(&config, url, observer);
+    if let Some(depth) = options.depth {
+        fetch_opts.depth(fetch_depth(depth));
+    }
+
     let mut builder = git2::build::RepoBuilder::new();
-    builder.fetch_options(get_fetch_options());
-    builder.clone(url, path).map_err(GitClientError::Git)
+    builder.fetch_options(fetch_opts);
+    builder.bare(options.bare || options.mirror);
+    if let Some(branch) = &branch {
+        builder.branch(branch);
+    }
+    if options.no_checkout {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.dry_run();
+        builder.with_checkout(checkout);
+    }
+    if options.mirror {
+        builder.remote_create(|repo, name, url| {
+            let remote = repo.remote_with_fetch(name, url, "+refs/*:refs/*")?;
+            repo.config()?
+                .set_bool(&format!("remote.{}.mirror", name), true)?;
+            Ok(remote)
+        });
+    } else if let (true, Some(branch)) = (options.single_branch, branch.clone()) {
+        builder.remote_create(move |repo, name, url| {
+            let refspec = format!("+refs/heads/{0}:refs/remotes/{1}/{0}", branch, name);
+            repo.remote_with_fetch(name, url, &refspec)
+        });
+    }
+
+    // Cloned from the host the ssh config points at, with the remote keeping
+    // the URL as given
+    let resolved_url = resolve_ssh_url(url);
+    let repo = with_tls_settings(&config, url, observer, || {
+        builder.clone(&resolved_url, path).inspect_err(|_| {
+            remove_partial_clone(path, existed);
+        })
+    })?;
+    // A clone that fails or is cancelled while being set up goes as well
+    if let Err(e) = finish_clone(&repo, url, &resolved_url, options, observer) {
+        drop(repo);
+        remove_partial_clone(path, existed);
+        return Err(e);
+    }
+    Ok(repo)
+}
+
+fn finish_clone(
+    repo: &Repository,
+    url: &str,
+    resolved_url: &str,
+    options: &CloneOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<(), GitClientError> {
+    if resolved_url != url {
+        repo.remote_set_url("origin", url)?;
+    }
+    if !repo.is_bare() && !options.no_checkout {
+        smudge_lfs_files(repo)?;
+        if options.recurse_submodules {
+            update_submodules(repo, &[], true, true, observer)?;
+        }
+    }
+    Ok(())
+}
+
+// Fetches more history into a shallow clone; `depth` of None removes the
+// shallow boundary entirely, like `git fetch --unshallow`.
+pub fn deepen_repo(
+    repo: &Repository,
+    remote_name: &str,
+    depth: Option<u32>,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<(), GitClientError> {
+    if !repo.is_shallow() {
+        return Err(GitClientError::Operation(
+            "Repository is not a shallow clone".to_string(),
+        ));
+    }
+    let ssh_repo = open_with_ssh_config(repo)?;
+    let mut remote = ssh_repo.as_ref().unwrap_or(repo).find_remote(remote_name)?;
+    let config = repo.config()?;
+    let url = remote.url().unwrap_or_default().to_string();
+    let mut fetch_opts = get_fetch_options(&config, &url, observer);
+    fetch_opts.depth(depth.map(fetch_depth).unwrap_or(UNSHALLOW_DEPTH));
+    with_tls_settings(&config, &url, observer, || {
+        remote.fetch::<&str>(&[], Some(&mut fetch_opts), None)
+    })?;
+    Ok(())
+}
+
+// Removes whatever a failed or cancelled clone left behind. An empty
+// directory the user picked beforehand is kept, but emptied again.
+fn remove_partial_clone(path: &Path, existed: bool) {
+    if !existed {
+        let _ = std::fs::remove_dir_all(path);
+        return;
+    }
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            let _ = if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                std::fs::remove_dir_all(&entry_path)
+            } else {
+                std::fs::remove_file(&entry_path)
+            };
+        }
+    }
 }
 
 pub fn get_repo_info(repo: &Repository) -> Result<RepoInfo, GitClientError> {
-    let path = repo
-        .workdir()
-        .or_else(|| repo.path().parent())
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
+    // A bare repository is its own git directory
+    let path = if repo.is_bare() {
+        Some(repo.path())
+    } else {
+        repo.workdir().or_else(|| repo.path().parent())
+    }
+    .map(|p| p.to_string_lossy().to_string())
+    .unwrap_or_default();
 
     let name = Path::new(&path)
         .file_name()
@@ -81,6 +269,7 @@ pub fn get_repo_info(repo: &Repository) -> Result<RepoInfo, GitClientError> {
         is_bare: repo.is_bare(),
         head_name,
         head_oid,
+        is_shallow: repo.is_shallow(),
     })
 }
 
@@ -102,7 +291,13 @@ fn format_status(status: git2::Status) -> String {
     }
 }
 
-fn create_file_status(path: &str, status: git2::Status, staged: bool) -> FileStatus {
+fn create_file_status(
+    path: &str,
+    status: git2::Status,
+    staged: bool,
+    submodule_paths: &HashSet<String>,
+    is_lfs: bool,
+) -> FileStatus {
     FileStatus {
         path: path.to_string(),
         status: format_status(status),
@@ -112,6 +307,8 @@ fn create_file_status(path: &str, status: git2::Status, staged: bool) -> FileSta
         is_deleted: status.is_index_deleted() || status.is_wt_deleted(),
         is_renamed: status.is_index_renamed() || status.is_wt_renamed(),
         is_conflicted: status.is_conflicted(),
+        is_submodule: submodule_paths.contains(path),
+        is_lfs,
     }
 }
 
@@ -122,19 +319,36 @@ pub fn get_status(repo: &Repository) -> Result<RepoStatus, GitClientError> {
         .include_ignored(false)
         .include_unmodified(false);
 
-    let statuses = repo.statuses(Some(&mut opts))?;
+    // Files left out by a sparse checkout are missing, not deleted
+    let statuses = sparse_aware_statuses(repo, &mut opts)?;
+    let submodule_paths: HashSet<String> = repo
+        .submodules()
+        .unwrap_or_default()
+        .iter()
+        .map(|s| s.path().to_string_lossy().to_string())
+        .collect();
 
     let mut staged = Vec::new();
     let mut unstaged = Vec::new();
     let mut untracked = Vec::new();
     let mut conflicted = Vec::new();
 
-    for entry in statuses.iter() {
-        let path = entry.path().unwrap_or("").to_string();
-        let status = entry.status();
+    for (path, mut status) in statuses {
+        let is_lfs = is_lfs_tracked(repo, &path);
+        // libgit2 doesn't run the clean filter, so smudged LFS files always
+        // differ from their pointer
+        if is_lfs && status.is_wt_modified() && lfs_matches_index(repo, &path) {
+            status.remove(Status::WT_MODIFIED);
+        }
 
         if status.is_conflicted() {
-            conflicted.push(create_file_status(&path, status, false));
+            conflicted.push(create_file_status(
+                &path,
+                status,
+                false,
+                &submodule_paths,
+                is_lfs,
+            ));
             continue;
         }
 
@@ -145,7 +359,13 @@ pub fn get_status(repo: &Repository) -> Result<RepoStatus, GitClientError> {
             || status.is_index_renamed()
             || status.is_index_typechange()
         {
-            staged.push(create_file_status(&path, status, true));
+            staged.push(create_file_status(
+                &path,
+                status,
+                true,
+                &submodule_paths,
+                is_lfs,
+            ));
         }
 
         // Check for unstaged changes (working tree)
@@ -154,12 +374,24 @@ pub fn get_status(repo: &Repository) -> Result<RepoStatus, GitClientError> {
             || status.is_wt_renamed()
             || status.is_wt_typechange()
         {
-            unstaged.push(create_file_status(&path, status, false));
+            unstaged.push(create_file_status(
+                &path,
+                status,
+                false,
+                &submodule_paths,
+                is_lfs,
+            ));
         }
 
         // Check for untracked files
         if status.is_wt_new() {
-            untracked.push(create_file_status(&path, status, false));
+            untracked.push(create_file_status(
+                &path,
+                status,
+                false,
+                &submodule_paths,
+                is_lfs,
+            ));
         }
     }
 
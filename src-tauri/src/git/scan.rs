@@ -0,0 +1,142 @@
+use crate::error::GitClientError;
+use crate::git::credentials::is_worktree_dirty;
+use crate::git::merge::list_branches;
+use crate::git::repository::{get_repo_info, RepoInfo};
+use git2::{Repository, RepositoryOpenFlags};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+// Dependency and build directories that never contain checkouts worth listing
+const SKIPPED_DIRS: &[&str] = &[
+    "node_modules",
+    "bower_components",
+    "vendor",
+    "target",
+    "__pycache__",
+    ".venv",
+    "venv",
+    ".tox",
+    ".gradle",
+    "Pods",
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ScannedRepo {
+    #[serde(flatten)]
+    pub info: RepoInfo,
+    pub is_worktree: bool,
+    pub is_dirty: bool,
+    pub upstream: Option<String>,
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+    // Local branches with commits their upstream does not have
+    pub unpushed_branches: Vec<String>,
+}
+
+fn looks_like_bare_repo(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+fn open_exact(dir: &Path) -> Option<Repository> {
+    Repository::open_ext(dir, RepositoryOpenFlags::NO_SEARCH, &[] as &[&OsStr]).ok()
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn describe(repo: &Repository) -> Result<ScannedRepo, GitClientError> {
+    let info = get_repo_info(repo)?;
+    let branches = list_branches(repo)?;
+    let head = branches.iter().find(|b| b.is_head && !b.is_remote);
+
+    Ok(ScannedRepo {
+        info,
+        is_worktree: repo.is_worktree(),
+        is_dirty: !repo.is_bare() && is_worktree_dirty(repo)?,
+        upstream: head.and_then(|b| b.upstream.clone()),
+        ahead: head.and_then(|b| b.ahead),
+        behind: head.and_then(|b| b.behind),
+        unpushed_branches: branches
+            .iter()
+            .filter(|b| !b.is_remote && b.ahead.unwrap_or(0) > 0)
+            .map(|b| b.name.clone())
+            .collect(),
+    })
+}
+
+// Walks `root` looking for repositories. A checkout's working tree is not
+// descended into; its linked worktrees are added even when they live outside
+// `root`. Symlinks are not followed.
+pub fn scan_for_repositories(
+    root: &Path,
+    max_depth: Option<usize>,
+) -> Result<Vec<ScannedRepo>, GitClientError> {
+    if !root.is_dir() {
+        return Err(GitClientError::InvalidPath(root.display().to_string()));
+    }
+
+    let mut seen_git_dirs = HashSet::new();
+    let mut found = Vec::new();
+    let mut record = |repo: Repository, found: &mut Vec<ScannedRepo>| {
+        if !seen_git_dirs.insert(canonical(repo.path())) {
+            return;
+        }
+        let linked: Vec<PathBuf> = repo
+            .worktrees()
+            .map(|names| {
+                names
+                    .iter()
+                    .flatten()
+                    .filter_map(|name| repo.find_worktree(name).ok())
+                    .map(|worktree| worktree.path().to_path_buf())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        match describe(&repo) {
+            Ok(scanned) => found.push(scanned),
+            Err(e) => eprintln!("Skipping repository at {}: {e}", repo.path().display()),
+        }
+
+        for path in linked {
+            if let Some(worktree_repo) = open_exact(&path) {
+                if seen_git_dirs.insert(canonical(worktree_repo.path())) {
+                    if let Ok(scanned) = describe(&worktree_repo) {
+                        found.push(scanned);
+                    }
+                }
+            }
+        }
+    };
+
+    let mut stack = vec![(root.to_path_buf(), 0usize)];
+    while let Some((dir, depth)) = stack.pop() {
+        if dir.join(".git").exists() || looks_like_bare_repo(&dir) {
+            if let Some(repo) = open_exact(&dir) {
+                record(repo, &mut found);
+                continue;
+            }
+        }
+        if max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            let name = entry.file_name();
+            if !is_dir || name == ".git" || SKIPPED_DIRS.iter().any(|s| name == *s) {
+                continue;
+            }
+            stack.push((entry.path(), depth + 1));
+        }
+    }
+
+    found.sort_by(|a, b| a.info.path.cmp(&b.info.path));
+    Ok(found)
+}
@@ -0,0 +1,725 @@
+use crate::error::GitClientError;
+use git2::build::CheckoutBuilder;
+use git2::{IndexEntryExtendedFlag, Repository, Status, StatusOptions};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+// Written by `enable_sparse_checkout` when no patterns are given: only the
+// files at the top level of the repository
+const DEFAULT_PATTERNS: &[&str] = &["/*", "!/*/"];
+
+const GITLINK_MODE: u32 = 0o160000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SparseCheckoutInfo {
+    pub enabled: bool,
+    pub cone: bool,
+    // Directories in cone mode, gitignore-style patterns otherwise
+    pub patterns: Vec<String>,
+    // Files outside the sparse set that stay in the working tree because they
+    // have local changes
+    pub kept_paths: Vec<String>,
+}
+
+struct SparsePattern {
+    glob: String,
+    negated: bool,
+    anchored: bool,
+    dir_only: bool,
+}
+
+enum SparseMatcher {
+    // Recursively included directories
+    Cone(Vec<String>),
+    Patterns(Vec<SparsePattern>),
+}
+
+fn sparse_file(repo: &Repository) -> PathBuf {
+    repo.path().join("info").join("sparse-checkout")
+}
+
+fn config_flag(repo: &Repository, key: &str) -> bool {
+    repo.config()
+        .and_then(|config| config.get_bool(key))
+        .unwrap_or(false)
+}
+
+pub fn is_sparse_checkout(repo: &Repository) -> bool {
+    config_flag(repo, "core.sparseCheckout")
+}
+
+fn read_lines(repo: &Repository) -> Result<Vec<String>, GitClientError> {
+    match std::fs::read_to_string(sparse_file(repo)) {
+        Ok(contents) => Ok(contents
+            .lines()
+            .map(|l| l.trim_end().to_string())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_lines(repo: &Repository, lines: &[String]) -> Result<(), GitClientError> {
+    let file = sparse_file(repo);
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    std::fs::write(file, contents)?;
+    Ok(())
+}
+
+// Recovers the directory list from a file written in cone format. Returns None
+// if the file contains patterns cone mode can't express.
+fn parse_cone(lines: &[String]) -> Option<Vec<String>> {
+    let mut dirs = Vec::new();
+    let mut parents = HashSet::new();
+    for line in lines {
+        match line.as_str() {
+            "/*" | "!/*/" => {}
+            l if l.starts_with("!/") && l.ends_with("/*/") && l.len() > 5 => {
+                parents.insert(&l[2..l.len() - 3]);
+            }
+            l if l.starts_with('/') && l.ends_with('/') && l.len() > 2 && !l.contains('*') => {
+                dirs.push(&l[1..l.len() - 1]);
+            }
+            _ => return None,
+        }
+    }
+    Some(
+        dirs.into_iter()
+            .filter(|d| !parents.contains(d))
+            .map(|d| d.to_string())
+            .collect(),
+    )
+}
+
+fn normalize_dir(dir: &str) -> Result<String, GitClientError> {
+    let trimmed = dir.trim().trim_matches('/');
+    let valid = !trimmed.is_empty()
+        && !trimmed.contains(['*', '?', '[', '\\', '!'])
+        && Path::new(trimmed)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if valid {
+        Ok(trimmed.to_string())
+    } else {
+        Err(GitClientError::Operation(format!(
+            "'{}' is not a valid directory for a cone-mode sparse checkout",
+            dir
+        )))
+    }
+}
+
+// Drops directories already covered by one of their ancestors
+fn minimal_dirs(dirs: BTreeSet<String>) -> Vec<String> {
+    dirs.iter()
+        .filter(|d| {
+            !dirs
+                .iter()
+                .any(|other| d.starts_with(other.as_str()) && d[other.len()..].starts_with('/'))
+        })
+        .cloned()
+        .collect()
+}
+
+fn cone_lines(dirs: &[String]) -> Vec<String> {
+    let recursive: BTreeSet<&str> = dirs.iter().map(|d| d.as_str()).collect();
+    let mut all: BTreeSet<&str> = recursive.clone();
+    for dir in dirs {
+        for (i, _) in dir.match_indices('/') {
+            all.insert(&dir[..i]);
+        }
+    }
+
+    let mut lines: Vec<String> = DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect();
+    for dir in all {
+        lines.push(format!("/{}/", dir));
+        if !recursive.contains(dir) {
+            lines.push(format!("!/{}/*/", dir));
+        }
+    }
+    lines
+}
+
+// gitignore-style glob matching: `*` and `?` don't cross `/`, `**` does
+pub(crate) fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => match pattern[2..].strip_prefix(b"/") {
+            Some(rest) => (0..=text.len())
+                .any(|i| (i == 0 || text[i - 1] == b'/') && wildmatch(rest, &text[i..])),
+            None => (0..=text.len()).any(|i| wildmatch(&pattern[2..], &text[i..])),
+        },
+        Some(b'*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| wildmatch(&pattern[1..], &text[i..])),
+        Some(b'?') => !text.is_empty() && text[0] != b'/' && wildmatch(&pattern[1..], &text[1..]),
+        Some(b'[') => match pattern.iter().skip(2).position(|&c| c == b']') {
+            Some(pos) => {
+                let class = &pattern[1..pos + 2];
+                let rest = &pattern[pos + 3..];
+                let Some(&c) = text.first() else {
+                    return false;
+                };
+                let (negated, class) = match class.first() {
+                    Some(b'!') | Some(b'^') => (true, &class[1..]),
+                    _ => (false, class),
+                };
+                let mut matched = false;
+                let mut i = 0;
+                while i < class.len() {
+                    if i + 2 < class.len() && class[i + 1] == b'-' {
+                        matched |= class[i] <= c && c <= class[i + 2];
+                        i += 3;
+                    } else {
+                        matched |= class[i] == c;
+                        i += 1;
+                    }
+                }
+                c != b'/' && matched != negated && wildmatch(rest, &text[1..])
+            }
+            None => text.first() == Some(&b'[') && wildmatch(&pattern[1..], &text[1..]),
+        },
+        Some(b'\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && wildmatch(&pattern[2..], &text[1..])
+        }
+        Some(&c) => text.first() == Some(&c) && wildmatch(&pattern[1..], &text[1..]),
+    }
+}
+
+impl SparsePattern {
+    fn parse(line: &str) -> Option<Self> {
+        let (negated, rest) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let dir_only = rest.ends_with('/');
+        let rest = rest.trim_end_matches('/');
+        let anchored = rest.contains('/');
+        let glob = rest.trim_start_matches('/');
+        if glob.is_empty() {
+            return None;
+        }
+        Some(Self {
+            glob: glob.to_string(),
+            negated,
+            anchored,
+            dir_only,
+        })
+    }
+
+    // A pattern matching a directory matches everything below it
+    fn matches(&self, path: &str) -> bool {
+        let dirs = path.match_indices('/').map(|(i, _)| (&path[..i], true));
+        dirs.chain(std::iter::once((path, false)))
+            .any(|(candidate, is_dir)| {
+                if self.dir_only && !is_dir {
+                    return false;
+                }
+                let target = if self.anchored {
+                    candidate
+                } else {
+                    candidate.rsplit('/').next().unwrap_or(candidate)
+                };
+                wildmatch(self.glob.as_bytes(), target.as_bytes())
+            })
+    }
+}
+
+impl SparseMatcher {
+    fn includes(&self, path: &str) -> bool {
+        match self {
+            SparseMatcher::Cone(dirs) => match path.rsplit_once('/') {
+                // Top-level files are always included
+                None => true,
+                Some((parent, _)) => dirs.iter().any(|dir| {
+                    (path.starts_with(dir.as_str()) && path[dir.len()..].starts_with('/'))
+                        || (dir.starts_with(parent) && dir[parent.len()..].starts_with('/'))
+                }),
+            },
+            SparseMatcher::Patterns(patterns) => patterns
+                .iter()
+                .rev()
+                .find(|p| p.matches(path))
+                .is_some_and(|p| !p.negated),
+        }
+    }
+}
+
+// The active matcher, or None when sparse checkout is disabled. Cone mode falls
+// back to pattern matching if the file isn't in cone format, as git does.
+fn load_matcher(repo: &Repository) -> Result<Option<SparseMatcher>, GitClientError> {
+    if !is_sparse_checkout(repo) {
+        return Ok(None);
+    }
+    let lines = read_lines(repo)?;
+    if config_flag(repo, "core.sparseCheckoutCone") {
+        if let Some(dirs) = parse_cone(&lines) {
+            return Ok(Some(SparseMatcher::Cone(dirs)));
+        }
+    }
+    Ok(Some(SparseMatcher::Patterns(
+        lines
+            .iter()
+            .filter_map(|l| SparsePattern::parse(l))
+            .collect(),
+    )))
+}
+
+// Index paths marked skip-worktree, i.e. left out of the working tree on
+// purpose. Their missing files are not deletions.
+pub fn sparse_skipped_paths(repo: &Repository) -> Result<HashSet<String>, git2::Error> {
+    let index = repo.index()?;
+    Ok(index
+        .iter()
+        .filter(|entry| entry.flags_extended & IndexEntryExtendedFlag::SKIP_WORKTREE.bits() != 0)
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .collect())
+}
+
+// Statuses by path, without the deletions libgit2 reports for files a sparse
+// checkout left out. Paths left with no status are dropped.
+pub fn sparse_aware_statuses(
+    repo: &Repository,
+    opts: &mut StatusOptions,
+) -> Result<Vec<(String, Status)>, git2::Error> {
+    let skipped = sparse_skipped_paths(repo)?;
+    Ok(repo
+        .statuses(Some(opts))?
+        .iter()
+        .filter_map(|entry| {
+            let path = entry.path().unwrap_or("").to_string();
+            let mut status = entry.status();
+            if skipped.contains(&path) {
+                status.remove(Status::WT_DELETED);
+            }
+            (!status.is_empty()).then_some((path, status))
+        })
+        .collect())
+}
+
+fn locally_modified_paths(repo: &Repository) -> Result<HashSet<String>, GitClientError> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let modified = Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_TYPECHANGE;
+    Ok(repo
+        .statuses(Some(&mut opts))?
+        .iter()
+        .filter(|entry| entry.status().intersects(modified))
+        .filter_map(|entry| entry.path().map(|p| p.to_string()))
+        .collect())
+}
+
+fn remove_with_empty_parents(workdir: &Path, path: &str) -> Result<(), GitClientError> {
+    let full_path = workdir.join(path);
+    match std::fs::remove_file(&full_path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let mut dir = full_path.parent();
+    while let Some(d) = dir {
+        if d == workdir || std::fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+    Ok(())
+}
+
+// Brings the working tree in line with the sparse set: files outside it get
+// the skip-worktree bit and are removed, skipped files inside it are restored.
+// Files with local changes are never removed. With sparse checkout disabled
+// every file is restored.
+fn apply_sparse_checkout(repo: &Repository) -> Result<(), GitClientError> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| {
+            GitClientError::Operation("Sparse checkout needs a working tree".to_string())
+        })?
+        .to_path_buf();
+    let matcher = load_matcher(repo)?;
+    let modified = locally_modified_paths(repo)?;
+    let skip_worktree = IndexEntryExtendedFlag::SKIP_WORKTREE.bits();
+
+    let mut index = repo.index()?;
+    let entries: Vec<_> = index.iter().collect();
+    let mut restore = Vec::new();
+    let mut remove = Vec::new();
+    for mut entry in entries {
+        // Leave conflicts and submodules alone
+        let stage = (entry.flags >> 12) & 0x3;
+        if stage != 0 || entry.mode == GITLINK_MODE {
+            continue;
+        }
+        let path = String::from_utf8_lossy(&entry.path).to_string();
+        let skipped = entry.flags_extended & skip_worktree != 0;
+        let included = matcher.as_ref().is_none_or(|m| m.includes(&path));
+
+        if included && skipped {
+            entry.flags_extended &= !skip_worktree;
+            index.add(&entry)?;
+            restore.push(path);
+        } else if !included && !skipped && !modified.contains(&path) {
+            entry.flags_extended |= skip_worktree;
+            index.add(&entry)?;
+            remove.push(path);
+        }
+    }
+    index.write()?;
+
+    for path in &remove {
+        remove_with_empty_parents(&workdir, path)?;
+    }
+
+    // An empty path list would check out everything
+    if !restore.is_empty() {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.recreate_missing(true);
+        for path in &restore {
+            checkout.path(path);
+        }
+        repo.checkout_index(Some(&mut index), Some(&mut checkout))?;
+    }
+
+    Ok(())
+}
+
+// Re-applies the sparse set after an operation that checked out files, since
+// libgit2 writes the whole tree
+pub fn reapply_sparse_checkout(repo: &Repository) -> Result<(), GitClientError> {
+    if is_sparse_checkout(repo) {
+        apply_sparse_checkout(repo)?;
+    }
+    Ok(())
+}
+
+pub fn get_sparse_checkout(repo: &Repository) -> Result<SparseCheckoutInfo, GitClientError> {
+    let enabled = is_sparse_checkout(repo);
+    let lines = read_lines(repo)?;
+    let cone = config_flag(repo, "core.sparseCheckoutCone");
+    let patterns = if cone {
+        parse_cone(&lines).unwrap_or(lines)
+    } else {
+        lines
+    };
+
+    let kept_paths = match load_matcher(repo)? {
+        Some(matcher) => {
+            let skipped = sparse_skipped_paths(repo)?;
+            let index = repo.index()?;
+            let mut kept: Vec<String> = index
+                .iter()
+                .filter(|entry| (entry.flags >> 12) & 0x3 == 0 && entry.mode != GITLINK_MODE)
+                .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+                .filter(|path| !skipped.contains(path) && !matcher.includes(path))
+                .collect();
+            kept.sort();
+            kept
+        }
+        None => Vec::new(),
+    };
+
+    Ok(SparseCheckoutInfo {
+        enabled,
+        cone,
+        patterns,
+        kept_paths,
+    })
+}
+
+fn ensure_enabled(repo: &Repository) -> Result<(), GitClientError> {
+    if is_sparse_checkout(repo) {
+        Ok(())
+    } else {
+        Err(GitClientError::Operation(
+            "Sparse checkout is not enabled".to_string(),
+        ))
+    }
+}
+
+fn is_cone(repo: &Repository) -> bool {
+    config_flag(repo, "core.sparseCheckoutCone")
+        && parse_cone(&read_lines(repo).unwrap_or_default()).is_some()
+}
+
+fn write_patterns(
+    repo: &Repository,
+    cone: bool,
+    patterns: &[String],
+) -> Result<(), GitClientError> {
+    if cone {
+        let dirs = patterns
+            .iter()
+            .map(|p| normalize_dir(p))
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        write_lines(repo, &cone_lines(&minimal_dirs(dirs)))
+    } else if patterns.is_empty() {
+        let defaults: Vec<String> = DEFAULT_PATTERNS.iter().map(|p| p.to_string()).collect();
+        write_lines(repo, &defaults)
+    } else {
+        let lines: Vec<String> = patterns
+            .iter()
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect();
+        write_lines(repo, &lines)
+    }
+}
+
+// Turns sparse checkout on with the given directories (cone mode) or patterns,
+// replacing any existing set. Without patterns only top-level files remain.
+pub fn enable_sparse_checkout(
+    repo: &Repository,
+    cone: bool,
+    patterns: &[String],
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    if repo.is_bare() {
+        return Err(GitClientError::Operation(
+            "Sparse checkout needs a working tree".to_string(),
+        ));
+    }
+    write_patterns(repo, cone, patterns)?;
+    let mut config = repo.config()?;
+    config.set_bool("core.sparseCheckout", true)?;
+    config.set_bool("core.sparseCheckoutCone", cone)?;
+    apply_sparse_checkout(repo)?;
+    get_sparse_checkout(repo)
+}
+
+// Restores every skipped file. The pattern file is kept so that sparse
+// checkout can be turned back on later.
+pub fn disable_sparse_checkout(repo: &Repository) -> Result<SparseCheckoutInfo, GitClientError> {
+    repo.config()?.set_bool("core.sparseCheckout", false)?;
+    apply_sparse_checkout(repo)?;
+    get_sparse_checkout(repo)
+}
+
+pub fn set_sparse_patterns(
+    repo: &Repository,
+    patterns: &[String],
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    ensure_enabled(repo)?;
+    write_patterns(repo, is_cone(repo), patterns)?;
+    apply_sparse_checkout(repo)?;
+    get_sparse_checkout(repo)
+}
+
+pub fn add_sparse_patterns(
+    repo: &Repository,
+    patterns: &[String],
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    ensure_enabled(repo)?;
+    let mut current = get_sparse_checkout(repo)?.patterns;
+    for pattern in patterns {
+        if !current.contains(pattern) {
+            current.push(pattern.clone());
+        }
+    }
+    set_sparse_patterns(repo, &current)
+}
+
+pub fn remove_sparse_patterns(
+    repo: &Repository,
+    patterns: &[String],
+) -> Result<SparseCheckoutInfo, GitClientError> {
+    ensure_enabled(repo)?;
+    let cone = is_cone(repo);
+    let normalize = |p: &str| {
+        if cone {
+            p.trim().trim_matches('/').to_string()
+        } else {
+            p.trim().to_string()
+        }
+    };
+    let removed: HashSet<String> = patterns.iter().map(|p| normalize(p)).collect();
+    let current: Vec<String> = get_sparse_checkout(repo)?
+        .patterns
+        .into_iter()
+        .filter(|p| !removed.contains(&normalize(p)))
+        .collect();
+    set_sparse_patterns(repo, &current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::credentials::{is_worktree_dirty, pull_remote, FetchRemoteOptions};
+    use git2::Signature;
+
+    fn commit_files(repo: &Repository, files: &[(&str, &str)], message: &str) {
+        let workdir = repo.workdir().unwrap();
+        let mut index = repo.index().unwrap();
+        for (path, contents) in files {
+            let full_path = workdir.join(path);
+            std::fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+            std::fs::write(full_path, contents).unwrap();
+            index.add_path(Path::new(path)).unwrap();
+        }
+        index.write().unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn pulls_into_a_sparse_checkout() {
+        let dir = std::env::temp_dir().join(format!("forked-sparse-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let upstream = Repository::init(dir.join("upstream")).unwrap();
+        commit_files(
+            &upstream,
+            &[("top.txt", "1\n"), ("docs/guide.txt", "guide\n")],
+            "Initial",
+        );
+        let repo = Repository::clone(
+            upstream.workdir().unwrap().to_str().unwrap(),
+            dir.join("clone"),
+        )
+        .unwrap();
+        let workdir = repo.workdir().unwrap().to_path_buf();
+
+        enable_sparse_checkout(&repo, true, &[]).unwrap();
+        assert!(!workdir.join("docs/guide.txt").exists());
+        assert!(!is_worktree_dirty(&repo).unwrap());
+
+        commit_files(&upstream, &[("top.txt", "2\n")], "Update");
+        let branch = repo.head().unwrap().shorthand().unwrap().to_string();
+        let result = pull_remote(
+            &repo,
+            "origin",
+            &branch,
+            &FetchRemoteOptions::default(),
+            None,
+        )
+        .unwrap();
+        assert!(result.fast_forward);
+        assert_eq!(
+            std::fs::read_to_string(workdir.join("top.txt")).unwrap(),
+            "2\n"
+        );
+        assert!(!workdir.join("docs/guide.txt").exists());
+        assert!(!is_worktree_dirty(&repo).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn patterns(lines: &[&str]) -> SparseMatcher {
+        SparseMatcher::Patterns(
+            lines
+                .iter()
+                .filter_map(|l| SparsePattern::parse(l))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(wildmatch(b"*.txt", b"notes.txt"));
+        assert!(!wildmatch(b"*.txt", b"docs/notes.txt"));
+        assert!(wildmatch(b"file?.rs", b"file1.rs"));
+        assert!(!wildmatch(b"a?b", b"a/b"));
+        assert!(wildmatch(b"**/notes.txt", b"notes.txt"));
+        assert!(wildmatch(b"**/notes.txt", b"a/b/notes.txt"));
+        assert!(!wildmatch(b"**/notes.txt", b"a/bnotes.txt"));
+        assert!(wildmatch(b"docs/**", b"docs/a/b.txt"));
+        assert!(wildmatch(b"a/**/b", b"a/b"));
+        assert!(wildmatch(b"a/**/b", b"a/x/y/b"));
+        assert!(wildmatch(b"\\*", b"*"));
+        assert!(!wildmatch(b"\\*", b"x"));
+    }
+
+    #[test]
+    fn matches_character_classes() {
+        assert!(wildmatch(b"[abc].txt", b"b.txt"));
+        assert!(!wildmatch(b"[abc].txt", b"d.txt"));
+        assert!(wildmatch(b"v[0-9]", b"v7"));
+        assert!(!wildmatch(b"v[0-9]", b"vx"));
+        assert!(wildmatch(b"[!a-c]x", b"dx"));
+        assert!(!wildmatch(b"[^a-c]x", b"bx"));
+        assert!(wildmatch(b"[]]", b"]"));
+        assert!(!wildmatch(b"a[/]b", b"a/b"));
+        // An unterminated class matches a literal bracket
+        assert!(wildmatch(b"[ab", b"[ab"));
+    }
+
+    #[test]
+    fn anchors_patterns_containing_a_slash() {
+        let unanchored = SparsePattern::parse("build").unwrap();
+        assert!(!unanchored.anchored);
+        assert!(unanchored.matches("build"));
+        assert!(unanchored.matches("src/build/out.o"));
+
+        let anchored = SparsePattern::parse("/build").unwrap();
+        assert!(anchored.anchored);
+        assert!(anchored.matches("build/out.o"));
+        assert!(!anchored.matches("src/build/out.o"));
+
+        let nested = SparsePattern::parse("src/*.rs").unwrap();
+        assert!(nested.anchored);
+        assert!(nested.matches("src/lib.rs"));
+        assert!(!nested.matches("x/src/lib.rs"));
+
+        let dir_only = SparsePattern::parse("logs/").unwrap();
+        assert!(dir_only.matches("logs/today.txt"));
+        assert!(!dir_only.matches("logs"));
+
+        assert!(SparsePattern::parse("/").is_none());
+    }
+
+    #[test]
+    fn applies_the_last_matching_pattern() {
+        let matcher = patterns(&["/*", "!/*/", "/docs/", "!/docs/drafts/"]);
+        assert!(matcher.includes("README.md"));
+        assert!(!matcher.includes("src/lib.rs"));
+        assert!(matcher.includes("docs/guide.md"));
+        assert!(!matcher.includes("docs/drafts/next.md"));
+
+        let escaped = SparsePattern::parse("\\!important").unwrap();
+        assert!(!escaped.negated);
+        assert!(escaped.matches("!important"));
+    }
+
+    #[test]
+    fn round_trips_cone_directories() {
+        let dirs = vec!["docs".to_string(), "src/app/ui".to_string()];
+        let lines = cone_lines(&dirs);
+        assert_eq!(
+            lines,
+            vec![
+                "/*",
+                "!/*/",
+                "/docs/",
+                "/src/",
+                "!/src/*/",
+                "/src/app/",
+                "!/src/app/*/",
+                "/src/app/ui/",
+            ]
+        );
+        assert_eq!(parse_cone(&lines), Some(dirs));
+
+        let matcher = SparseMatcher::Cone(parse_cone(&lines).unwrap());
+        assert!(matcher.includes("top.txt"));
+        assert!(matcher.includes("docs/a/b.txt"));
+        assert!(matcher.includes("src/main.rs"));
+        assert!(!matcher.includes("src/other/x.rs"));
+        assert!(matcher.includes("src/app/ui/view.rs"));
+
+        assert_eq!(parse_cone(&["/*".to_string(), "*.txt".to_string()]), None);
+        assert_eq!(parse_cone(&["/docs/*/".to_string()]), None);
+    }
+}
@@ -0,0 +1,265 @@
+use crate::error::GitClientError;
+use crate::git::history::{
+    ensure_clean_worktree, ensure_no_merge_commits, prepare_history_rewrite, try_pick_tree,
+    update_rewritten_head,
+};
+use git2::build::CheckoutBuilder;
+use git2::{Commit, Oid, Repository, ResetType, Status, StatusOptions};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const SPLIT_STATE_FILE: &str = "forked-split.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SplitState {
+    pub head_name: String,
+    pub orig_head: String,
+    pub split_commit: String,
+    pub base_commit: String,
+    // Commits that came after the split commit, oldest first
+    pub replay_commits: Vec<String>,
+    // Original message, offered as a starting point for the new commits
+    pub message: String,
+    // The replayed commit that stopped on conflicts, left for the user to
+    // resolve before continuing
+    #[serde(default)]
+    pub conflicted_commit: Option<String>,
+}
+
+fn split_state_path(repo: &Repository) -> PathBuf {
+    repo.path().join(SPLIT_STATE_FILE)
+}
+
+fn parse_oid(id: &str) -> Result<Oid, GitClientError> {
+    Oid::from_str(id).map_err(|e| GitClientError::Operation(format!("Invalid commit ID: {}", e)))
+}
+
+fn write_split_state(repo: &Repository, state: &SplitState) -> Result<(), GitClientError> {
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| GitClientError::Operation(format!("Failed to save split state: {}", e)))?;
+    std::fs::write(split_state_path(repo), json)?;
+    Ok(())
+}
+
+pub fn get_split_state(repo: &Repository) -> Result<Option<SplitState>, GitClientError> {
+    let path = split_state_path(repo);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = std::fs::read_to_string(path)?;
+    let state = serde_json::from_str(&json)
+        .map_err(|e| GitClientError::Operation(format!("Corrupt split state: {}", e)))?;
+    Ok(Some(state))
+}
+
+fn require_split_state(repo: &Repository) -> Result<SplitState, GitClientError> {
+    get_split_state(repo)?
+        .ok_or_else(|| GitClientError::Operation("No split in progress".to_string()))
+}
+
+// Keeps other history rewrites from moving the branch out from under a split
+pub(crate) fn ensure_no_split_in_progress(
+    repo: &Repository,
+    action: &str,
+) -> Result<(), GitClientError> {
+    if split_state_path(repo).exists() {
+        return Err(GitClientError::Operation(format!(
+            "Cannot {} while a split is in progress; continue or abort it first",
+            action
+        )));
+    }
+    Ok(())
+}
+
+// Leaves the commit's changes unstaged in the working tree on top of its parent
+fn unstage_split_commit(
+    repo: &Repository,
+    head_name: &str,
+    commit: &Commit,
+    base: &Commit,
+) -> Result<(), GitClientError> {
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))?;
+    let mut branch_ref = repo.find_reference(head_name)?;
+    branch_ref.set_target(base.id(), "split commit: start")?;
+    repo.set_head(head_name)?;
+    repo.reset(base.as_object(), ResetType::Mixed, None)?;
+    Ok(())
+}
+
+pub fn start_split(repo: &Repository, commit_id: &str) -> Result<SplitState, GitClientError> {
+    let rewrite = prepare_history_rewrite(repo, "split a commit")?;
+    let oid = parse_oid(commit_id)?;
+    let position = rewrite
+        .first_parent_chain
+        .iter()
+        .position(|candidate| *candidate == oid)
+        .ok_or_else(|| {
+            GitClientError::Operation(
+                "Selected commit must be on the current branch first-parent history".to_string(),
+            )
+        })?;
+    ensure_no_merge_commits(
+        repo,
+        &rewrite.first_parent_chain[0..=position],
+        "split a commit",
+    )?;
+
+    let commit = repo.find_commit(oid)?;
+    if commit.parent_count() == 0 {
+        return Err(GitClientError::Operation(
+            "Cannot split the root commit".to_string(),
+        ));
+    }
+    let base = commit.parent(0)?;
+
+    let state = SplitState {
+        head_name: rewrite.head_name.clone(),
+        orig_head: rewrite.first_parent_chain[0].to_string(),
+        split_commit: oid.to_string(),
+        base_commit: base.id().to_string(),
+        replay_commits: rewrite.first_parent_chain[0..position]
+            .iter()
+            .rev()
+            .map(|oid| oid.to_string())
+            .collect(),
+        message: commit.message().unwrap_or("").to_string(),
+        conflicted_commit: None,
+    };
+    write_split_state(repo, &state)?;
+
+    // A split that didn't start is undone, so its state doesn't block other
+    // rewrites
+    if let Err(e) = unstage_split_commit(repo, &rewrite.head_name, &commit, &base) {
+        if abort_split(repo).is_err() {
+            let _ = std::fs::remove_file(split_state_path(repo));
+        }
+        return Err(e);
+    }
+
+    Ok(state)
+}
+
+// Commits the user's resolution of the conflicted commit with its original
+// author and message
+fn commit_resolution(repo: &Repository, id: &str, parent: &Commit) -> Result<Oid, GitClientError> {
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        return Err(GitClientError::Operation(
+            "Resolve and stage all conflicts before continuing the split".to_string(),
+        ));
+    }
+    let mut status_options = StatusOptions::new();
+    status_options.include_untracked(false);
+    let unstaged = repo
+        .statuses(Some(&mut status_options))?
+        .iter()
+        .any(|entry| {
+            entry.status().intersects(
+                Status::WT_MODIFIED
+                    | Status::WT_DELETED
+                    | Status::WT_TYPECHANGE
+                    | Status::WT_RENAMED,
+            )
+        });
+    if unstaged {
+        return Err(GitClientError::Operation(
+            "Stage or discard unstaged changes before continuing the split".to_string(),
+        ));
+    }
+
+    let original = repo.find_commit(parse_oid(id)?)?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let oid = repo.commit(
+        None,
+        &original.author(),
+        &original.committer(),
+        original.message().unwrap_or(""),
+        &tree,
+        &[parent],
+    )?;
+    repo.cleanup_state()?;
+    Ok(oid)
+}
+
+// Replays the commits that came after the split commit onto the new ones.
+// If one of them conflicts, the branch is left at the commits replayed so far
+// with the conflicts in the working tree, and the returned state names the
+// conflicting commit; continuing again commits the resolution and goes on.
+pub fn continue_split(repo: &Repository) -> Result<Option<SplitState>, GitClientError> {
+    let mut state = require_split_state(repo)?;
+
+    let head = repo.head()?;
+    if head.name() != Some(state.head_name.as_str()) {
+        return Err(GitClientError::Operation(format!(
+            "HEAD must be on {} to continue the split",
+            state.head_name
+        )));
+    }
+    let mut current_parent = head.peel_to_commit()?;
+    match state.conflicted_commit.take() {
+        Some(id) => {
+            let resolved = commit_resolution(repo, &id, &current_parent)?;
+            current_parent = repo.find_commit(resolved)?;
+        }
+        None => {
+            ensure_clean_worktree(repo, "continue the split")?;
+            let base_oid = parse_oid(&state.base_commit)?;
+            if current_parent.id() != base_oid
+                && !repo.graph_descendant_of(current_parent.id(), base_oid)?
+            {
+                return Err(GitClientError::Operation(
+                    "New commits must be based on the parent of the split commit".to_string(),
+                ));
+            }
+        }
+    }
+
+    while !state.replay_commits.is_empty() {
+        let id = state.replay_commits.remove(0);
+        let original = repo.find_commit(parse_oid(&id)?)?;
+        let Some(tree_id) = try_pick_tree(repo, &original, current_parent.tree_id())? else {
+            update_rewritten_head(
+                repo,
+                &state.head_name,
+                current_parent.id(),
+                "split commit: conflict",
+            )?;
+            // Cherry-picking onto the branch leaves the conflicts in the index
+            // and working tree for the user to resolve
+            repo.cherrypick(&original, None)?;
+            state.conflicted_commit = Some(id);
+            write_split_state(repo, &state)?;
+            return Ok(Some(state));
+        };
+        let tree = repo.find_tree(tree_id)?;
+        let rewritten_oid = repo.commit(
+            None,
+            &original.author(),
+            &original.committer(),
+            original.message().unwrap_or(""),
+            &tree,
+            &[&current_parent],
+        )?;
+        current_parent = repo.find_commit(rewritten_oid)?;
+    }
+
+    update_rewritten_head(
+        repo,
+        &state.head_name,
+        current_parent.id(),
+        "split commit: finish",
+    )?;
+    std::fs::remove_file(split_state_path(repo))?;
+    Ok(None)
+}
+
+pub fn abort_split(repo: &Repository) -> Result<(), GitClientError> {
+    let state = require_split_state(repo)?;
+    let orig_head = repo.find_commit(parse_oid(&state.orig_head)?)?;
+    // A hard reset also restores index entries for files the split left untracked
+    repo.set_head(&state.head_name)?;
+    repo.reset(orig_head.as_object(), ResetType::Hard, None)?;
+    repo.cleanup_state()?;
+    std::fs::remove_file(split_state_path(repo))?;
+    Ok(())
+}
@@ -0,0 +1,702 @@
+use git2::{ConfigLevel, Repository};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+const SYSTEM_SSH_CONFIG: &str = "/etc/ssh/ssh_config";
+
+// Nested Include directives deeper than this are ignored, like OpenSSH
+const MAX_INCLUDE_DEPTH: u32 = 16;
+
+// What ~/.ssh/config and /etc/ssh/ssh_config say about one host
+#[derive(Debug, Clone, Default)]
+pub struct SshHostConfig {
+    // The host as written in the remote URL, possibly an alias
+    pub host: String,
+    pub hostname: String,
+    pub port: u16,
+    pub user: Option<String>,
+    pub identity_files: Vec<PathBuf>,
+    // Only offer the identity files, never other keys from the agent
+    pub identities_only: bool,
+    // Name the host key is looked up under in known_hosts
+    pub host_key_alias: Option<String>,
+    // "yes", "no", "accept-new" or "ask"
+    pub strict_host_key_checking: Option<String>,
+    pub hash_known_hosts: bool,
+    pub user_known_hosts_files: Vec<PathBuf>,
+}
+
+// Settings as collected while reading the config files; the first value
+// obtained wins, except identity files, which accumulate
+#[derive(Default)]
+struct Collected {
+    hostname: Option<String>,
+    port: Option<u16>,
+    user: Option<String>,
+    identity_files: Vec<String>,
+    identities_only: Option<bool>,
+    host_key_alias: Option<String>,
+    strict_host_key_checking: Option<String>,
+    hash_known_hosts: Option<bool>,
+    user_known_hosts_files: Option<Vec<String>>,
+}
+
+// An SSH remote URL, either `ssh://[user@]host[:port]/path` or the scp-like
+// `[user@]host:path`
+#[derive(Debug, Clone)]
+struct SshUrl {
+    user: Option<String>,
+    host: String,
+    port: Option<u16>,
+    path: String,
+    scp: bool,
+}
+
+fn home_dir() -> PathBuf {
+    dirs::home_dir().unwrap_or_else(|| PathBuf::from("~"))
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None if path == "~" => home_dir(),
+        None => PathBuf::from(path),
+    }
+}
+
+// `*` matches any run of characters and `?` any single one
+pub(crate) fn wildcard_match(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => {
+            wildcard_match(&pattern[1..], text)
+                || (!text.is_empty() && wildcard_match(pattern, &text[1..]))
+        }
+        (Some(b'?'), Some(_)) => wildcard_match(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p.eq_ignore_ascii_case(t) => {
+            wildcard_match(&pattern[1..], &text[1..])
+        }
+        _ => false,
+    }
+}
+
+// A list of patterns matches if any pattern does and no negated one does
+pub(crate) fn match_pattern_list<'a>(
+    patterns: impl IntoIterator<Item = &'a str>,
+    host: &str,
+) -> bool {
+    let mut matched = false;
+    for pattern in patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if wildcard_match(negated.as_bytes(), host.as_bytes()) => return false,
+            Some(_) => {}
+            None => matched |= wildcard_match(pattern.as_bytes(), host.as_bytes()),
+        }
+    }
+    matched
+}
+
+// Splits a config line into its keyword and arguments. The keyword may be
+// followed by `=`, and arguments may be double-quoted.
+fn tokenize(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let split = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let keyword = line[..split].to_lowercase();
+    let rest = line[split..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if has_token {
+        args.push(current);
+    }
+    Some((keyword, args))
+}
+
+// Files named by an Include directive, which may use `*` in the file name.
+// Relative paths are taken from ~/.ssh for the user's config and /etc/ssh
+// for the system one.
+fn include_files(pattern: &str, base: &Path) -> Vec<PathBuf> {
+    let path = expand_tilde(pattern);
+    let path = if path.is_absolute() {
+        path
+    } else {
+        base.join(path)
+    };
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            wildcard_match(
+                name.as_bytes(),
+                entry.file_name().to_string_lossy().as_bytes(),
+            )
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
+fn parse_yes_no(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" | "true" => Some(true),
+        "no" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn read_config(file: &Path, base: &Path, host: &str, settings: &mut Collected, depth: u32) {
+    let Ok(contents) = std::fs::read_to_string(file) else {
+        return;
+    };
+    // Lines before the first Host or Match apply to every host
+    let mut active = true;
+    for line in contents.lines() {
+        let Some((keyword, args)) = tokenize(line) else {
+            continue;
+        };
+        let first = args.first().cloned();
+        match keyword.as_str() {
+            "host" => active = match_pattern_list(args.iter().map(|a| a.as_str()), host),
+            // Only `Match all` is understood; other criteria need the
+            // connection details ssh itself has, so those blocks are skipped
+            "match" => active = args.len() == 1 && args[0].eq_ignore_ascii_case("all"),
+            _ if !active => {}
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in &args {
+                    for included in include_files(pattern, base) {
+                        read_config(&included, base, host, settings, depth + 1);
+                    }
+                }
+            }
+            "hostname" => settings.hostname = settings.hostname.take().or(first),
+            "port" => {
+                settings.port = settings.port.or_else(|| first?.parse().ok());
+            }
+            "user" => settings.user = settings.user.take().or(first),
+            "identityfile" => settings.identity_files.extend(first),
+            "identitiesonly" => {
+                settings.identities_only = settings
+                    .identities_only
+                    .or_else(|| parse_yes_no(first.as_deref()?));
+            }
+            "hostkeyalias" => {
+                settings.host_key_alias = settings.host_key_alias.take().or(first);
+            }
+            "stricthostkeychecking" => {
+                settings.strict_host_key_checking =
+                    settings.strict_host_key_checking.take().or_else(|| {
+                        Some(match first?.to_lowercase().as_str() {
+                            "true" => "yes".to_string(),
+                            "false" | "off" => "no".to_string(),
+                            other => other.to_string(),
+                        })
+                    });
+            }
+            "hashknownhosts" => {
+                settings.hash_known_hosts = settings
+                    .hash_known_hosts
+                    .or_else(|| parse_yes_no(first.as_deref()?));
+            }
+            "userknownhostsfile" => {
+                settings.user_known_hosts_files = settings
+                    .user_known_hosts_files
+                    .take()
+                    .or(Some(args).filter(|a| !a.is_empty()));
+            }
+            _ => {}
+        }
+    }
+}
+
+// Expands the `%` tokens ssh allows in HostName and IdentityFile
+fn expand_tokens(value: &str, host: &str, hostname: &str, user: &str, port: u16) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some('h') => out.push_str(hostname),
+            Some('n') => out.push_str(host),
+            Some('p') => out.push_str(&port.to_string()),
+            Some('r') => out.push_str(user),
+            Some('d') => out.push_str(&home_dir().to_string_lossy()),
+            Some('u') => out.push_str(&whoami()),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+fn whoami() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+// Resolves a host from ~/.ssh/config, then /etc/ssh/ssh_config. A user or
+// port given in the URL takes precedence, as on the ssh command line.
+pub fn resolve_ssh_host(host: &str, port: Option<u16>, user: Option<&str>) -> SshHostConfig {
+    let mut settings = Collected::default();
+    let ssh_dir = home_dir().join(".ssh");
+    read_config(&ssh_dir.join("config"), &ssh_dir, host, &mut settings, 0);
+    read_config(
+        Path::new(SYSTEM_SSH_CONFIG),
+        Path::new("/etc/ssh"),
+        host,
+        &mut settings,
+        0,
+    );
+
+    let port = port.or(settings.port).unwrap_or(22);
+    let hostname = settings
+        .hostname
+        .map(|h| expand_tokens(&h, host, host, "", port))
+        .unwrap_or_else(|| host.to_string());
+    let user = user.map(|u| u.to_string()).or(settings.user);
+    let remote_user = user.as_deref().unwrap_or("git");
+    let expand_path = |p: &str| expand_tilde(&expand_tokens(p, host, &hostname, remote_user, port));
+    let identity_files = settings
+        .identity_files
+        .iter()
+        .map(|f| expand_path(f))
+        .collect();
+    let user_known_hosts_files = match settings.user_known_hosts_files {
+        Some(files) => files.iter().map(|f| expand_path(f)).collect(),
+        None => vec![ssh_dir.join("known_hosts"), ssh_dir.join("known_hosts2")],
+    };
+
+    SshHostConfig {
+        host: host.to_string(),
+        port,
+        user,
+        identity_files,
+        identities_only: settings.identities_only.unwrap_or(false),
+        host_key_alias: settings.host_key_alias,
+        strict_host_key_checking: settings.strict_host_key_checking,
+        hash_known_hosts: settings.hash_known_hosts.unwrap_or(false),
+        user_known_hosts_files,
+        hostname,
+    }
+}
+
+fn split_user(authority: &str) -> (Option<String>, &str) {
+    match authority.rsplit_once('@') {
+        Some((user, host)) => (Some(user.to_string()), host),
+        None => (None, authority),
+    }
+}
+
+// Splits `host[:port]` or `[host]:port`
+fn split_port(authority: &str) -> Option<(String, Option<u16>)> {
+    if let Some(rest) = authority.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => Some(port.parse().ok()?),
+            None => None,
+        };
+        return Some((host.to_string(), port));
+    }
+    match authority.rsplit_once(':') {
+        Some((host, port)) => Some((host.to_string(), Some(port.parse().ok()?))),
+        None => Some((authority.to_string(), None)),
+    }
+}
+
+fn parse_ssh_url(url: &str) -> Option<SshUrl> {
+    for scheme in ["ssh://", "ssh+git://", "git+ssh://"] {
+        if let Some(rest) = url.strip_prefix(scheme) {
+            let (authority, path) = match rest.find('/') {
+                Some(i) => (&rest[..i], &rest[i..]),
+                None => (rest, ""),
+            };
+            let (user, host_port) = split_user(authority);
+            let (host, port) = split_port(host_port)?;
+            return Some(SshUrl {
+                user,
+                host,
+                port,
+                path: path.to_string(),
+                scp: false,
+            });
+        }
+    }
+    if url.contains("://") {
+        return None;
+    }
+
+    // `[user@host:port]:path`, as libgit2 accepts it
+    if let Some(rest) = url.strip_prefix('[') {
+        let (inner, path) = rest.split_once("]:")?;
+        let (user, host_port) = split_user(inner);
+        let (host, port) = split_port(host_port)?;
+        return Some(SshUrl {
+            user,
+            host,
+            port,
+            path: path.to_string(),
+            scp: true,
+        });
+    }
+    let (authority, path) = url.split_once(':')?;
+    // A single letter is a Windows drive, and a slash means a local path
+    if authority.contains('/') {
+        return None;
+    }
+    let (user, host) = split_user(authority);
+    if host.len() < 2 {
+        return None;
+    }
+    Some(SshUrl {
+        user,
+        host: host.to_string(),
+        port: None,
+        path: path.to_string(),
+        scp: true,
+    })
+}
+
+// The URL with the host, port and user the config resolved to
+fn resolved_url(url: &SshUrl, config: &SshHostConfig) -> String {
+    let user = config
+        .user
+        .as_deref()
+        .map(|u| format!("{}@", u))
+        .unwrap_or_default();
+    let host = if config.hostname.contains(':') {
+        format!("[{}]", config.hostname)
+    } else {
+        config.hostname.clone()
+    };
+    match (url.scp, config.port) {
+        (true, 22) => format!("{}{}:{}", user, host, url.path),
+        (true, port) => format!("[{}{}:{}]:{}", user, config.hostname, port, url.path),
+        (false, 22) => format!("ssh://{}{}{}", user, host, url.path),
+        (false, port) => format!("ssh://{}{}:{}{}", user, host, port, url.path),
+    }
+}
+
+// Host settings of the SSH URLs resolved by `resolve_ssh_url`, by the URL
+// libgit2 ends up connecting to, for the credential and certificate
+// callbacks
+fn resolved_hosts() -> &'static Mutex<HashMap<String, SshHostConfig>> {
+    static HOSTS: OnceLock<Mutex<HashMap<String, SshHostConfig>>> = OnceLock::new();
+    HOSTS.get_or_init(Default::default)
+}
+
+// The host settings for an SSH URL handed to a credential callback
+pub(crate) fn host_config_for_url(url: &str) -> Option<SshHostConfig> {
+    if let Some(config) = resolved_hosts().lock().ok()?.get(url) {
+        return Some(config.clone());
+    }
+    let parsed = parse_ssh_url(url)?;
+    Some(resolve_ssh_host(
+        &parsed.host,
+        parsed.port,
+        parsed.user.as_deref(),
+    ))
+}
+
+// The host settings for the host name a certificate check is made for
+pub(crate) fn host_config_for_host(hostname: &str) -> SshHostConfig {
+    let known = resolved_hosts().lock().ok().and_then(|hosts| {
+        hosts
+            .values()
+            .find(|config| config.hostname.eq_ignore_ascii_case(hostname))
+            .cloned()
+    });
+    known.unwrap_or_else(|| resolve_ssh_host(hostname, None, None))
+}
+
+// The URL an SSH remote URL resolves to under the ssh config; other URLs
+// are returned as they are
+pub fn resolve_ssh_url(url: &str) -> String {
+    let Some(parsed) = parse_ssh_url(url) else {
+        return url.to_string();
+    };
+    let host = resolve_ssh_host(&parsed.host, parsed.port, parsed.user.as_deref());
+    let resolved = resolved_url(&parsed, &host);
+    if let Ok(mut hosts) = resolved_hosts().lock() {
+        hosts.insert(resolved.clone(), host);
+    }
+    resolved
+}
+
+// Git for Windows' %PROGRAMDATA%\Git\config, the only place libgit2 reads
+// that level from
+fn programdata_config() -> Option<PathBuf> {
+    if !cfg!(windows) {
+        return None;
+    }
+    let file = PathBuf::from(std::env::var_os("PROGRAMDATA")?)
+        .join("Git")
+        .join("config");
+    file.exists().then_some(file)
+}
+
+fn quote_config_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// libgit2 talks to SSH servers itself and knows nothing of the ssh config
+// files, so remote URLs naming a host alias are pointed at the real host,
+// port and user with `url.<resolved>.insteadOf = <original>`.
+//
+// The rewrite goes into a config file added to a second handle on the
+// repository, returned for the network operation; None when no remote
+// needs it. libgit2 writes config changes to the file added last, so it
+// can't be added to a handle anything else writes config through.
+pub fn open_with_ssh_config(repo: &Repository) -> Result<Option<Repository>, git2::Error> {
+    let config = repo.config()?;
+    let mut urls = Vec::new();
+    for name in repo.remotes()?.iter().flatten() {
+        for key in ["url", "pushurl"] {
+            if let Ok(url) = config.get_string(&format!("remote.{}.{}", name, key)) {
+                urls.push(url);
+            }
+        }
+    }
+
+    let mut rewrites = Vec::new();
+    for url in urls {
+        let resolved = resolve_ssh_url(&url);
+        if resolved != url && !rewrites.iter().any(|(_, from)| *from == url) {
+            rewrites.push((resolved, url));
+        }
+    }
+    if rewrites.is_empty() {
+        return Ok(None);
+    }
+
+    // The program data level is where libgit2 would otherwise read it from,
+    // and adding a file replaces the existing one
+    let mut contents = String::new();
+    if let Some(programdata) = programdata_config() {
+        contents.push_str(&format!(
+            "[include]\n\tpath = {}\n",
+            quote_config_value(&programdata.to_string_lossy())
+        ));
+    }
+    for (resolved, original) in &rewrites {
+        contents.push_str(&format!(
+            "[url {}]\n\tinsteadOf = {}\n",
+            quote_config_value(resolved),
+            quote_config_value(original)
+        ));
+    }
+    let digest = Sha256::digest(contents.as_bytes());
+    let name: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    let dir = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("forked");
+    let file = dir.join(format!("ssh-{}.gitconfig", name));
+    if !file.exists() {
+        std::fs::create_dir_all(&dir)
+            .and_then(|_| std::fs::write(&file, &contents))
+            .map_err(|e| git2::Error::from_str(&format!("Failed to apply ssh config: {}", e)))?;
+    }
+    let ssh_repo = Repository::open(repo.path())?;
+    ssh_repo
+        .config()?
+        .add_file(&file, ConfigLevel::ProgramData, true)?;
+    Ok(Some(ssh_repo))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host_config(hostname: &str, port: u16, user: Option<&str>) -> SshHostConfig {
+        SshHostConfig {
+            hostname: hostname.to_string(),
+            port,
+            user: user.map(|u| u.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_ssh_urls() {
+        let url = parse_ssh_url("ssh://git@example.com:2222/owner/repo.git").unwrap();
+        assert_eq!(url.user.as_deref(), Some("git"));
+        assert_eq!(url.host, "example.com");
+        assert_eq!(url.port, Some(2222));
+        assert_eq!(url.path, "/owner/repo.git");
+        assert!(!url.scp);
+
+        let url = parse_ssh_url("git+ssh://[::1]/repo").unwrap();
+        assert_eq!((url.host.as_str(), url.port), ("::1", None));
+
+        let url = parse_ssh_url("git@github.com:owner/repo.git").unwrap();
+        assert_eq!(url.user.as_deref(), Some("git"));
+        assert_eq!(url.host, "github.com");
+        assert_eq!(url.path, "owner/repo.git");
+        assert!(url.scp);
+
+        let url = parse_ssh_url("[me@alias:2222]:repo.git").unwrap();
+        assert_eq!(url.user.as_deref(), Some("me"));
+        assert_eq!((url.host.as_str(), url.port), ("alias", Some(2222)));
+        assert_eq!(url.path, "repo.git");
+    }
+
+    #[test]
+    fn ignores_other_urls() {
+        assert!(parse_ssh_url("https://github.com/owner/repo.git").is_none());
+        assert!(parse_ssh_url("file:///tmp/repo").is_none());
+        assert!(parse_ssh_url("C:/repos/project").is_none());
+        assert!(parse_ssh_url("./some:dir").is_none());
+        assert!(parse_ssh_url("/tmp/repo").is_none());
+        assert!(parse_ssh_url("ssh://example.com:port/repo").is_none());
+    }
+
+    #[test]
+    fn resolves_urls_to_the_configured_host() {
+        let scp = parse_ssh_url("work:owner/repo.git").unwrap();
+        let url = parse_ssh_url("ssh://work/owner/repo.git").unwrap();
+
+        let config = host_config("github.com", 22, Some("git"));
+        assert_eq!(resolved_url(&scp, &config), "git@github.com:owner/repo.git");
+        assert_eq!(
+            resolved_url(&url, &config),
+            "ssh://git@github.com/owner/repo.git"
+        );
+
+        let config = host_config("git.example.com", 2222, None);
+        assert_eq!(
+            resolved_url(&scp, &config),
+            "[git.example.com:2222]:owner/repo.git"
+        );
+        assert_eq!(
+            resolved_url(&url, &config),
+            "ssh://git.example.com:2222/owner/repo.git"
+        );
+
+        let config = host_config("::1", 2222, Some("me"));
+        assert_eq!(
+            resolved_url(&url, &config),
+            "ssh://me@[::1]:2222/owner/repo.git"
+        );
+    }
+
+    #[test]
+    fn matches_pattern_lists() {
+        assert!(match_pattern_list(["*.example.com"], "git.EXAMPLE.com"));
+        assert!(!match_pattern_list(["*.example.com"], "example.com"));
+        assert!(match_pattern_list(["gh?"], "gh1"));
+        assert!(!match_pattern_list(["gh?"], "gh"));
+        assert!(match_pattern_list(["github.com", "work"], "work"));
+        assert!(!match_pattern_list(
+            ["*.example.com", "!secret.example.com"],
+            "secret.example.com"
+        ));
+        assert!(match_pattern_list(
+            ["*.example.com", "!secret.example.com"],
+            "git.example.com"
+        ));
+        // A negation alone matches nothing
+        assert!(!match_pattern_list(["!secret"], "other"));
+    }
+
+    #[test]
+    fn tokenizes_config_lines() {
+        assert_eq!(
+            tokenize("  IdentityFile \"~/.ssh/my key\""),
+            Some((
+                "identityfile".to_string(),
+                vec!["~/.ssh/my key".to_string()]
+            ))
+        );
+        assert_eq!(
+            tokenize("Port=2222"),
+            Some(("port".to_string(), vec!["2222".to_string()]))
+        );
+        assert_eq!(tokenize("# a comment"), None);
+        assert_eq!(tokenize(""), None);
+    }
+
+    #[test]
+    fn reads_matching_host_blocks_and_includes() {
+        let dir = std::env::temp_dir().join(format!("forked-ssh-config-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        std::fs::write(
+            dir.join("config"),
+            "Include conf.d/*.conf\n\
+             Host work !other\n\
+             \tHostName github.com\n\
+             \tIdentityFile ~/.ssh/work\n\
+             Host *\n\
+             \tUser fallback\n\
+             \tPort 22\n\
+             \tIdentityFile ~/.ssh/default\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("conf.d").join("a.conf"),
+            "Host work\n\tPort 2222\n\tStrictHostKeyChecking yes\n",
+        )
+        .unwrap();
+
+        let mut settings = Collected::default();
+        read_config(&dir.join("config"), &dir, "work", &mut settings, 0);
+        assert_eq!(settings.hostname.as_deref(), Some("github.com"));
+        assert_eq!(settings.port, Some(2222));
+        assert_eq!(settings.user.as_deref(), Some("fallback"));
+        assert_eq!(settings.strict_host_key_checking.as_deref(), Some("yes"));
+        assert_eq!(
+            settings.identity_files,
+            vec!["~/.ssh/work".to_string(), "~/.ssh/default".to_string()]
+        );
+
+        let mut settings = Collected::default();
+        read_config(&dir.join("config"), &dir, "other", &mut settings, 0);
+        assert_eq!(settings.hostname, None);
+        assert_eq!(settings.port, Some(22));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::error::GitClientError;
+use crate::git::credentials::{get_fetch_options, RemoteObserver};
+use crate::git::tls::with_tls_settings;
+use git2::{Oid, Repository, Submodule, SubmoduleIgnore, SubmoduleStatus, SubmoduleUpdateOptions};
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SubmoduleInfo {
+    pub name: String,
+    pub path: String,
+    pub url: Option<String>,
+    pub branch: Option<String>,
+    // Commit recorded in the superproject's index
+    pub recorded_commit: Option<String>,
+    // Commit currently checked out inside the submodule
+    pub checked_out_commit: Option<String>,
+    pub is_initialized: bool,
+    pub is_checked_out: bool,
+    pub is_dirty: bool,
+    pub is_out_of_date: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SubmoduleDiff {
+    pub old_commit: Option<String>,
+    pub new_commit: Option<String>,
+    pub commits_added: usize,
+    pub commits_removed: usize,
+    // One line per commit in the range, prefixed with ">" (added) or "<" (removed)
+    // like `git diff --submodule=log`
+    pub commit_summaries: Vec<String>,
+    pub summary: String,
+}
+
+fn submodule_name(submodule: &Submodule) -> String {
+    submodule
+        .name()
+        .map(|n| n.to_string())
+        .unwrap_or_else(|| submodule.path().to_string_lossy().to_string())
+}
+
+fn submodule_to_info(
+    repo: &Repository,
+    submodule: &Submodule,
+) -> Result<SubmoduleInfo, GitClientError> {
+    let name = submodule_name(submodule);
+    let status = repo.submodule_status(&name, SubmoduleIgnore::None)?;
+    let recorded = submodule.index_id().or_else(|| submodule.head_id());
+    let checked_out = submodule.workdir_id();
+    let is_checked_out = status.contains(SubmoduleStatus::IN_WD)
+        && !status.contains(SubmoduleStatus::WD_UNINITIALIZED);
+
+    Ok(SubmoduleInfo {
+        name,
+        path: submodule.path().to_string_lossy().to_string(),
+        url: submodule.url().map(|u| u.to_string()),
+        branch: submodule.branch().map(|b| b.to_string()),
+        recorded_commit: recorded.map(|o| o.to_string()),
+        checked_out_commit: checked_out.map(|o| o.to_string()),
+        is_initialized: repo
+            .config()?
+            .get_string(&format!("submodule.{}.url", submodule_name(submodule)))
+            .is_ok(),
+        is_checked_out,
+        is_dirty: status.intersects(
+            SubmoduleStatus::WD_INDEX_MODIFIED
+                | SubmoduleStatus::WD_WD_MODIFIED
+                | SubmoduleStatus::WD_UNTRACKED,
+        ),
+        is_out_of_date: is_checked_out && recorded != checked_out,
+    })
+}
+
+pub fn list_submodules(repo: &Repository) -> Result<Vec<SubmoduleInfo>, GitClientError> {
+    repo.submodules()?
+        .iter()
+        .map(|submodule| submodule_to_info(repo, submodule))
+        .collect()
+}
+
+// Restricts an operation to the named submodules, or all of them when empty.
+fn selected_submodules<'r>(
+    repo: &'r Repository,
+    names: &[String],
+) -> Result<Vec<Submodule<'r>>, GitClientError> {
+    let submodules = repo.submodules()?;
+    if names.is_empty() {
+        return Ok(submodules);
+    }
+    for name in names {
+        if !submodules.iter().any(|s| submodule_name(s) == *name) {
+            return Err(GitClientError::Operation(format!(
+                "Submodule '{}' not found",
+                name
+            )));
+        }
+    }
+    Ok(submodules
+        .into_iter()
+        .filter(|s| names.contains(&submodule_name(s)))
+        .collect())
+}
+
+pub fn init_submodules(repo: &Repository, names: &[String]) -> Result<(), GitClientError> {
+    for mut submodule in selected_submodules(repo, names)? {
+        submodule.init(false)?;
+    }
+    Ok(())
+}
+
+pub fn update_submodules(
+    repo: &Repository,
+    names: &[String],
+    init: bool,
+    recursive: bool,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<(), GitClientError> {
+    for mut submodule in selected_submodules(repo, names)? {
+        let config = repo.config()?;
+        let url = submodule.url().unwrap_or_default().to_string();
+        let mut update_opts = SubmoduleUpdateOptions::new();
+        update_opts.fetch(get_fetch_options(&config, &url, observer));
+        with_tls_settings(&config, &url, observer, || {
+            submodule.update(init, Some(&mut update_opts))
+        })?;
+
+        if recursive {
+            if let Ok(sub_repo) = submodule.open() {
+                update_submodules(&sub_repo, &[], init, true, observer)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Copies submodule URLs from .gitmodules into .git/config and each submodule's remote.
+pub fn sync_submodules(
+    repo: &Repository,
+    names: &[String],
+    recursive: bool,
+) -> Result<(), GitClientError> {
+    for mut submodule in selected_submodules(repo, names)? {
+        submodule.sync()?;
+
+        if recursive {
+            if let Ok(sub_repo) = submodule.open() {
+                sync_submodules(&sub_repo, &[], true)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn submodule_workdir(repo: &Repository, name: &str) -> Result<PathBuf, GitClientError> {
+    let submodule = selected_submodules(repo, &[name.to_string()])?
+        .into_iter()
+        .next()
+        .ok_or_else(|| GitClientError::Operation(format!("Submodule '{}' not found", name)))?;
+    let workdir = repo.workdir().ok_or(GitClientError::NoRepository)?;
+    let path = workdir.join(submodule.path());
+    if submodule.open().is_err() {
+        return Err(GitClientError::Operation(format!(
+            "Submodule '{}' is not checked out; update it first",
+            name
+        )));
+    }
+    Ok(path)
+}
+
+fn short(oid: Option<Oid>) -> String {
+    oid.map(|o| o.to_string()[..7].to_string())
+        .unwrap_or_else(|| "0000000".to_string())
+}
+
+pub fn diff_submodule(
+    repo: &Repository,
+    path: &str,
+    old_id: Option<Oid>,
+    new_id: Option<Oid>,
+) -> SubmoduleDiff {
+    let mut diff = SubmoduleDiff {
+        old_commit: old_id.map(|o| o.to_string()),
+        new_commit: new_id.map(|o| o.to_string()),
+        commits_added: 0,
+        commits_removed: 0,
+        commit_summaries: Vec::new(),
+        summary: format!("Submodule {} {}..{}", path, short(old_id), short(new_id)),
+    };
+
+    let (Some(old_id), Some(new_id)) = (old_id, new_id) else {
+        diff.summary = match (old_id, new_id) {
+            (None, Some(_)) => format!("Submodule {} {} (new submodule)", path, short(new_id)),
+            (Some(_), None) => format!("Submodule {} {} (submodule deleted)", path, short(old_id)),
+            _ => diff.summary,
+        };
+        return diff;
+    };
+
+    let sub_repo = match repo.find_submodule(path).and_then(|s| s.open()) {
+        Ok(sub_repo) => sub_repo,
+        Err(_) => {
+            diff.summary.push_str(" (not checked out)");
+            return diff;
+        }
+    };
+
+    let mut collect = |from: Oid, hide: Oid, marker: &str| -> Result<usize, git2::Error> {
+        let mut revwalk = sub_repo.revwalk()?;
+        revwalk.push(from)?;
+        revwalk.hide(hide)?;
+        let mut count = 0;
+        for oid in revwalk {
+            let commit = sub_repo.find_commit(oid?)?;
+            diff.commit_summaries
+                .push(format!("{} {}", marker, commit.summary().unwrap_or("")));
+            count += 1;
+        }
+        Ok(count)
+    };
+
+    match (collect(new_id, old_id, ">"), collect(old_id, new_id, "<")) {
+        (Ok(added), Ok(removed)) => {
+            diff.commits_added = added;
+            diff.commits_removed = removed;
+            let mut parts = Vec::new();
+            if added > 0 {
+                parts.push(format!(
+                    "{} commit{}",
+                    added,
+                    if added == 1 { "" } else { "s" }
+                ));
+            }
+            if removed > 0 {
+                parts.push(format!("{} removed", removed));
+            }
+            if !parts.is_empty() {
+                diff.summary = format!("{}: {}", diff.summary, parts.join(", "));
+            }
+        }
+        // A walk that failed partway leaves summaries for only some commits
+        _ => {
+            diff.commit_summaries.clear();
+            diff.summary.push_str(" (commits not present)");
+        }
+    }
+
+    diff
+}
@@ -0,0 +1,387 @@
+use crate::git::credentials::{
+    classify_rejection, classify_update, lease_oid, rejection, FetchRemoteOptions, PushRefStatus,
+    PushRemoteOptions, PushResult, RefUpdate, RemoteObserver, TransferProgress,
+};
+use crate::git::process::{kill_process_group, spawn_in_process_group};
+use git2::{ErrorClass, ErrorCode, Oid, Repository};
+use std::collections::HashMap;
+use std::io::{BufReader, Read};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+
+// How often a running git process is checked for cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+struct GitOutput {
+    success: bool,
+    stdout: String,
+    // stderr as a terminal would end up showing it, without the
+    // intermediate states of progress lines
+    lines: Vec<String>,
+}
+
+// The installed git's version, or None if there is no usable git executable
+pub fn system_git_version() -> Option<String> {
+    let output = Command::new("git").arg("--version").output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn git_command(repo: &Repository) -> Command {
+    let mut command = Command::new("git");
+    command
+        .current_dir(repo.workdir().unwrap_or_else(|| repo.path()))
+        // The output is parsed, so keep it in English
+        .env("LC_ALL", "C")
+        // Nobody can answer a terminal prompt; credentials come from the
+        // configured helpers and the SSH agent
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // ssh asks on /dev/tty for host keys and passphrases, which hangs when
+    // the app was started from a terminal. Batch mode makes those failures
+    // instead, unless the user chose their own ssh command.
+    let ssh_configured = std::env::var_os("GIT_SSH_COMMAND").is_some()
+        || std::env::var_os("GIT_SSH").is_some()
+        || repo
+            .config()
+            .is_ok_and(|config| config.get_string("core.sshCommand").is_ok());
+    if !ssh_configured {
+        command.env("GIT_SSH_COMMAND", "ssh -o BatchMode=yes");
+    }
+    command
+}
+
+// Counts like "(450/1000)" in a progress line
+fn progress_counts(line: &str) -> Option<(usize, usize)> {
+    let start = line.find('(')? + 1;
+    let end = start + line[start..].find(')')?;
+    let (current, total) = line[start..end].split_once('/')?;
+    Some((current.trim().parse().ok()?, total.trim().parse().ok()?))
+}
+
+// The amount after the counts, like "1.20 MiB" or "250 bytes"
+fn progress_bytes(line: &str) -> Option<usize> {
+    let (_, after) = line.split_once("), ")?;
+    let mut parts = after.split_whitespace();
+    let amount: f64 = parts.next()?.parse().ok()?;
+    let unit = match parts.next()?.trim_end_matches(',') {
+        "bytes" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((amount * unit) as usize)
+}
+
+// Follows git's `--progress` output, e.g.
+// "Receiving objects:  45% (450/1000), 1.20 MiB | 2.00 MiB/s"
+fn update_progress(progress: &mut TransferProgress, line: &str) {
+    let stage = if line.starts_with("Receiving objects:") {
+        "receiving"
+    } else if line.starts_with("Resolving deltas:") {
+        "resolving"
+    } else if line.starts_with("Writing objects:") {
+        "pushing"
+    } else {
+        let message = line.strip_prefix("remote:").unwrap_or(line).trim();
+        if !message.is_empty() {
+            progress.message = Some(message.to_string());
+        }
+        return;
+    };
+    progress.stage = stage.to_string();
+    if let Some((current, total)) = progress_counts(line) {
+        progress.current = current;
+        progress.total = total;
+    }
+    if let Some(bytes) = progress_bytes(line) {
+        progress.bytes = bytes;
+    }
+}
+
+// Runs git to completion, reporting its progress and killing it when the
+// operation is cancelled
+fn run_git(
+    mut command: Command,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<GitOutput, git2::Error> {
+    // Its own process group, so cancelling stops the ssh or
+    // git-remote-https process along with git
+    let mut child = spawn_in_process_group(&mut command)
+        .map_err(|e| git2::Error::from_str(&format!("Failed to run git: {}", e)))?;
+
+    let stdout = child.stdout.take();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut output = String::new();
+        if let Some(mut stdout) = stdout {
+            let _ = stdout.read_to_string(&mut output);
+        }
+        output
+    });
+
+    // Progress lines are rewritten in place with `\r`; each line is sent
+    // along with whether it was finished with a newline
+    let (sender, receiver) = mpsc::channel();
+    let stderr = child.stderr.take();
+    std::thread::spawn(move || {
+        let Some(stderr) = stderr else {
+            return;
+        };
+        let mut line = Vec::new();
+        for byte in BufReader::new(stderr).bytes() {
+            let Ok(byte) = byte else {
+                break;
+            };
+            if byte != b'\r' && byte != b'\n' {
+                line.push(byte);
+                continue;
+            }
+            if !line.is_empty() {
+                let text = String::from_utf8_lossy(&line).into_owned();
+                let _ = sender.send((text, byte == b'\n'));
+                line.clear();
+            }
+        }
+        if !line.is_empty() {
+            let _ = sender.send((String::from_utf8_lossy(&line).into_owned(), true));
+        }
+    });
+
+    let mut progress = TransferProgress::default();
+    let mut lines = Vec::new();
+    loop {
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok((line, finished)) => {
+                if let Some(observer) = observer {
+                    update_progress(&mut progress, &line);
+                    observer.progress(&progress);
+                }
+                if finished {
+                    lines.push(line);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if observer.is_some_and(|o| o.is_cancelled()) {
+            kill_process_group(&mut child);
+            return Err(git2::Error::from_str("cancelled"));
+        }
+    }
+
+    let status = child
+        .wait()
+        .map_err(|e| git2::Error::from_str(&format!("Failed to run git: {}", e)))?;
+    Ok(GitOutput {
+        success: status.success(),
+        stdout: stdout_reader.join().unwrap_or_default(),
+        lines,
+    })
+}
+
+// The error git reported, classified like libgit2's errors so that
+// authentication and network failures are told apart
+fn command_error(lines: &[String]) -> git2::Error {
+    let errors: Vec<&str> = lines
+        .iter()
+        .filter_map(|l| {
+            l.strip_prefix("fatal: ")
+                .or_else(|| l.strip_prefix("error: "))
+        })
+        .collect();
+    let message = if errors.is_empty() {
+        lines
+            .last()
+            .cloned()
+            .unwrap_or_else(|| "git exited with an error".to_string())
+    } else {
+        errors.join("\n")
+    };
+    let lower = message.to_lowercase();
+    if lower.contains("authentication failed")
+        || lower.contains("permission denied")
+        || lower.contains("could not read username")
+    {
+        git2::Error::new(ErrorCode::Auth, ErrorClass::Net, message)
+    } else if lower.contains("could not resolve")
+        || lower.contains("connection")
+        || lower.contains("timed out")
+        || lower.contains("network is unreachable")
+    {
+        git2::Error::new(ErrorCode::GenericError, ErrorClass::Net, message)
+    } else {
+        git2::Error::from_str(&message)
+    }
+}
+
+fn ref_snapshot(repo: &Repository) -> Result<HashMap<String, Oid>, git2::Error> {
+    let mut refs = HashMap::new();
+    for reference in repo.references()?.flatten() {
+        if let (Some(name), Some(oid)) = (reference.name(), reference.target()) {
+            refs.insert(name.to_string(), oid);
+        }
+    }
+    Ok(refs)
+}
+
+// `git fetch` for one remote. The updated refs are found by comparing the
+// refs before and after, which works with every git version.
+pub(crate) fn fetch_with_git(
+    repo: &Repository,
+    remote_name: &str,
+    options: &FetchRemoteOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<Vec<RefUpdate>, git2::Error> {
+    let mut command = git_command(repo);
+    command.args(["fetch", "--progress"]);
+    match options.prune {
+        Some(true) => {
+            command.arg("--prune");
+        }
+        Some(false) => {
+            command.arg("--no-prune");
+        }
+        None => {}
+    }
+    match options.tags.as_deref() {
+        Some("all") => {
+            command.arg("--tags");
+        }
+        Some("none") => {
+            command.arg("--no-tags");
+        }
+        Some("auto") | None => {}
+        Some(other) => {
+            return Err(git2::Error::from_str(&format!(
+                "Invalid tag option: {}. Expected 'all', 'auto' or 'none'.",
+                other
+            )))
+        }
+    }
+    command.arg("--").arg(remote_name);
+    if let Some(refspec) = &options.refspec {
+        command.arg(refspec);
+    }
+
+    let before = ref_snapshot(repo)?;
+    let output = run_git(command, observer)?;
+    if !output.success {
+        return Err(command_error(&output.lines));
+    }
+    let after = ref_snapshot(repo)?;
+
+    let changed = after
+        .iter()
+        .filter(|(name, oid)| before.get(*name) != Some(oid))
+        .map(|(name, new)| (name, before.get(name).copied().unwrap_or(Oid::zero()), *new));
+    let deleted = before
+        .iter()
+        .filter(|(name, _)| !after.contains_key(*name))
+        .map(|(name, old)| (name, *old, Oid::zero()));
+    let mut updates: Vec<RefUpdate> = changed
+        .chain(deleted)
+        .map(|(name, old, new)| classify_update(repo, remote_name, name.clone(), old, new))
+        .collect();
+    updates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(updates)
+}
+
+// `git push --porcelain` prints "<flag>\t<from>:<to>\t<summary> (<reason>)"
+// for each ref, with `!` flagging rejections
+fn parse_push_porcelain(stdout: &str) -> Vec<PushRefStatus> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let flag = fields.next()?;
+            let refs = fields.next()?;
+            let summary = fields.next().unwrap_or_default();
+            let refname = refs.rsplit_once(':').map_or(refs, |(_, to)| to);
+            if flag != "!" {
+                return Some(PushRefStatus {
+                    refname: refname.to_string(),
+                    status: "ok".to_string(),
+                    message: None,
+                });
+            }
+            let reason = summary
+                .split_once('(')
+                .and_then(|(_, reason)| reason.strip_suffix(')'))
+                .unwrap_or(summary);
+            Some(rejection(
+                refname,
+                classify_rejection(reason),
+                reason.to_string(),
+            ))
+        })
+        .collect()
+}
+
+// `git push` of a branch, with the same options and result as the libgit2
+// push; git checks the lease itself
+pub(crate) fn push_with_git(
+    repo: &Repository,
+    remote_name: &str,
+    branch_name: &str,
+    options: &PushRemoteOptions,
+    observer: Option<&dyn RemoteObserver>,
+) -> Result<PushResult, git2::Error> {
+    let remote_branch = options.remote_branch.as_deref().unwrap_or(branch_name);
+    let remote_ref = format!("refs/heads/{}", remote_branch);
+
+    let mut command = git_command(repo);
+    command.args(["push", "--porcelain", "--progress"]);
+    if options.force_with_lease && !options.force {
+        let expected = lease_oid(repo, remote_name, remote_branch, options)?;
+        // Nothing after the colon means the branch must not exist yet
+        let expected = if expected.is_zero() {
+            String::new()
+        } else {
+            expected.to_string()
+        };
+        command.arg(format!("--force-with-lease={}:{}", remote_ref, expected));
+    }
+    if options.tags {
+        command.arg("--tags");
+    }
+    for option in &options.push_options {
+        command.arg(format!("--push-option={}", option));
+    }
+    let refspec = if options.delete {
+        format!(":{}", remote_ref)
+    } else {
+        format!(
+            "{}refs/heads/{}:{}",
+            if options.force { "+" } else { "" },
+            branch_name,
+            remote_ref
+        )
+    };
+    command.arg("--").arg(remote_name).arg(refspec);
+
+    let output = run_git(command, observer)?;
+    let updates = parse_push_porcelain(&output.stdout);
+    // git exits with an error when a ref was rejected, which is reported
+    // per ref instead
+    if updates.is_empty() && !output.success {
+        return Err(command_error(&output.lines));
+    }
+    let remote_messages = output
+        .lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("remote:"))
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect();
+    Ok(PushResult {
+        updates,
+        remote_messages,
+    })
+}
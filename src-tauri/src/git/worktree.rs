@@ -0,0 +1,299 @@
+use crate::error::GitClientError;
+use git2::{
+    Repository, StatusOptions, WorktreeAddOptions, WorktreeLockStatus, WorktreePruneOptions,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WorktreeInfo {
+    // None for the main working tree
+    pub name: Option<String>,
+    pub path: String,
+    pub is_main: bool,
+    pub is_current: bool,
+    pub head_name: Option<String>,
+    pub head_oid: Option<String>,
+    pub is_detached: bool,
+    pub is_locked: bool,
+    pub lock_reason: Option<String>,
+    pub is_prunable: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AddWorktreeOptions {
+    pub path: String,
+    // Defaults to the last component of `path`
+    pub name: Option<String>,
+    pub branch: Option<String>,
+    pub create_branch: bool,
+    // Commit to branch from or detach at; defaults to HEAD
+    pub start_point: Option<String>,
+    pub detach: bool,
+    pub lock: bool,
+}
+
+// The repository's shared git directory; for a linked worktree this is the
+// main repository's `.git`, not `.git/worktrees/<name>`.
+pub fn common_dir(repo: &Repository) -> PathBuf {
+    let git_dir = repo.path();
+    if repo.is_worktree() {
+        if let Ok(contents) = std::fs::read_to_string(git_dir.join("commondir")) {
+            let common = git_dir.join(contents.trim());
+            return common.canonicalize().unwrap_or(common);
+        }
+    }
+    git_dir.to_path_buf()
+}
+
+fn same_path(a: &Path, b: &Path) -> bool {
+    let a = a.canonicalize().unwrap_or_else(|_| a.to_path_buf());
+    let b = b.canonicalize().unwrap_or_else(|_| b.to_path_buf());
+    a == b
+}
+
+fn head_details(repo: &Repository) -> (Option<String>, Option<String>, bool) {
+    match repo.head() {
+        Ok(head) => {
+            let detached = !head.is_branch();
+            let name = if detached {
+                None
+            } else {
+                head.shorthand().map(|s| s.to_string())
+            };
+            (name, head.target().map(|o| o.to_string()), detached)
+        }
+        Err(_) => (None, None, false),
+    }
+}
+
+fn find_linked_worktree(repo: &Repository, name: &str) -> Result<git2::Worktree, GitClientError> {
+    repo.find_worktree(name)
+        .map_err(|_| GitClientError::Operation(format!("Worktree '{}' not found", name)))
+}
+
+pub fn list_worktrees(repo: &Repository) -> Result<Vec<WorktreeInfo>, GitClientError> {
+    let current_workdir = repo.workdir().map(|p| p.to_path_buf());
+    let is_current = |path: &Path| {
+        current_workdir
+            .as_deref()
+            .map(|current| same_path(current, path))
+            .unwrap_or(false)
+    };
+
+    let mut worktrees = Vec::new();
+
+    let main_repo = Repository::open(common_dir(repo))?;
+    if let Some(main_workdir) = main_repo.workdir() {
+        let (head_name, head_oid, is_detached) = head_details(&main_repo);
+        worktrees.push(WorktreeInfo {
+            name: None,
+            path: main_workdir.to_string_lossy().to_string(),
+            is_main: true,
+            is_current: is_current(main_workdir),
+            head_name,
+            head_oid,
+            is_detached,
+            is_locked: false,
+            lock_reason: None,
+            is_prunable: false,
+        });
+    }
+
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        let path = worktree.path().to_path_buf();
+        let (is_locked, lock_reason) = match worktree.is_locked()? {
+            WorktreeLockStatus::Unlocked => (false, None),
+            WorktreeLockStatus::Locked(reason) => (true, reason.filter(|r| !r.is_empty())),
+        };
+        let (head_name, head_oid, is_detached) = match Repository::open_from_worktree(&worktree) {
+            Ok(worktree_repo) => head_details(&worktree_repo),
+            Err(_) => (None, None, false),
+        };
+
+        worktrees.push(WorktreeInfo {
+            name: Some(name.to_string()),
+            path: path.to_string_lossy().to_string(),
+            is_main: false,
+            is_current: is_current(&path),
+            head_name,
+            head_oid,
+            is_detached,
+            is_locked,
+            lock_reason,
+            is_prunable: worktree.is_prunable(None).unwrap_or(false),
+        });
+    }
+
+    Ok(worktrees)
+}
+
+pub fn add_worktree(
+    repo: &Repository,
+    options: &AddWorktreeOptions,
+) -> Result<WorktreeInfo, GitClientError> {
+    let path = Path::new(&options.path);
+    if path.exists() && path.read_dir()?.next().is_some() {
+        return Err(GitClientError::InvalidPath(format!(
+            "{} already exists and is not empty",
+            path.display()
+        )));
+    }
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None => path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .ok_or_else(|| GitClientError::InvalidPath(options.path.clone()))?,
+    };
+
+    let checks_out_existing = !options.detach && options.branch.is_some() && !options.create_branch;
+    if checks_out_existing && options.start_point.is_some() {
+        return Err(GitClientError::Operation(
+            "A start point only applies to a new branch or a detached worktree".to_string(),
+        ));
+    }
+
+    let start_commit = match &options.start_point {
+        Some(spec) => repo.revparse_single(spec)?.peel_to_commit()?,
+        None => repo.head()?.peel_to_commit()?,
+    };
+
+    let mut add_opts = WorktreeAddOptions::new();
+    add_opts.lock(options.lock);
+
+    // libgit2 always checks out a branch, so a detached worktree starts on a
+    // temporary branch that is removed once HEAD has been detached.
+    let temp_branch = format!("forked-worktree-{}", name);
+    let (branch, created_branch) = if options.detach {
+        (
+            repo.branch(&temp_branch, &start_commit, false)?,
+            Some(temp_branch.clone()),
+        )
+    } else {
+        match &options.branch {
+            Some(branch_name) if options.create_branch => (
+                repo.branch(branch_name, &start_commit, false)?,
+                Some(branch_name.clone()),
+            ),
+            Some(branch_name) => (
+                repo.find_branch(branch_name, git2::BranchType::Local)?,
+                None,
+            ),
+            // What libgit2 would do, but from the start point rather than HEAD
+            None => (
+                repo.branch(&name, &start_commit, false)?,
+                Some(name.clone()),
+            ),
+        }
+    };
+    add_opts.reference(Some(branch.get()));
+
+    let added = repo
+        .worktree(&name, path, Some(&add_opts))
+        .map_err(GitClientError::from)
+        .and_then(|worktree| {
+            if !options.detach {
+                return Ok(());
+            }
+            let detached = Repository::open_from_worktree(&worktree)
+                .and_then(|worktree_repo| worktree_repo.set_head_detached(start_commit.id()));
+            if detached.is_err() {
+                // Still on the temporary branch, which can't be deleted while
+                // a worktree has it checked out
+                let mut prune_opts = WorktreePruneOptions::new();
+                prune_opts.valid(true).locked(true).working_tree(true);
+                let _ = worktree.prune(Some(&mut prune_opts));
+            }
+            Ok(detached?)
+        });
+    drop(branch);
+
+    // A branch made for a worktree that failed to be added goes with it
+    if let (Err(_), Some(created)) = (&added, &created_branch) {
+        if let Ok(mut branch) = repo.find_branch(created, git2::BranchType::Local) {
+            let _ = branch.delete();
+        }
+    }
+    added?;
+    if options.detach {
+        repo.find_branch(&temp_branch, git2::BranchType::Local)?
+            .delete()?;
+    }
+
+    list_worktrees(repo)?
+        .into_iter()
+        .find(|w| w.name.as_deref() == Some(name.as_str()))
+        .ok_or_else(|| GitClientError::Operation(format!("Worktree '{}' not found", name)))
+}
+
+pub fn lock_worktree(
+    repo: &Repository,
+    name: &str,
+    reason: Option<&str>,
+) -> Result<(), GitClientError> {
+    let worktree = find_linked_worktree(repo, name)?;
+    worktree.lock(reason)?;
+    Ok(())
+}
+
+pub fn unlock_worktree(repo: &Repository, name: &str) -> Result<(), GitClientError> {
+    let worktree = find_linked_worktree(repo, name)?;
+    worktree.unlock()?;
+    Ok(())
+}
+
+// Removes administrative data for worktrees whose directories no longer exist.
+pub fn prune_worktrees(repo: &Repository) -> Result<Vec<String>, GitClientError> {
+    let mut pruned = Vec::new();
+    for name in repo.worktrees()?.iter().flatten() {
+        let worktree = repo.find_worktree(name)?;
+        if worktree.is_prunable(None)? {
+            worktree.prune(None)?;
+            pruned.push(name.to_string());
+        }
+    }
+    Ok(pruned)
+}
+
+pub fn remove_worktree(repo: &Repository, name: &str, force: bool) -> Result<(), GitClientError> {
+    let worktree = find_linked_worktree(repo, name)?;
+
+    if repo
+        .workdir()
+        .map(|current| same_path(current, worktree.path()))
+        .unwrap_or(false)
+    {
+        return Err(GitClientError::Operation(
+            "Cannot remove the worktree that is currently open".to_string(),
+        ));
+    }
+
+    if !force {
+        if let WorktreeLockStatus::Locked(_) = worktree.is_locked()? {
+            return Err(GitClientError::Operation(format!(
+                "Worktree '{}' is locked",
+                name
+            )));
+        }
+        if let Ok(worktree_repo) = Repository::open_from_worktree(&worktree) {
+            let mut status_opts = StatusOptions::new();
+            status_opts
+                .include_untracked(true)
+                .recurse_untracked_dirs(true);
+            if !worktree_repo.statuses(Some(&mut status_opts))?.is_empty() {
+                return Err(GitClientError::Operation(format!(
+                    "Worktree '{}' has uncommitted changes",
+                    name
+                )));
+            }
+        }
+    }
+
+    let mut prune_opts = WorktreePruneOptions::new();
+    prune_opts.valid(true).locked(force).working_tree(true);
+    worktree.prune(Some(&mut prune_opts))?;
+    Ok(())
+}
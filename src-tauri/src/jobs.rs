@@ -0,0 +1,331 @@
+use crate::error::GitClientError;
+use crate::git::{CredentialRequest, CredentialResponse, RemoteObserver, TransferProgress};
+use crate::state::AppState;
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+// Finished jobs are kept so the frontend can still query their outcome
+const MAX_FINISHED_JOBS: usize = 50;
+
+// Transfer callbacks fire for every object; progress events are rate limited
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+// An unanswered credential prompt is declined after this long
+const CREDENTIAL_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct JobInfo {
+    pub id: String,
+    // "clone", "fetch", "pull", "push", "deepen", "update_submodules",
+    // "bisect_run" or "test_connection"
+    pub kind: String,
+    pub repo_id: Option<String>,
+    pub description: String,
+    pub status: JobStatus,
+    pub progress: TransferProgress,
+    pub error: Option<String>,
+    // The operation's return value once it has succeeded
+    pub result: Option<serde_json::Value>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct JobProgressEvent {
+    pub job_id: String,
+    pub repo_id: Option<String>,
+    pub progress: TransferProgress,
+}
+
+// Sent when a job needs credentials; answer with `respond_credential_request`
+#[derive(Debug, Serialize, Clone)]
+pub struct CredentialPromptEvent {
+    pub prompt_id: String,
+    pub job_id: String,
+    pub repo_id: Option<String>,
+    #[serde(flatten)]
+    pub request: CredentialRequest,
+}
+
+#[derive(Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, JobInfo>>,
+    // Cancellation tokens of running jobs
+    cancel_tokens: Mutex<HashMap<String, Arc<AtomicBool>>>,
+    next_id: AtomicU64,
+    // Jobs waiting for the user to answer a credential prompt
+    credential_prompts: Mutex<HashMap<String, mpsc::Sender<Option<CredentialResponse>>>>,
+    next_prompt_id: AtomicU64,
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+fn job_number(id: &str) -> u64 {
+    id.trim_start_matches("job-").parse().unwrap_or(u64::MAX)
+}
+
+impl JobRegistry {
+    fn create(
+        &self,
+        kind: &str,
+        repo_id: Option<String>,
+        description: String,
+    ) -> (JobInfo, Arc<AtomicBool>) {
+        let id = format!("job-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let job = JobInfo {
+            id: id.clone(),
+            kind: kind.to_string(),
+            repo_id,
+            description,
+            status: JobStatus::Running,
+            progress: TransferProgress::default(),
+            error: None,
+            result: None,
+            started_at: now(),
+            finished_at: None,
+        };
+        let token = Arc::new(AtomicBool::new(false));
+        self.cancel_tokens.lock().insert(id.clone(), token.clone());
+        self.jobs.lock().insert(id, job.clone());
+        (job, token)
+    }
+
+    // Asks a running job to stop; it finishes as cancelled once the operation
+    // notices
+    pub fn cancel(&self, job_id: &str) -> Result<(), GitClientError> {
+        match self.cancel_tokens.lock().get(job_id) {
+            Some(token) => {
+                token.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            None if self.jobs.lock().contains_key(job_id) => Err(GitClientError::Operation(
+                format!("Job '{}' has already finished", job_id),
+            )),
+            None => Err(GitClientError::Operation(format!(
+                "Job '{}' not found",
+                job_id
+            ))),
+        }
+    }
+
+    fn set_progress(&self, job_id: &str, progress: &TransferProgress) {
+        if let Some(job) = self.jobs.lock().get_mut(job_id) {
+            job.progress = progress.clone();
+        }
+    }
+
+    fn finish(
+        &self,
+        job_id: &str,
+        outcome: Result<serde_json::Value, GitClientError>,
+    ) -> Option<JobInfo> {
+        let cancelled = self
+            .cancel_tokens
+            .lock()
+            .remove(job_id)
+            .is_some_and(|token| token.load(Ordering::Relaxed));
+        let mut jobs = self.jobs.lock();
+        let job = jobs.get_mut(job_id)?;
+        match outcome {
+            Ok(result) => {
+                job.status = JobStatus::Succeeded;
+                job.result = Some(result);
+            }
+            Err(_) if cancelled => {
+                job.status = JobStatus::Cancelled;
+                job.error = Some("Cancelled".to_string());
+            }
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+        job.finished_at = Some(now());
+        let finished = job.clone();
+
+        let mut done: Vec<String> = jobs
+            .values()
+            .filter(|j| j.status != JobStatus::Running)
+            .map(|j| j.id.clone())
+            .collect();
+        if done.len() > MAX_FINISHED_JOBS {
+            done.sort_by_key(|id| job_number(id));
+            for id in &done[..done.len() - MAX_FINISHED_JOBS] {
+                jobs.remove(id);
+            }
+        }
+
+        Some(finished)
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<JobInfo> {
+        self.jobs.lock().get(job_id).cloned()
+    }
+
+    // Hands the user's answer to the job waiting for it. None declines.
+    pub fn respond_credential(
+        &self,
+        prompt_id: &str,
+        response: Option<CredentialResponse>,
+    ) -> Result<(), GitClientError> {
+        let sender = self
+            .credential_prompts
+            .lock()
+            .remove(prompt_id)
+            .ok_or_else(|| {
+                GitClientError::Operation(format!(
+                    "Credential prompt '{}' is no longer pending",
+                    prompt_id
+                ))
+            })?;
+        let _ = sender.send(response);
+        Ok(())
+    }
+
+    // Whether a job is still running for the repository
+    pub fn is_running_for(&self, repo_id: &str) -> bool {
+        self.jobs
+            .lock()
+            .values()
+            .any(|j| j.status == JobStatus::Running && j.repo_id.as_deref() == Some(repo_id))
+    }
+
+    // All known jobs, oldest first
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self.jobs.lock().values().cloned().collect();
+        jobs.sort_by_key(|j| job_number(&j.id));
+        jobs
+    }
+}
+
+// Forwards network progress of a job to the registry and the frontend
+pub struct JobObserver {
+    app_handle: AppHandle,
+    job_id: String,
+    repo_id: Option<String>,
+    cancelled: Arc<AtomicBool>,
+    last_emit: Mutex<Option<Instant>>,
+}
+
+impl RemoteObserver for JobObserver {
+    fn progress(&self, progress: &TransferProgress) {
+        let state = self.app_handle.state::<AppState>();
+        state.jobs.set_progress(&self.job_id, progress);
+
+        let mut last_emit = self.last_emit.lock();
+        let complete = progress.total > 0 && progress.current >= progress.total;
+        if !complete && last_emit.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+            return;
+        }
+        *last_emit = Some(Instant::now());
+        let _ = self.app_handle.emit(
+            "job-progress",
+            JobProgressEvent {
+                job_id: self.job_id.clone(),
+                repo_id: self.repo_id.clone(),
+                progress: progress.clone(),
+            },
+        );
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Emits `credential-requested` and blocks the job until the frontend
+    // answers, the job is cancelled or the prompt times out. Either of the
+    // latter emits `credential-request-closed` so the prompt can be dismissed.
+    fn request_credential(&self, request: &CredentialRequest) -> Option<CredentialResponse> {
+        let state = self.app_handle.state::<AppState>();
+        let prompts = &state.jobs.credential_prompts;
+        let prompt_id = format!(
+            "prompt-{}",
+            state.jobs.next_prompt_id.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let (tx, rx) = mpsc::channel();
+        prompts.lock().insert(prompt_id.clone(), tx);
+        let _ = self.app_handle.emit(
+            "credential-requested",
+            CredentialPromptEvent {
+                prompt_id: prompt_id.clone(),
+                job_id: self.job_id.clone(),
+                repo_id: self.repo_id.clone(),
+                request: request.clone(),
+            },
+        );
+
+        let started = Instant::now();
+        while started.elapsed() < CREDENTIAL_TIMEOUT && !self.is_cancelled() {
+            match rx.recv_timeout(Duration::from_millis(250)) {
+                Ok(response) => return response,
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        prompts.lock().remove(&prompt_id);
+        let _ = self.app_handle.emit("credential-request-closed", prompt_id);
+        None
+    }
+}
+
+// Runs `task` on the blocking thread pool and returns immediately. The job
+// emits `job-started`, `job-progress` and `job-finished` events; the finished
+// event carries the task's result.
+pub fn spawn_job<T, F>(
+    app_handle: &AppHandle,
+    kind: &str,
+    repo_id: Option<String>,
+    description: String,
+    task: F,
+) -> JobInfo
+where
+    T: Serialize,
+    F: FnOnce(&JobObserver) -> Result<T, GitClientError> + Send + 'static,
+{
+    let state = app_handle.state::<AppState>();
+    let (job, cancelled) = state.jobs.create(kind, repo_id.clone(), description);
+    let _ = app_handle.emit("job-started", job.clone());
+
+    let observer = JobObserver {
+        app_handle: app_handle.clone(),
+        job_id: job.id.clone(),
+        repo_id,
+        cancelled,
+        last_emit: Mutex::new(None),
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        // The user's job goes first; a background fetch would race it
+        if let Some(repo_id) = &observer.repo_id {
+            let state = observer.app_handle.state::<AppState>();
+            state.background_fetch.cancel_for(repo_id);
+        }
+        let outcome = task(&observer).and_then(|value| {
+            serde_json::to_value(value).map_err(|e| GitClientError::Operation(e.to_string()))
+        });
+        let state = observer.app_handle.state::<AppState>();
+        if let Some(finished) = state.jobs.finish(&observer.job_id, outcome) {
+            let _ = observer.app_handle.emit("job-finished", finished);
+        }
+    });
+
+    job
+}
@@ -1,10 +1,17 @@
+pub mod background_fetch;
+pub mod catalog;
 pub mod commands;
 pub mod error;
 pub mod git;
+pub mod jobs;
+pub mod settings;
 pub mod state;
 pub mod watcher;
 
+use catalog::RepoCatalog;
+use settings::SettingsStore;
 use state::AppState;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -12,6 +19,14 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_shell::init())
         .manage(AppState::new())
+        .setup(|app| {
+            let config_dir = app.path().app_config_dir()?;
+            let state = app.state::<AppState>();
+            *state.catalog.lock() = RepoCatalog::load(config_dir.join("repositories.json"));
+            *state.settings.lock() = SettingsStore::load(config_dir.join("settings.json"));
+            background_fetch::start(app.app_handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Repository commands
             commands::open_repository,
@@ -20,7 +35,18 @@ pub fn run() {
             commands::get_status,
             commands::get_repo_info,
             commands::close_repository,
-            commands::get_current_repo_path,
+            commands::list_open_repositories,
+            commands::scan_repositories,
+            // Catalog commands
+            commands::get_repo_catalog,
+            commands::set_repo_favorite,
+            commands::set_repo_alias,
+            commands::set_repo_group,
+            commands::add_repo_group,
+            commands::rename_repo_group,
+            commands::remove_repo_group,
+            commands::remove_from_catalog,
+            commands::restore_session,
             // Staging/commit commands
             commands::stage_file,
             commands::unstage_file,
@@ -34,6 +60,18 @@ pub fn run() {
             commands::get_commit_details,
             commands::reset_to_commit,
             commands::squash_commits,
+            commands::create_fixup_commit,
+            commands::autosquash,
+            commands::start_split_commit,
+            commands::continue_split_commit,
+            commands::abort_split_commit,
+            commands::get_split_state,
+            // Bisect commands
+            commands::bisect_start,
+            commands::bisect_mark,
+            commands::bisect_status,
+            commands::bisect_reset,
+            commands::bisect_run,
             // Branch commands
             commands::list_branches,
             commands::create_branch,
@@ -44,7 +82,28 @@ pub fn run() {
             commands::fetch,
             commands::pull,
             commands::push,
+            commands::deepen_repository,
             commands::list_remotes,
+            commands::add_remote,
+            commands::rename_remote,
+            commands::remove_remote,
+            commands::set_remote_url,
+            commands::set_remote_push_url,
+            commands::set_remote_fetch_refspecs,
+            commands::test_remote_connection,
+            // Background fetch commands
+            commands::get_background_fetch_status,
+            commands::set_background_fetch_settings,
+            commands::resume_background_fetch,
+            // System git commands
+            commands::get_system_git_settings,
+            commands::set_system_git_settings,
+            commands::set_repo_system_git,
+            // Identity commands
+            commands::get_identity_settings,
+            commands::set_identity_settings,
+            commands::get_repo_identity,
+            commands::apply_identity_profile,
             // Diff commands
             commands::get_file_diff,
             commands::get_commit_diff,
@@ -54,9 +113,45 @@ pub fn run() {
             commands::stash_apply,
             commands::stash_drop,
             commands::stash_list,
+            // Submodule commands
+            commands::list_submodules,
+            commands::init_submodules,
+            commands::update_submodules,
+            commands::sync_submodules,
+            commands::open_submodule,
+            // Worktree commands
+            commands::list_worktrees,
+            commands::add_worktree,
+            commands::lock_worktree,
+            commands::unlock_worktree,
+            commands::prune_worktrees,
+            commands::remove_worktree,
+            // Sparse checkout commands
+            commands::get_sparse_checkout,
+            commands::enable_sparse_checkout,
+            commands::disable_sparse_checkout,
+            commands::set_sparse_patterns,
+            commands::add_sparse_patterns,
+            commands::remove_sparse_patterns,
+            // LFS commands
+            commands::get_lfs_status,
+            commands::list_lfs_locks,
+            commands::lock_lfs_file,
+            commands::unlock_lfs_file,
+            // Job commands
+            commands::list_jobs,
+            commands::get_job,
+            commands::cancel_job,
+            commands::respond_credential_request,
             // Config commands
             commands::get_git_config,
             commands::set_git_config,
+            commands::list_config_entries,
+            commands::get_config_value,
+            commands::set_config_value,
+            commands::add_config_value,
+            commands::unset_config_value,
+            commands::list_config_includes,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
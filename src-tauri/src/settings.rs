@@ -0,0 +1,198 @@
+use crate::error::GitClientError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct BackgroundFetchSettings {
+    pub enabled: bool,
+    // Time between fetches of a repository's remotes
+    pub interval_minutes: u32,
+}
+
+impl Default for BackgroundFetchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 10,
+        }
+    }
+}
+
+// Runs fetch, pull and push with the installed `git` executable instead of
+// libgit2, so core.sshCommand, credential helpers and proxies behave as in
+// a terminal
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SystemGitSettings {
+    pub enabled: bool,
+    // Per-repository overrides of `enabled`, by repository path
+    pub repos: HashMap<String, bool>,
+}
+
+impl SystemGitSettings {
+    pub fn enabled_for(&self, repo_path: &Path) -> bool {
+        self.repos
+            .get(repo_path.to_string_lossy().as_ref())
+            .copied()
+            .unwrap_or(self.enabled)
+    }
+}
+
+// A name, email and signing key to commit with
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct IdentityProfile {
+    // What the profile is called, unique among the profiles
+    pub name: String,
+    pub user_name: String,
+    pub user_email: String,
+    pub signing_key: Option<String>,
+    // gpg.format: "openpgp", "ssh" or "x509"
+    pub signing_format: Option<String>,
+}
+
+// Chooses a profile for the repositories it matches
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct IdentityRule {
+    // "remote_url" or "directory"
+    pub kind: String,
+    // A remote URL with `*` and `?` wildcards, like `*github.com*my-org/*`,
+    // or a directory the repository is in, which may use gitignore-style
+    // wildcards
+    pub pattern: String,
+    // The name of the profile to use
+    pub profile: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct IdentitySettings {
+    pub profiles: Vec<IdentityProfile>,
+    // Tried in order; the first one matching a repository applies
+    pub rules: Vec<IdentityRule>,
+}
+
+impl IdentitySettings {
+    fn validate(&self) -> Result<(), GitClientError> {
+        let invalid = |message: String| Err(GitClientError::Operation(message));
+        for (i, profile) in self.profiles.iter().enumerate() {
+            if profile.name.trim().is_empty() {
+                return invalid("Identity profiles need a name".to_string());
+            }
+            if self.profiles[..i].iter().any(|p| p.name == profile.name) {
+                return invalid(format!(
+                    "There is more than one profile named {}",
+                    profile.name
+                ));
+            }
+            if profile.user_name.trim().is_empty() || profile.user_email.trim().is_empty() {
+                return invalid(format!(
+                    "The profile {} needs a name and an email",
+                    profile.name
+                ));
+            }
+            if let Some(format) = &profile.signing_format {
+                if !matches!(format.as_str(), "openpgp" | "ssh" | "x509") {
+                    return invalid(format!(
+                        "Invalid signing format: {}. Expected 'openpgp', 'ssh' or 'x509'.",
+                        format
+                    ));
+                }
+            }
+        }
+        for rule in &self.rules {
+            if !matches!(rule.kind.as_str(), "remote_url" | "directory") {
+                return invalid(format!(
+                    "Invalid identity rule kind: {}. Expected 'remote_url' or 'directory'.",
+                    rule.kind
+                ));
+            }
+            if rule.pattern.trim().is_empty() {
+                return invalid("Identity rules need a pattern".to_string());
+            }
+            if !self.profiles.iter().any(|p| p.name == rule.profile) {
+                return invalid(format!("No identity profile is named {}", rule.profile));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Backend settings, persisted next to the repository catalog
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    pub background_fetch: BackgroundFetchSettings,
+    pub system_git: SystemGitSettings,
+    pub identities: IdentitySettings,
+}
+
+#[derive(Debug, Default)]
+pub struct SettingsStore {
+    // None until the app config directory is known
+    file: Option<PathBuf>,
+    data: AppSettings,
+}
+
+impl SettingsStore {
+    pub fn load(file: PathBuf) -> Self {
+        let data = match std::fs::read_to_string(&file) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                eprintln!("Ignoring unreadable settings: {e}");
+                AppSettings::default()
+            }),
+            Err(_) => AppSettings::default(),
+        };
+        Self {
+            file: Some(file),
+            data,
+        }
+    }
+
+    fn save(&self) -> Result<(), GitClientError> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let json = serde_json::to_string_pretty(&self.data)
+            .map_err(|e| GitClientError::Operation(e.to_string()))?;
+        // Write then rename so a crash never leaves truncated settings
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, json)?;
+        std::fs::rename(&tmp, file)?;
+        Ok(())
+    }
+
+    pub fn get(&self) -> &AppSettings {
+        &self.data
+    }
+
+    pub fn set_background_fetch(
+        &mut self,
+        settings: BackgroundFetchSettings,
+    ) -> Result<(), GitClientError> {
+        if settings.interval_minutes == 0 {
+            return Err(GitClientError::Operation(
+                "The background fetch interval must be at least one minute".to_string(),
+            ));
+        }
+        self.data.background_fetch = settings;
+        self.save()
+    }
+
+    pub fn set_system_git(&mut self, settings: SystemGitSettings) -> Result<(), GitClientError> {
+        self.data.system_git = settings;
+        self.save()
+    }
+
+    pub fn set_identities(&mut self, settings: IdentitySettings) -> Result<(), GitClientError> {
+        settings.validate()?;
+        self.data.identities = settings;
+        self.save()
+    }
+}
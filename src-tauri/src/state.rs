@@ -1,43 +1,111 @@
+use crate::background_fetch::BackgroundFetcher;
+use crate::catalog::RepoCatalog;
+use crate::error::GitClientError;
+use crate::jobs::JobRegistry;
+use crate::settings::SettingsStore;
 use crate::watcher::RepoWatcher;
 use git2::Repository;
 use parking_lot::Mutex;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 pub struct RepoState {
-    pub repository: Option<Repository>,
-    pub path: Option<PathBuf>,
+    pub repository: Mutex<Repository>,
+    pub path: PathBuf,
+    // Canonical git directory, used to detect a repository that is already open
+    git_dir: PathBuf,
+    pub watcher: Mutex<Option<RepoWatcher>>,
+}
+
+impl RepoState {
+    pub fn new(repository: Repository, path: PathBuf) -> Self {
+        let git_dir = canonical(repository.path());
+        Self {
+            repository: Mutex::new(repository),
+            path,
+            git_dir,
+            watcher: Mutex::new(None),
+        }
+    }
+
+    // A separate handle for background jobs, so that a slow network operation
+    // doesn't hold the lock every other command needs
+    pub fn reopen(&self) -> Result<Repository, GitClientError> {
+        Repository::open(&self.path).map_err(GitClientError::Git)
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
 pub struct AppState {
-    pub repo: Mutex<RepoState>,
-    pub watcher: Mutex<Option<RepoWatcher>>,
+    pub repos: Mutex<HashMap<String, Arc<RepoState>>>,
+    pub catalog: Mutex<RepoCatalog>,
+    pub jobs: JobRegistry,
+    pub settings: Mutex<SettingsStore>,
+    pub background_fetch: BackgroundFetcher,
+    next_id: AtomicU64,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
-            repo: Mutex::new(RepoState {
-                repository: None,
-                path: None,
-            }),
-            watcher: Mutex::new(None),
+            repos: Mutex::new(HashMap::new()),
+            catalog: Mutex::new(RepoCatalog::default()),
+            jobs: JobRegistry::default(),
+            settings: Mutex::new(SettingsStore::default()),
+            background_fetch: BackgroundFetcher::default(),
+            next_id: AtomicU64::new(1),
         }
     }
 
-    pub fn set_repository(&self, repo: Repository, path: PathBuf) {
-        let mut guard = self.repo.lock();
-        guard.repository = Some(repo);
-        guard.path = Some(path);
+    pub fn next_repo_id(&self) -> String {
+        format!("repo-{}", self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    // Returns the ID of an open repository sharing `repo`'s git directory
+    pub fn find_repo_id(&self, repo: &Repository) -> Option<String> {
+        let git_dir = canonical(repo.path());
+        self.repos
+            .lock()
+            .iter()
+            .find(|(_, state)| state.git_dir == git_dir)
+            .map(|(id, _)| id.clone())
+    }
+
+    pub fn set_repository(&self, repo_id: String, state: RepoState) {
+        self.repos.lock().insert(repo_id, Arc::new(state));
+    }
+
+    pub fn get_repo(&self, repo_id: &str) -> Result<Arc<RepoState>, GitClientError> {
+        self.repos
+            .lock()
+            .get(repo_id)
+            .cloned()
+            .ok_or_else(|| GitClientError::UnknownRepository(repo_id.to_string()))
     }
 
-    pub fn clear_repository(&self) {
-        let mut guard = self.repo.lock();
-        guard.repository = None;
-        guard.path = None;
+    pub fn remove_repository(&self, repo_id: &str) -> Option<Arc<RepoState>> {
+        self.repos.lock().remove(repo_id)
     }
 
-    pub fn get_repo_path(&self) -> Option<PathBuf> {
-        self.repo.lock().path.clone()
+    // Open repositories ordered by ID, i.e. the order they were opened in
+    pub fn list_repositories(&self) -> Vec<(String, Arc<RepoState>)> {
+        let mut repos: Vec<_> = self
+            .repos
+            .lock()
+            .iter()
+            .map(|(id, state)| (id.clone(), state.clone()))
+            .collect();
+        repos.sort_by_key(|(id, _)| {
+            id.trim_start_matches("repo-")
+                .parse::<u64>()
+                .unwrap_or(u64::MAX)
+        });
+        repos
     }
 }
 
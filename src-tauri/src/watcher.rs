@@ -1,49 +1,110 @@
+use crate::git::common_dir;
+use git2::Repository;
 use notify::RecursiveMode;
 use notify_debouncer_mini::{new_debouncer, DebouncedEventKind, Debouncer};
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc;
 use std::time::Duration;
 use tauri::{AppHandle, Emitter};
 
+#[derive(Debug, Serialize, Clone)]
+pub struct RepoChangedEvent {
+    pub repo_id: String,
+}
+
 pub struct RepoWatcher {
     _debouncer: Debouncer<notify::RecommendedWatcher>,
     _thread: std::thread::JoinHandle<()>,
 }
 
-fn should_emit_for_path(path: &Path, repo_root: &Path) -> bool {
-    let git_dir = repo_root.join(".git");
-    if let Ok(relative) = path.strip_prefix(&git_dir) {
-        let rel_str = relative.to_string_lossy();
-        rel_str.starts_with("HEAD") || rel_str.starts_with("refs") || rel_str == "index"
-    } else {
-        // Outside .git/ — always emit
-        true
+// Where a repository keeps its files. For a linked worktree `.git` is a file
+// pointing at `<main>/.git/worktrees/<name>`, with refs in the main `.git`.
+struct WatchedPaths {
+    workdir: Option<PathBuf>,
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+}
+
+impl WatchedPaths {
+    fn from_repo(repo: &Repository) -> Self {
+        let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+        Self {
+            workdir: repo.workdir().map(canonical),
+            git_dir: canonical(repo.path()),
+            common_dir: canonical(&common_dir(repo)),
+        }
+    }
+
+    fn is_inside_workdir(&self, path: &Path) -> bool {
+        self.workdir
+            .as_deref()
+            .map(|workdir| path.starts_with(workdir))
+            .unwrap_or(false)
+    }
+
+    fn should_emit_for_path(&self, path: &Path) -> bool {
+        if let Ok(relative) = path.strip_prefix(&self.git_dir) {
+            let rel_str = relative.to_string_lossy();
+            return rel_str.starts_with("HEAD")
+                || rel_str.starts_with("refs")
+                || rel_str == "index";
+        }
+        if let Ok(relative) = path.strip_prefix(&self.common_dir) {
+            // Other worktrees' HEAD and index live here too; only shared refs matter
+            let rel_str = relative.to_string_lossy();
+            return rel_str.starts_with("refs") || rel_str == "packed-refs";
+        }
+        if let Some(workdir) = &self.workdir {
+            if let Ok(relative) = path.strip_prefix(workdir) {
+                // The `.git` file of a linked worktree
+                return relative != Path::new(".git");
+            }
+        }
+        false
     }
 }
 
 impl RepoWatcher {
-    pub fn start(repo_path: PathBuf, app_handle: AppHandle) -> Result<Self, String> {
+    pub fn start(
+        repo: &Repository,
+        repo_id: String,
+        app_handle: AppHandle,
+    ) -> Result<Self, String> {
         let (tx, rx) = mpsc::channel();
 
         let mut debouncer = new_debouncer(Duration::from_millis(500), tx)
             .map_err(|e| format!("Failed to create file watcher: {e}"))?;
 
-        debouncer
-            .watcher()
-            .watch(&repo_path, RecursiveMode::Recursive)
-            .map_err(|e| format!("Failed to watch path: {e}"))?;
+        let paths = WatchedPaths::from_repo(repo);
+        let mut roots: Vec<PathBuf> = paths.workdir.iter().cloned().collect();
+        for dir in [&paths.common_dir, &paths.git_dir] {
+            if !paths.is_inside_workdir(dir) && !roots.iter().any(|root| dir.starts_with(root)) {
+                roots.push(dir.clone());
+            }
+        }
+        for root in &roots {
+            debouncer
+                .watcher()
+                .watch(root, RecursiveMode::Recursive)
+                .map_err(|e| format!("Failed to watch path: {e}"))?;
+        }
 
-        let repo_root = repo_path.clone();
         let thread = std::thread::spawn(move || {
             while let Ok(result) = rx.recv() {
                 match result {
                     Ok(events) => {
                         let dominated_by_any_real_change = events.iter().any(|event| {
                             event.kind == DebouncedEventKind::Any
-                                && should_emit_for_path(&event.path, &repo_root)
+                                && paths.should_emit_for_path(&event.path)
                         });
                         if dominated_by_any_real_change {
-                            let _ = app_handle.emit("repo-changed", ());
+                            let _ = app_handle.emit(
+                                "repo-changed",
+                                RepoChangedEvent {
+                                    repo_id: repo_id.clone(),
+                                },
+                            );
                         }
                     }
                     Err(e) => {
@@ -6,9 +6,11 @@ import { Header } from '@/components/layout/Header';
 import { MainPanel } from '@/components/layout/MainPanel';
 import { Sidebar } from '@/components/layout/Sidebar';
 import { CloneDialog } from '@/components/repository/CloneDialog';
+import { CredentialDialog } from '@/components/repository/CredentialDialog';
 import { InitDialog } from '@/components/repository/InitDialog';
 import { RepoSelector } from '@/components/repository/RepoSelector';
 import { StashDialog } from '@/components/staging/StashDialog';
+import type { RemoteRefsUpdatedEvent, RepoChangedEvent } from '@/lib/types';
 import { useRepoStore } from '@/stores/repoStore';
 import { useSettingsStore } from '@/stores/settingsStore';
 import { useUiStore } from '@/stores/uiStore';
@@ -35,12 +37,9 @@ function App() {
     document.documentElement.classList.toggle('dark', theme === 'dark');
   }, [theme]);
 
-  // Auto-open most recent repository on startup
+  // Reopen the previous session's repositories on startup
   useEffect(() => {
-    const recentRepos = useSettingsStore.getState().recentRepos;
-    if (recentRepos.length > 0 && !useRepoStore.getState().repoInfo) {
-      useRepoStore.getState().openRepository(recentRepos[0]);
-    }
+    useRepoStore.getState().restoreSession();
   }, []);
 
   // Add to recent repos when opening
@@ -64,7 +63,8 @@ function App() {
     if (!repoInfo) return;
 
     let throttleTimer: ReturnType<typeof setTimeout> | null = null;
-    const unlisten = listen('repo-changed', () => {
+    const unlisten = listen<RepoChangedEvent>('repo-changed', (event) => {
+      if (event.payload.repo_id !== repoInfo.repo_id) return;
       if (throttleTimer) return;
       throttleTimer = setTimeout(() => {
         throttleTimer = null;
@@ -78,6 +78,23 @@ function App() {
     };
   }, [repoInfo]);
 
+  // Background fetches move remote-tracking branches, changing ahead/behind
+  useEffect(() => {
+    if (!repoInfo) return;
+
+    const unlisten = listen<RemoteRefsUpdatedEvent>(
+      'remote-refs-updated',
+      (event) => {
+        if (event.payload.repo_id !== repoInfo.repo_id) return;
+        useRepoStore.getState().refreshBranches();
+      },
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [repoInfo]);
+
   const handleMouseDown = (e: React.MouseEvent) => {
     e.preventDefault();
     isDragging.current = true;
@@ -200,6 +217,7 @@ function App() {
       <InitDialog />
       <CreateBranchDialog />
       <StashDialog />
+      <CredentialDialog />
     </div>
   );
 }
@@ -6,12 +6,24 @@ import { useRepoStore } from '@/stores/repoStore';
 
 export function SidebarCommitBox() {
   const [message, setMessage] = useState('');
-  const { createCommit, status } = useRepoStore();
+  const { createCommit, status, identity } = useRepoStore();
   const hasStaged = (status?.staged.length || 0) > 0;
+  // The profile the repository's rules choose, when the configured user
+  // isn't the profile's
+  const mismatchedProfile =
+    identity?.profile && !identity.matches ? identity.profile : null;
+  const configuredUser =
+    identity?.user_name && identity.user_email
+      ? `${identity.user_name} <${identity.user_email}>`
+      : 'an unset user';
 
   const handleCommit = () => {
     if (message.trim()) {
-      createCommit(message.trim());
+      // Committing with the warning showing confirms the identity
+      createCommit(
+        message.trim(),
+        mismatchedProfile ? { confirm_identity: true } : undefined,
+      );
       setMessage('');
     }
   };
@@ -31,13 +43,18 @@ export function SidebarCommitBox() {
         onChange={(e) => setMessage(e.target.value)}
         onKeyDown={handleKeyDown}
       />
+      {mismatchedProfile && (
+        <p className="mt-1 rounded-md border border-amber-500/25 bg-amber-500/10 px-2 py-1 text-xs text-amber-600 dark:text-amber-400">
+          {`The ${mismatchedProfile.name} profile commits as ${mismatchedProfile.user_name} <${mismatchedProfile.user_email}>, but this commit would be made as ${configuredUser}.`}
+        </p>
+      )}
       <Button
         className="w-full mt-2"
         disabled={!hasStaged || !message.trim()}
         onClick={handleCommit}
       >
         <Check className="h-4 w-4 mr-2" />
-        Commit
+        {mismatchedProfile ? 'Commit anyway' : 'Commit'}
       </Button>
     </div>
   );
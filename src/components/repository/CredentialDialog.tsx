@@ -0,0 +1,216 @@
+import { listen } from '@tauri-apps/api/event';
+import { KeyRound, ShieldAlert } from 'lucide-react';
+import { useEffect, useState } from 'react';
+import { Button } from '@/components/common/Button';
+import { Dialog } from '@/components/common/Dialog/Dialog';
+import { DialogContent } from '@/components/common/Dialog/DialogContent';
+import { DialogDescription } from '@/components/common/Dialog/DialogDescription';
+import { DialogFooter } from '@/components/common/Dialog/DialogFooter';
+import { DialogHeader } from '@/components/common/Dialog/DialogHeader';
+import { DialogTitle } from '@/components/common/Dialog/DialogTitle';
+import { Input } from '@/components/common/Input';
+import * as tauri from '@/lib/tauri';
+import type { CredentialPromptEvent } from '@/lib/types';
+
+const TITLES: Record<CredentialPromptEvent['kind'], string> = {
+  userpass: 'Sign In',
+  ssh_passphrase: 'SSH Key Passphrase',
+  ssh_key: 'Choose SSH Key',
+  host_key: 'Verify Host Key',
+  certificate: 'Untrusted Certificate',
+};
+
+// Answers credential prompts from network jobs, one at a time
+export function CredentialDialog() {
+  const [prompts, setPrompts] = useState<CredentialPromptEvent[]>([]);
+  const [username, setUsername] = useState('');
+  const [secret, setSecret] = useState('');
+  const [keyPath, setKeyPath] = useState('');
+
+  const prompt = prompts[0];
+
+  useEffect(() => {
+    const requested = listen<CredentialPromptEvent>(
+      'credential-requested',
+      (event) => setPrompts((queue) => [...queue, event.payload]),
+    );
+    // The job was cancelled or the prompt timed out
+    const closed = listen<string>('credential-request-closed', (event) =>
+      setPrompts((queue) => queue.filter((p) => p.prompt_id !== event.payload)),
+    );
+    return () => {
+      requested.then((fn) => fn());
+      closed.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    setUsername(prompt?.username ?? '');
+    setSecret('');
+    setKeyPath(prompt?.key_path ?? prompt?.available_keys[0] ?? '');
+  }, [prompt]);
+
+  const answer = async (submit: boolean) => {
+    if (!prompt) return;
+    setPrompts((queue) => queue.slice(1));
+    const response = !submit
+      ? null
+      : prompt.kind === 'host_key'
+        ? { trust_host_key: true }
+        : prompt.kind === 'certificate'
+          ? { trust_certificate: true }
+          : prompt.kind === 'userpass'
+            ? { username, password: secret }
+            : { key_path: keyPath, passphrase: secret || null };
+    try {
+      await tauri.respondCredentialRequest(prompt.prompt_id, response);
+    } catch (e) {
+      console.error('Failed to answer credential prompt:', e);
+    }
+  };
+
+  const canSubmit =
+    prompt?.kind === 'host_key' || prompt?.kind === 'certificate'
+      ? true
+      : prompt?.kind === 'userpass'
+        ? username !== '' && secret !== ''
+        : prompt?.kind === 'ssh_passphrase'
+          ? secret !== ''
+          : keyPath !== '';
+  const hostKey = prompt?.host_key;
+  const certificate = prompt?.certificate;
+
+  return (
+    <Dialog open={!!prompt} onOpenChange={(open) => !open && answer(false)}>
+      <DialogContent>
+        {prompt && (
+          <>
+            <DialogHeader>
+              <DialogTitle>{TITLES[prompt.kind]}</DialogTitle>
+              <DialogDescription>
+                {prompt.retry
+                  ? 'The previous credentials were rejected. '
+                  : ''}
+                {prompt.url}
+              </DialogDescription>
+            </DialogHeader>
+            {hostKey ? (
+              <div className="space-y-3 py-4 text-sm">
+                <p>
+                  {hostKey.status === 'changed'
+                    ? `The host key for ${hostKey.host} has changed. Someone could be intercepting the connection, or the server's key was replaced.`
+                    : `The authenticity of ${hostKey.host} can't be established.`}
+                </p>
+                <p className="font-mono break-all">
+                  {hostKey.key_type} {hostKey.fingerprint}
+                </p>
+                {hostKey.known_fingerprints.length > 0 && (
+                  <div>
+                    <span className="text-muted-foreground">
+                      Previously known:
+                    </span>
+                    {hostKey.known_fingerprints.map((fingerprint) => (
+                      <p key={fingerprint} className="font-mono break-all">
+                        {fingerprint}
+                      </p>
+                    ))}
+                  </div>
+                )}
+              </div>
+            ) : certificate ? (
+              <div className="space-y-3 py-4 text-sm">
+                <p>
+                  The certificate of {certificate.host} isn't trusted (
+                  {certificate.error}). Someone could be intercepting the
+                  connection, or the server uses a certificate authority that
+                  isn't configured.
+                </p>
+                <p className="font-mono break-all">
+                  SHA-256 {certificate.fingerprint}
+                </p>
+                <p className="text-muted-foreground">
+                  Trusting it lasts until the app quits. To trust a company
+                  certificate authority permanently, set http.sslCAInfo.
+                </p>
+              </div>
+            ) : (
+              <div className="space-y-4 py-4">
+                {prompt.kind === 'userpass' && (
+                  <label
+                    htmlFor="credential-username"
+                    className="block text-sm font-medium"
+                  >
+                    <span className="mb-1.5 block">Username</span>
+                    <Input
+                      id="credential-username"
+                      value={username}
+                      onChange={(e) => setUsername(e.target.value)}
+                    />
+                  </label>
+                )}
+                {prompt.kind === 'ssh_key' && (
+                  <label
+                    htmlFor="credential-key"
+                    className="block text-sm font-medium"
+                  >
+                    <span className="mb-1.5 block">Private Key</span>
+                    <Input
+                      id="credential-key"
+                      list="credential-keys"
+                      value={keyPath}
+                      onChange={(e) => setKeyPath(e.target.value)}
+                    />
+                    <datalist id="credential-keys">
+                      {prompt.available_keys.map((key) => (
+                        <option key={key} value={key} />
+                      ))}
+                    </datalist>
+                  </label>
+                )}
+                {prompt.kind === 'ssh_passphrase' && (
+                  <p className="text-sm text-muted-foreground font-mono truncate">
+                    {prompt.key_path}
+                  </p>
+                )}
+                <label
+                  htmlFor="credential-secret"
+                  className="block text-sm font-medium"
+                >
+                  <span className="mb-1.5 block">
+                    {prompt.kind === 'userpass'
+                      ? 'Password or Token'
+                      : prompt.kind === 'ssh_key'
+                        ? 'Passphrase (if the key has one)'
+                        : 'Passphrase'}
+                  </span>
+                  <Input
+                    id="credential-secret"
+                    type="password"
+                    value={secret}
+                    onChange={(e) => setSecret(e.target.value)}
+                    onKeyDown={(e) => {
+                      if (e.key === 'Enter' && canSubmit) answer(true);
+                    }}
+                  />
+                </label>
+              </div>
+            )}
+            <DialogFooter>
+              <Button variant="outline" onClick={() => answer(false)}>
+                Cancel
+              </Button>
+              <Button onClick={() => answer(true)} disabled={!canSubmit}>
+                {hostKey || certificate ? (
+                  <ShieldAlert className="h-4 w-4 mr-2" />
+                ) : (
+                  <KeyRound className="h-4 w-4 mr-2" />
+                )}
+                {hostKey || certificate ? 'Trust and Connect' : 'Continue'}
+              </Button>
+            </DialogFooter>
+          </>
+        )}
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -1,205 +1,775 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import type {
+  AddWorktreeOptions,
+  BackgroundFetchSettings,
+  BackgroundFetchStatus,
+  BisectStatus,
   BranchInfo,
+  CloneOptions,
   CommitDetails,
   CommitDiff,
   CommitGraphEntry,
   CommitInfo,
+  CommitOptions,
+  ConfigEntryInfo,
+  ConfigInclude,
+  ConfigLevel,
+  ConfigValueType,
+  CredentialResponse,
+  FetchRemoteOptions,
   FileDiff,
   GitConfig,
+  IdentitySettings,
+  JobInfo,
+  LfsLock,
+  LfsStatus,
   MergeResult,
-  PullResult,
+  OpenRepoInfo,
+  PushRemoteOptions,
   RemoteInfo,
+  RepoCatalogView,
+  RepoIdentity,
   RepoInfo,
   RepoStatus,
+  ScannedRepo,
+  SessionRestore,
+  SparseCheckoutInfo,
+  SplitState,
   StashEntry,
+  SubmoduleInfo,
+  SystemGitSettings,
+  SystemGitStatus,
+  WorktreeInfo,
 } from './types';
 
 // Repository commands
-export async function openRepository(path: string): Promise<RepoInfo> {
+export async function openRepository(path: string): Promise<OpenRepoInfo> {
   return invoke('open_repository', { path });
 }
 
-export async function initRepository(path: string): Promise<RepoInfo> {
+export async function initRepository(path: string): Promise<OpenRepoInfo> {
   return invoke('init_repository', { path });
 }
 
 export async function cloneRepository(
   url: string,
   path: string,
-): Promise<RepoInfo> {
-  return invoke('clone_repository', { url, path });
+  options?: CloneOptions,
+): Promise<JobInfo> {
+  return invoke('clone_repository', { url, path, options: options ?? null });
 }
 
-export async function getStatus(): Promise<RepoStatus> {
-  return invoke('get_status');
+export async function getStatus(repoId: string): Promise<RepoStatus> {
+  return invoke('get_status', { repoId });
 }
 
-export async function getRepoInfo(): Promise<RepoInfo> {
-  return invoke('get_repo_info');
+export async function getRepoInfo(repoId: string): Promise<RepoInfo> {
+  return invoke('get_repo_info', { repoId });
 }
 
-export async function closeRepository(): Promise<void> {
-  return invoke('close_repository');
+export async function closeRepository(repoId: string): Promise<void> {
+  return invoke('close_repository', { repoId });
 }
 
-export async function getCurrentRepoPath(): Promise<string | null> {
-  return invoke('get_current_repo_path');
+export async function listOpenRepositories(): Promise<OpenRepoInfo[]> {
+  return invoke('list_open_repositories');
+}
+
+export async function scanRepositories(
+  root: string,
+  maxDepth?: number,
+): Promise<ScannedRepo[]> {
+  return invoke('scan_repositories', { root, maxDepth: maxDepth ?? null });
+}
+
+// Catalog commands
+export async function getRepoCatalog(): Promise<RepoCatalogView> {
+  return invoke('get_repo_catalog');
+}
+
+export async function setRepoFavorite(
+  path: string,
+  favorite: boolean,
+): Promise<void> {
+  return invoke('set_repo_favorite', { path, favorite });
+}
+
+export async function setRepoAlias(
+  path: string,
+  alias: string | null,
+): Promise<void> {
+  return invoke('set_repo_alias', { path, alias });
+}
+
+export async function setRepoGroup(
+  path: string,
+  group: string | null,
+): Promise<void> {
+  return invoke('set_repo_group', { path, group });
+}
+
+export async function addRepoGroup(name: string): Promise<void> {
+  return invoke('add_repo_group', { name });
+}
+
+export async function renameRepoGroup(
+  oldName: string,
+  newName: string,
+): Promise<void> {
+  return invoke('rename_repo_group', { oldName, newName });
+}
+
+export async function removeRepoGroup(name: string): Promise<void> {
+  return invoke('remove_repo_group', { name });
+}
+
+export async function removeFromCatalog(path: string): Promise<void> {
+  return invoke('remove_from_catalog', { path });
+}
+
+export async function restoreSession(): Promise<SessionRestore> {
+  return invoke('restore_session');
 }
 
 // Staging commands
-export async function stageFile(path: string): Promise<void> {
-  return invoke('stage_file', { path });
+export async function stageFile(repoId: string, path: string): Promise<void> {
+  return invoke('stage_file', { repoId, path });
 }
 
-export async function unstageFile(path: string): Promise<void> {
-  return invoke('unstage_file', { path });
+export async function unstageFile(repoId: string, path: string): Promise<void> {
+  return invoke('unstage_file', { repoId, path });
 }
 
-export async function stageAll(): Promise<void> {
-  return invoke('stage_all');
+export async function stageAll(repoId: string): Promise<void> {
+  return invoke('stage_all', { repoId });
 }
 
-export async function unstageAll(): Promise<void> {
-  return invoke('unstage_all');
+export async function unstageAll(repoId: string): Promise<void> {
+  return invoke('unstage_all', { repoId });
 }
 
-export async function discardChanges(path: string): Promise<void> {
-  return invoke('discard_changes', { path });
+export async function discardChanges(
+  repoId: string,
+  path: string,
+): Promise<void> {
+  return invoke('discard_changes', { repoId, path });
 }
 
-export async function discardAllChanges(): Promise<void> {
-  return invoke('discard_all_changes');
+export async function discardAllChanges(repoId: string): Promise<void> {
+  return invoke('discard_all_changes', { repoId });
 }
 
 // Commit commands
-export async function createCommit(message: string): Promise<CommitInfo> {
-  return invoke('create_commit', { message });
+export async function createCommit(
+  repoId: string,
+  message: string,
+  options?: CommitOptions,
+): Promise<CommitInfo> {
+  return invoke('create_commit', { repoId, message, options: options ?? null });
 }
 
 export async function getCommitHistory(
+  repoId: string,
   limit: number = 50,
   skip: number = 0,
 ): Promise<CommitInfo[]> {
-  return invoke('get_commit_history', { limit, skip });
+  return invoke('get_commit_history', { repoId, limit, skip });
 }
 
 export async function getCommitHistoryWithGraph(
+  repoId: string,
   limit: number = 50,
   skip: number = 0,
   branchName?: string | null,
 ): Promise<CommitGraphEntry[]> {
   return invoke('get_commit_history_with_graph', {
+    repoId,
     limit,
     skip,
     branchName: branchName ?? null,
   });
 }
 
-export async function getCommitDetails(oid: string): Promise<CommitDetails> {
-  return invoke('get_commit_details', { oid });
+export async function getCommitDetails(
+  repoId: string,
+  oid: string,
+): Promise<CommitDetails> {
+  return invoke('get_commit_details', { repoId, oid });
 }
 
 export async function resetToCommit(
+  repoId: string,
   commitId: string,
   mode: 'soft' | 'hard',
 ): Promise<void> {
-  return invoke('reset_to_commit', { commitId, mode });
+  return invoke('reset_to_commit', { repoId, commitId, mode });
 }
 
 export async function squashCommits(
+  repoId: string,
   commitIds: string[],
   message: string,
 ): Promise<void> {
-  return invoke('squash_commits', { commitIds, message });
+  return invoke('squash_commits', { repoId, commitIds, message });
+}
+
+export async function createFixupCommit(
+  repoId: string,
+  targetId: string,
+  kind: 'fixup' | 'squash' | 'amend',
+  message?: string,
+): Promise<CommitInfo> {
+  return invoke('create_fixup_commit', {
+    repoId,
+    targetId,
+    kind,
+    message: message ?? null,
+  });
+}
+
+export async function autosquash(repoId: string): Promise<void> {
+  return invoke('autosquash', { repoId });
+}
+
+export async function startSplitCommit(
+  repoId: string,
+  commitId: string,
+): Promise<SplitState> {
+  return invoke('start_split_commit', { repoId, commitId });
+}
+
+export async function continueSplitCommit(
+  repoId: string,
+): Promise<SplitState | null> {
+  return invoke('continue_split_commit', { repoId });
+}
+
+export async function abortSplitCommit(repoId: string): Promise<void> {
+  return invoke('abort_split_commit', { repoId });
+}
+
+export async function getSplitState(
+  repoId: string,
+): Promise<SplitState | null> {
+  return invoke('get_split_state', { repoId });
+}
+
+// Bisect commands
+export async function bisectStart(
+  repoId: string,
+  bad: string | null,
+  good: string[],
+): Promise<BisectStatus> {
+  return invoke('bisect_start', { repoId, bad, good });
+}
+
+export async function bisectMark(
+  repoId: string,
+  term: 'good' | 'bad' | 'skip',
+  commit?: string,
+): Promise<BisectStatus> {
+  return invoke('bisect_mark', { repoId, term, commit: commit ?? null });
+}
+
+export async function bisectStatus(repoId: string): Promise<BisectStatus> {
+  return invoke('bisect_status', { repoId });
+}
+
+export async function bisectReset(repoId: string): Promise<void> {
+  return invoke('bisect_reset', { repoId });
+}
+
+export async function bisectRun(
+  repoId: string,
+  command: string,
+): Promise<JobInfo> {
+  return invoke('bisect_run', { repoId, command });
 }
 
 // Branch commands
-export async function listBranches(): Promise<BranchInfo[]> {
-  return invoke('list_branches');
+export async function listBranches(repoId: string): Promise<BranchInfo[]> {
+  return invoke('list_branches', { repoId });
 }
 
 export async function createBranch(
+  repoId: string,
   name: string,
   sourceBranch?: string,
 ): Promise<BranchInfo> {
-  return invoke('create_branch', { name, sourceBranch });
+  return invoke('create_branch', { repoId, name, sourceBranch });
 }
 
-export async function checkoutBranch(name: string): Promise<void> {
-  return invoke('checkout_branch', { name });
+export async function checkoutBranch(
+  repoId: string,
+  name: string,
+): Promise<void> {
+  return invoke('checkout_branch', { repoId, name });
 }
 
-export async function deleteBranch(name: string): Promise<void> {
-  return invoke('delete_branch', { name });
+export async function deleteBranch(
+  repoId: string,
+  name: string,
+): Promise<void> {
+  return invoke('delete_branch', { repoId, name });
 }
 
-export async function mergeBranch(name: string): Promise<MergeResult> {
-  return invoke('merge_branch', { name });
+export async function mergeBranch(
+  repoId: string,
+  name: string,
+): Promise<MergeResult> {
+  return invoke('merge_branch', { repoId, name });
 }
 
 // Remote commands
-export async function fetchRemote(remote: string): Promise<void> {
-  return invoke('fetch', { remote });
+export async function fetchRemote(
+  repoId: string,
+  remote: string,
+  options?: FetchRemoteOptions,
+): Promise<JobInfo> {
+  return invoke('fetch', { repoId, remote, options: options ?? null });
 }
 
 export async function pullRemote(
+  repoId: string,
   remote: string,
   branch: string,
-): Promise<PullResult> {
-  return invoke('pull', { remote, branch });
+): Promise<JobInfo> {
+  return invoke('pull', { repoId, remote, branch });
 }
 
 export async function pushRemote(
+  repoId: string,
   remote: string,
   branch: string,
+  options?: PushRemoteOptions,
+): Promise<JobInfo> {
+  return invoke('push', { repoId, remote, branch, options: options ?? null });
+}
+
+export async function deepenRepository(
+  repoId: string,
+  remote: string,
+  depth?: number,
+): Promise<JobInfo> {
+  return invoke('deepen_repository', { repoId, remote, depth: depth ?? null });
+}
+
+export async function listRemotes(repoId: string): Promise<RemoteInfo[]> {
+  return invoke('list_remotes', { repoId });
+}
+
+export async function addRemote(
+  repoId: string,
+  name: string,
+  url: string,
+  fetchRefspec?: string,
+): Promise<RemoteInfo> {
+  return invoke('add_remote', {
+    repoId,
+    name,
+    url,
+    fetchRefspec: fetchRefspec ?? null,
+  });
+}
+
+// Resolves with refspecs that could not be renamed automatically
+export async function renameRemote(
+  repoId: string,
+  oldName: string,
+  newName: string,
+): Promise<string[]> {
+  return invoke('rename_remote', { repoId, oldName, newName });
+}
+
+export async function removeRemote(
+  repoId: string,
+  name: string,
 ): Promise<void> {
-  return invoke('push', { remote, branch });
+  return invoke('remove_remote', { repoId, name });
+}
+
+export async function setRemoteUrl(
+  repoId: string,
+  name: string,
+  url: string,
+): Promise<RemoteInfo> {
+  return invoke('set_remote_url', { repoId, name, url });
+}
+
+// null removes the separate push URL
+export async function setRemotePushUrl(
+  repoId: string,
+  name: string,
+  pushUrl: string | null,
+): Promise<RemoteInfo> {
+  return invoke('set_remote_push_url', { repoId, name, pushUrl });
 }
 
-export async function listRemotes(): Promise<RemoteInfo[]> {
-  return invoke('list_remotes');
+export async function setRemoteFetchRefspecs(
+  repoId: string,
+  name: string,
+  refspecs: string[],
+): Promise<RemoteInfo> {
+  return invoke('set_remote_fetch_refspecs', { repoId, name, refspecs });
+}
+
+// `remote` is a remote name or a URL. The job's result is a
+// RemoteConnectionTest.
+export async function testRemoteConnection(
+  repoId: string,
+  remote: string,
+  push: boolean = false,
+): Promise<JobInfo> {
+  return invoke('test_remote_connection', { repoId, remote, push });
+}
+
+// Background fetch commands
+export async function getBackgroundFetchStatus(): Promise<BackgroundFetchStatus> {
+  return invoke('get_background_fetch_status');
+}
+
+export async function setBackgroundFetchSettings(
+  settings: BackgroundFetchSettings,
+): Promise<BackgroundFetchStatus> {
+  return invoke('set_background_fetch_settings', { settings });
+}
+
+export async function resumeBackgroundFetch(
+  repoId: string,
+): Promise<BackgroundFetchStatus> {
+  return invoke('resume_background_fetch', { repoId });
+}
+
+// System git commands
+export async function getSystemGitSettings(): Promise<SystemGitStatus> {
+  return invoke('get_system_git_settings');
+}
+
+export async function setSystemGitSettings(
+  settings: SystemGitSettings,
+): Promise<SystemGitStatus> {
+  return invoke('set_system_git_settings', { settings });
+}
+
+// `enabled` of null follows the global setting again
+export async function setRepoSystemGit(
+  repoId: string,
+  enabled: boolean | null,
+): Promise<SystemGitStatus> {
+  return invoke('set_repo_system_git', { repoId, enabled });
+}
+
+// Identity commands
+export async function getIdentitySettings(): Promise<IdentitySettings> {
+  return invoke('get_identity_settings');
+}
+
+export async function setIdentitySettings(
+  settings: IdentitySettings,
+): Promise<IdentitySettings> {
+  return invoke('set_identity_settings', { settings });
+}
+
+export async function getRepoIdentity(repoId: string): Promise<RepoIdentity> {
+  return invoke('get_repo_identity', { repoId });
+}
+
+// Writes the profile's user and signing settings to the local config
+export async function applyIdentityProfile(
+  repoId: string,
+  profile: string,
+): Promise<RepoIdentity> {
+  return invoke('apply_identity_profile', { repoId, profile });
 }
 
 // Diff commands
 export async function getFileDiff(
+  repoId: string,
   path: string,
   staged: boolean,
 ): Promise<FileDiff> {
-  return invoke('get_file_diff', { path, staged });
+  return invoke('get_file_diff', { repoId, path, staged });
 }
 
-export async function getCommitDiff(oid: string): Promise<CommitDiff> {
-  return invoke('get_commit_diff', { oid });
+export async function getCommitDiff(
+  repoId: string,
+  oid: string,
+): Promise<CommitDiff> {
+  return invoke('get_commit_diff', { repoId, oid });
 }
 
 // Stash commands
-export async function stashSave(message?: string): Promise<void> {
-  return invoke('stash_save', { message });
+export async function stashSave(
+  repoId: string,
+  message?: string,
+): Promise<void> {
+  return invoke('stash_save', { repoId, message });
+}
+
+export async function stashPop(repoId: string, index: number): Promise<void> {
+  return invoke('stash_pop', { repoId, index });
+}
+
+export async function stashApply(repoId: string, index: number): Promise<void> {
+  return invoke('stash_apply', { repoId, index });
+}
+
+export async function stashDrop(repoId: string, index: number): Promise<void> {
+  return invoke('stash_drop', { repoId, index });
+}
+
+export async function stashList(repoId: string): Promise<StashEntry[]> {
+  return invoke('stash_list', { repoId });
+}
+
+// Submodule commands
+export async function listSubmodules(repoId: string): Promise<SubmoduleInfo[]> {
+  return invoke('list_submodules', { repoId });
+}
+
+export async function initSubmodules(
+  repoId: string,
+  names: string[] = [],
+): Promise<void> {
+  return invoke('init_submodules', { repoId, names });
+}
+
+export async function updateSubmodules(
+  repoId: string,
+  names: string[] = [],
+  init: boolean = true,
+  recursive: boolean = true,
+): Promise<JobInfo> {
+  return invoke('update_submodules', { repoId, names, init, recursive });
+}
+
+export async function syncSubmodules(
+  repoId: string,
+  names: string[] = [],
+  recursive: boolean = true,
+): Promise<void> {
+  return invoke('sync_submodules', { repoId, names, recursive });
+}
+
+export async function openSubmodule(
+  repoId: string,
+  name: string,
+): Promise<OpenRepoInfo> {
+  return invoke('open_submodule', { repoId, name });
+}
+
+// Worktree commands
+export async function listWorktrees(repoId: string): Promise<WorktreeInfo[]> {
+  return invoke('list_worktrees', { repoId });
+}
+
+export async function addWorktree(
+  repoId: string,
+  options: AddWorktreeOptions,
+): Promise<WorktreeInfo> {
+  return invoke('add_worktree', { repoId, options });
+}
+
+export async function lockWorktree(
+  repoId: string,
+  name: string,
+  reason?: string,
+): Promise<void> {
+  return invoke('lock_worktree', { repoId, name, reason: reason ?? null });
+}
+
+export async function unlockWorktree(
+  repoId: string,
+  name: string,
+): Promise<void> {
+  return invoke('unlock_worktree', { repoId, name });
+}
+
+export async function pruneWorktrees(repoId: string): Promise<string[]> {
+  return invoke('prune_worktrees', { repoId });
+}
+
+export async function removeWorktree(
+  repoId: string,
+  name: string,
+  force: boolean = false,
+): Promise<void> {
+  return invoke('remove_worktree', { repoId, name, force });
+}
+
+// Sparse checkout commands
+export async function getSparseCheckout(
+  repoId: string,
+): Promise<SparseCheckoutInfo> {
+  return invoke('get_sparse_checkout', { repoId });
+}
+
+export async function enableSparseCheckout(
+  repoId: string,
+  cone: boolean,
+  patterns: string[] = [],
+): Promise<SparseCheckoutInfo> {
+  return invoke('enable_sparse_checkout', { repoId, cone, patterns });
+}
+
+export async function disableSparseCheckout(
+  repoId: string,
+): Promise<SparseCheckoutInfo> {
+  return invoke('disable_sparse_checkout', { repoId });
+}
+
+export async function setSparsePatterns(
+  repoId: string,
+  patterns: string[],
+): Promise<SparseCheckoutInfo> {
+  return invoke('set_sparse_patterns', { repoId, patterns });
+}
+
+export async function addSparsePatterns(
+  repoId: string,
+  patterns: string[],
+): Promise<SparseCheckoutInfo> {
+  return invoke('add_sparse_patterns', { repoId, patterns });
+}
+
+export async function removeSparsePatterns(
+  repoId: string,
+  patterns: string[],
+): Promise<SparseCheckoutInfo> {
+  return invoke('remove_sparse_patterns', { repoId, patterns });
+}
+
+// LFS commands
+export async function getLfsStatus(repoId: string): Promise<LfsStatus> {
+  return invoke('get_lfs_status', { repoId });
+}
+
+export async function listLfsLocks(repoId: string): Promise<LfsLock[]> {
+  return invoke('list_lfs_locks', { repoId });
+}
+
+export async function lockLfsFile(
+  repoId: string,
+  path: string,
+): Promise<LfsLock> {
+  return invoke('lock_lfs_file', { repoId, path });
+}
+
+export async function unlockLfsFile(
+  repoId: string,
+  path: string,
+  force: boolean = false,
+): Promise<void> {
+  return invoke('unlock_lfs_file', { repoId, path, force });
 }
 
-export async function stashPop(index: number): Promise<void> {
-  return invoke('stash_pop', { index });
+// Job commands
+export async function listJobs(): Promise<JobInfo[]> {
+  return invoke('list_jobs');
 }
 
-export async function stashApply(index: number): Promise<void> {
-  return invoke('stash_apply', { index });
+export async function getJob(jobId: string): Promise<JobInfo> {
+  return invoke('get_job', { jobId });
 }
 
-export async function stashDrop(index: number): Promise<void> {
-  return invoke('stash_drop', { index });
+export async function cancelJob(jobId: string): Promise<void> {
+  return invoke('cancel_job', { jobId });
 }
 
-export async function stashList(): Promise<StashEntry[]> {
-  return invoke('stash_list');
+// Answers a `credential-requested` event; null declines
+export async function respondCredentialRequest(
+  promptId: string,
+  response: CredentialResponse | null,
+): Promise<void> {
+  return invoke('respond_credential_request', { promptId, response });
+}
+
+// Resolves with the job's result once it finishes, or rejects with its error
+export function waitForJob<T>(job: JobInfo): Promise<T> {
+  return new Promise((resolve, reject) => {
+    let unlisten: UnlistenFn | null = null;
+    const settle = (finished: JobInfo) => {
+      unlisten?.();
+      if (finished.status === 'succeeded') {
+        resolve(finished.result as T);
+      } else {
+        reject(finished.error ?? `Job ${finished.status}`);
+      }
+    };
+    listen<JobInfo>('job-finished', (event) => {
+      if (event.payload.id === job.id) settle(event.payload);
+    }).then(async (fn) => {
+      unlisten = fn;
+      // The job may have finished before the listener was registered
+      const current = await getJob(job.id);
+      if (current.status !== 'running') settle(current);
+    }, reject);
+  });
 }
 
 // Config commands
-export async function getGitConfig(): Promise<GitConfig> {
-  return invoke('get_git_config');
+export async function getGitConfig(repoId: string): Promise<GitConfig> {
+  return invoke('get_git_config', { repoId });
 }
 
 // another test
-export async function setGitConfig(key: string, value: string): Promise<void> {
-  return invoke('set_git_config', { key, value });
+export async function setGitConfig(
+  repoId: string,
+  key: string,
+  value: string,
+): Promise<void> {
+  return invoke('set_git_config', { repoId, key, value });
+}
+
+export async function listConfigEntries(
+  repoId: string,
+  level?: ConfigLevel,
+): Promise<ConfigEntryInfo[]> {
+  return invoke('list_config_entries', { repoId, level });
+}
+
+// All values of a key; the last one is the one that applies
+export async function getConfigValue(
+  repoId: string,
+  key: string,
+  level?: ConfigLevel,
+): Promise<ConfigEntryInfo[]> {
+  return invoke('get_config_value', { repoId, key, level });
+}
+
+export async function setConfigValue(
+  repoId: string,
+  level: ConfigLevel,
+  key: string,
+  value: string,
+  valueType?: ConfigValueType,
+): Promise<void> {
+  return invoke('set_config_value', { repoId, level, key, value, valueType });
+}
+
+export async function addConfigValue(
+  repoId: string,
+  level: ConfigLevel,
+  key: string,
+  value: string,
+  valueType?: ConfigValueType,
+): Promise<void> {
+  return invoke('add_config_value', { repoId, level, key, value, valueType });
+}
+
+// Removes the entries with `value`, or all entries of the key
+export async function unsetConfigValue(
+  repoId: string,
+  level: ConfigLevel,
+  key: string,
+  value?: string,
+): Promise<void> {
+  return invoke('unset_config_value', { repoId, level, key, value });
+}
+
+export async function listConfigIncludes(
+  repoId: string,
+): Promise<ConfigInclude[]> {
+  return invoke('list_config_includes', { repoId });
 }
@@ -5,6 +5,62 @@ export interface RepoInfo {
   is_bare: boolean;
   head_name: string | null;
   head_oid: string | null;
+  is_shallow: boolean;
+}
+
+export interface CloneOptions {
+  depth?: number | null;
+  branch?: string | null;
+  single_branch?: boolean;
+  bare?: boolean;
+  mirror?: boolean;
+  no_checkout?: boolean;
+  recurse_submodules?: boolean;
+}
+
+export interface OpenRepoInfo extends RepoInfo {
+  repo_id: string;
+}
+
+export interface RepoChangedEvent {
+  repo_id: string;
+}
+
+export interface RemoteRefsUpdatedEvent {
+  repo_id: string;
+  updates: RefUpdate[];
+}
+
+export interface ScannedRepo extends RepoInfo {
+  is_worktree: boolean;
+  is_dirty: boolean;
+  upstream: string | null;
+  ahead: number | null;
+  behind: number | null;
+  unpushed_branches: string[];
+}
+
+export interface CatalogEntry {
+  path: string;
+  alias: string | null;
+  favorite: boolean;
+  group: string | null;
+  last_opened: number | null;
+}
+
+export interface CatalogRepo extends CatalogEntry {
+  exists: boolean;
+}
+
+export interface RepoCatalogView {
+  repos: CatalogRepo[];
+  groups: string[];
+}
+
+export interface SessionRestore {
+  opened: OpenRepoInfo[];
+  missing: string[];
+  failed: string[];
 }
 
 export interface FileStatus {
@@ -16,6 +72,8 @@ export interface FileStatus {
   is_deleted: boolean;
   is_renamed: boolean;
   is_conflicted: boolean;
+  is_submodule: boolean;
+  is_lfs: boolean;
 }
 
 export interface RepoStatus {
@@ -40,6 +98,26 @@ export interface CommitInfo {
   tag_names: string[];
 }
 
+export interface CommitIdentity {
+  name: string;
+  email: string;
+}
+
+export interface CommitTime {
+  seconds: number;
+  offset_minutes: number;
+}
+
+export interface CommitOptions {
+  author?: CommitIdentity | null;
+  author_time?: CommitTime | null;
+  committer_time?: CommitTime | null;
+  allow_empty?: boolean;
+  // Commit even though the repository's identity profile asks for another
+  // user
+  confirm_identity?: boolean;
+}
+
 export interface FileChange {
   path: string;
   old_path: string | null;
@@ -60,6 +138,16 @@ export interface CommitDetails {
   stats: CommitStats;
 }
 
+export interface SplitState {
+  head_name: string;
+  orig_head: string;
+  split_commit: string;
+  base_commit: string;
+  replay_commits: string[];
+  message: string;
+  conflicted_commit: string | null;
+}
+
 // Graph types
 export interface GraphConnection {
   from_lane: number;
@@ -86,6 +174,31 @@ export interface CommitGraphEntry {
   graph: GraphNode;
 }
 
+// Bisect types
+export interface BisectStatus {
+  in_progress: boolean;
+  original_head: string | null;
+  current: string | null;
+  bad: string | null;
+  good: string[];
+  skipped: string[];
+  suspects: CommitInfo[];
+  estimated_steps: number;
+  first_bad_commit: CommitInfo | null;
+  only_skipped_left: boolean;
+}
+
+export interface BisectRunStep {
+  commit: string;
+  exit_code: number | null;
+  result: 'good' | 'bad' | 'skip';
+}
+
+export interface BisectRunResult {
+  steps: BisectRunStep[];
+  status: BisectStatus;
+}
+
 // Branch types
 export interface BranchInfo {
   name: string;
@@ -111,6 +224,189 @@ export interface RemoteInfo {
   name: string;
   url: string;
   push_url: string | null;
+  fetch_refspecs: string[];
+  push_refspecs: string[];
+}
+
+export interface RemoteRef {
+  name: string;
+  oid: string;
+}
+
+export interface RemoteConnectionTest {
+  url: string;
+  default_branch: string | null;
+  refs: RemoteRef[];
+}
+
+export interface FetchRemoteOptions {
+  all_remotes?: boolean;
+  // Omit to follow remote.<name>.prune / fetch.prune
+  prune?: boolean | null;
+  tags?: 'all' | 'auto' | 'none' | null;
+  refspec?: string | null;
+}
+
+export interface RefUpdate {
+  remote: string;
+  name: string;
+  kind: 'new' | 'fast_forward' | 'forced' | 'deleted';
+  old_oid: string | null;
+  new_oid: string | null;
+}
+
+export interface FetchResult {
+  updates: RefUpdate[];
+  // Remotes that failed during a fetch of all remotes
+  errors: string[];
+}
+
+export interface BackgroundFetchSettings {
+  enabled: boolean;
+  interval_minutes: number;
+}
+
+export interface RepoFetchStatus {
+  repo_id: string;
+  last_fetched_at: number | null;
+  last_error: string | null;
+  failures: number;
+  // Credentials were rejected; fetching waits for resumeBackgroundFetch
+  paused: boolean;
+  next_fetch_at: number | null;
+}
+
+export interface BackgroundFetchStatus {
+  settings: BackgroundFetchSettings;
+  // Set while the network is unreachable
+  network_retry_at: number | null;
+  repos: RepoFetchStatus[];
+}
+
+// Fetch, pull and push run the installed git executable instead of libgit2
+export interface SystemGitSettings {
+  enabled: boolean;
+  // Per-repository overrides, by repository path
+  repos: Record<string, boolean>;
+}
+
+export interface SystemGitStatus {
+  settings: SystemGitSettings;
+  // Output of `git --version`; null when git isn't installed
+  version: string | null;
+}
+
+// Identity types
+export interface IdentityProfile {
+  // Unique among the profiles
+  name: string;
+  user_name: string;
+  user_email: string;
+  signing_key: string | null;
+  signing_format: 'openpgp' | 'ssh' | 'x509' | null;
+}
+
+export interface IdentityRule {
+  kind: 'remote_url' | 'directory';
+  // A remote URL with `*` and `?` wildcards, or a directory
+  pattern: string;
+  // The name of the profile to use
+  profile: string;
+}
+
+export interface IdentitySettings {
+  profiles: IdentityProfile[];
+  // The first rule matching a repository applies
+  rules: IdentityRule[];
+}
+
+export interface RepoIdentity {
+  profile: IdentityProfile | null;
+  user_name: string | null;
+  user_email: string | null;
+  // Whether the configured user is the profile's
+  matches: boolean;
+}
+
+export interface HostKeyInfo {
+  // `host`, or `[host]:port` for other ports
+  host: string;
+  key_type: string;
+  fingerprint: string;
+  status: 'unknown' | 'changed';
+  // What known_hosts has for the host when the key changed
+  known_fingerprints: string[];
+}
+
+// A TLS certificate the server presented that isn't trusted
+export interface CertificateInfo {
+  host: string;
+  // SHA-256 of the certificate as colon-separated hex
+  fingerprint: string;
+  // Why the certificate was rejected
+  error: string;
+}
+
+export interface CredentialRequest {
+  kind: 'userpass' | 'ssh_passphrase' | 'ssh_key' | 'host_key' | 'certificate';
+  url: string;
+  username: string | null;
+  // The key a passphrase is needed for
+  key_path: string | null;
+  // Keys found in ~/.ssh to choose from
+  available_keys: string[];
+  // The previous answer was rejected
+  retry: boolean;
+  host_key: HostKeyInfo | null;
+  certificate: CertificateInfo | null;
+}
+
+export interface CredentialPromptEvent extends CredentialRequest {
+  prompt_id: string;
+  job_id: string;
+  repo_id: string | null;
+}
+
+export interface CredentialResponse {
+  username?: string | null;
+  password?: string | null;
+  key_path?: string | null;
+  passphrase?: string | null;
+  // Accept the host key and add it to known_hosts
+  trust_host_key?: boolean;
+  // Accept the certificate until the app quits
+  trust_certificate?: boolean;
+}
+
+export interface PushRemoteOptions {
+  // Defaults to the local branch's name
+  remote_branch?: string | null;
+  force_with_lease?: boolean;
+  // Omit to expect the remote-tracking branch's commit
+  expected_oid?: string | null;
+  force?: boolean;
+  // Required alongside force
+  confirm_force?: boolean;
+  tags?: boolean;
+  delete?: boolean;
+  push_options?: string[];
+}
+
+export interface PushRefStatus {
+  refname: string;
+  status:
+    | 'ok'
+    | 'rejected_non_fast_forward'
+    | 'rejected_stale'
+    | 'rejected_hook'
+    | 'rejected';
+  message: string | null;
+}
+
+export interface PushResult {
+  updates: PushRefStatus[];
+  // Text printed by the remote, e.g. hook output or pull request links
+  remote_messages: string[];
 }
 
 export interface PullResult {
@@ -143,6 +439,19 @@ export interface FileDiff {
   status: string;
   hunks: DiffHunk[];
   is_binary: boolean;
+  submodule: SubmoduleDiff | null;
+  // Set for LFS content, in which case hunks are empty
+  lfs: LfsDiff | null;
+}
+
+export interface LfsPointer {
+  oid: string;
+  size: number;
+}
+
+export interface LfsDiff {
+  old: LfsPointer | null;
+  new: LfsPointer | null;
 }
 
 export interface DiffStats {
@@ -164,8 +473,151 @@ export interface StashEntry {
   oid: string;
 }
 
+// Submodule types
+export interface SubmoduleInfo {
+  name: string;
+  path: string;
+  url: string | null;
+  branch: string | null;
+  recorded_commit: string | null;
+  checked_out_commit: string | null;
+  is_initialized: boolean;
+  is_checked_out: boolean;
+  is_dirty: boolean;
+  is_out_of_date: boolean;
+}
+
+export interface SubmoduleDiff {
+  old_commit: string | null;
+  new_commit: string | null;
+  commits_added: number;
+  commits_removed: number;
+  commit_summaries: string[];
+  summary: string;
+}
+
+// Worktree types
+export interface WorktreeInfo {
+  name: string | null;
+  path: string;
+  is_main: boolean;
+  is_current: boolean;
+  head_name: string | null;
+  head_oid: string | null;
+  is_detached: boolean;
+  is_locked: boolean;
+  lock_reason: string | null;
+  is_prunable: boolean;
+}
+
+export interface AddWorktreeOptions {
+  path: string;
+  name?: string | null;
+  branch?: string | null;
+  create_branch?: boolean;
+  start_point?: string | null;
+  detach?: boolean;
+  lock?: boolean;
+}
+
+// LFS types
+export interface LfsTrackedPattern {
+  pattern: string;
+  source: string;
+}
+
+export interface LfsStatus {
+  // null when git-lfs isn't installed
+  version: string | null;
+  patterns: LfsTrackedPattern[];
+}
+
+export interface LfsLock {
+  id: string;
+  path: string;
+  owner: string | null;
+  locked_at: string | null;
+}
+
+// Sparse checkout types
+export interface SparseCheckoutInfo {
+  enabled: boolean;
+  cone: boolean;
+  // Directories in cone mode, gitignore-style patterns otherwise
+  patterns: string[];
+  // Excluded files kept in the working tree because they have local changes
+  kept_paths: string[];
+}
+
 // Config types
 export interface GitConfig {
   user_name: string | null;
   user_email: string | null;
 }
+
+export type ConfigLevel = 'system' | 'xdg' | 'global' | 'local' | 'worktree';
+
+export type ConfigValueType = 'bool' | 'int' | 'path' | 'string';
+
+export interface ConfigEntryInfo {
+  name: string;
+  // null for a key without `=`, which counts as true
+  value: string | null;
+  level: ConfigLevel;
+  // The level's config file; null for entries from included files
+  file: string | null;
+  include_depth: number;
+}
+
+// An include.path or includeIf.<condition>.path entry
+export interface ConfigInclude {
+  level: ConfigLevel;
+  // The file the include is written in
+  file: string;
+  // Like `gitdir:~/work/`; null for include.path
+  condition: string | null;
+  path: string;
+  resolved_path: string;
+  exists: boolean;
+  // Whether the condition holds for the repository
+  active: boolean;
+  // What the included file sets, when it applies
+  entries: ConfigEntryInfo[];
+}
+
+export interface TransferProgress {
+  stage: '' | 'receiving' | 'resolving' | 'pushing' | 'bisecting';
+  current: number;
+  total: number;
+  bytes: number;
+  message: string | null;
+}
+
+export type JobStatus = 'running' | 'succeeded' | 'failed' | 'cancelled';
+
+export interface JobInfo {
+  id: string;
+  kind:
+    | 'clone'
+    | 'fetch'
+    | 'pull'
+    | 'push'
+    | 'deepen'
+    | 'update_submodules'
+    | 'bisect_run'
+    | 'test_connection';
+  repo_id: string | null;
+  description: string;
+  status: JobStatus;
+  progress: TransferProgress;
+  error: string | null;
+  result: unknown;
+  started_at: number;
+  finished_at: number | null;
+}
+
+export interface JobProgressEvent {
+  job_id: string;
+  repo_id: string | null;
+  progress: TransferProgress;
+}
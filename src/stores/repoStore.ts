@@ -2,10 +2,18 @@ import { create } from 'zustand';
 import * as tauri from '@/lib/tauri';
 import type {
   BranchInfo,
+  CloneOptions,
   CommitGraphEntry,
   CommitInfo,
+  CommitOptions,
+  FetchRemoteOptions,
+  FetchResult,
+  OpenRepoInfo,
+  PullResult,
+  PushRemoteOptions,
+  PushResult,
   RemoteInfo,
-  RepoInfo,
+  RepoIdentity,
   RepoStatus,
   StashEntry,
 } from '@/lib/types';
@@ -13,7 +21,8 @@ import { runAction, runWithLoading } from './storeHelpers';
 
 interface RepoState {
   // Repository state
-  repoInfo: RepoInfo | null;
+  repoInfo: OpenRepoInfo | null;
+  openRepos: OpenRepoInfo[];
   status: RepoStatus | null;
   isLoading: boolean;
   error: string | null;
@@ -31,21 +40,32 @@ interface RepoState {
 
   // Remotes
   remotes: RemoteInfo[];
+  lastPushResult: PushResult | null;
 
   // Stashes
   stashes: StashEntry[];
 
+  // The identity commits are made with, against the matching profile
+  identity: RepoIdentity | null;
+
   // Actions
   openRepository: (path: string) => Promise<void>;
   initRepository: (path: string) => Promise<void>;
-  cloneRepository: (url: string, path: string) => Promise<void>;
+  cloneRepository: (
+    url: string,
+    path: string,
+    options?: CloneOptions,
+  ) => Promise<void>;
   closeRepository: () => void;
+  switchRepository: (repoId: string) => Promise<void>;
+  restoreSession: () => Promise<void>;
   refreshStatus: () => Promise<void>;
   refreshCommits: (limit?: number) => Promise<void>;
   loadMoreCommits: () => Promise<void>;
   refreshBranches: () => Promise<void>;
   refreshRemotes: () => Promise<void>;
   refreshStashes: () => Promise<void>;
+  refreshIdentity: () => Promise<void>;
   refreshAll: () => Promise<void>;
 
   // Staging actions
@@ -57,7 +77,7 @@ interface RepoState {
   discardAll: () => Promise<void>;
 
   // Commit actions
-  createCommit: (message: string) => Promise<void>;
+  createCommit: (message: string, options?: CommitOptions) => Promise<void>;
   selectCommit: (commit: CommitInfo | null) => void;
   resetToCommit: (commitId: string, mode: 'soft' | 'hard') => Promise<void>;
   squashCommits: (commitIds: string[], message: string) => Promise<void>;
@@ -70,9 +90,13 @@ interface RepoState {
   viewBranchCommits: (name: string | null) => Promise<void>;
 
   // Remote actions
-  fetch: (remote: string) => Promise<void>;
+  fetch: (remote: string, options?: FetchRemoteOptions) => Promise<void>;
   pull: (remote: string, branch: string) => Promise<void>;
-  push: (remote: string, branch: string) => Promise<void>;
+  push: (
+    remote: string,
+    branch: string,
+    options?: PushRemoteOptions,
+  ) => Promise<void>;
 
   // Stash actions
   stashSave: (message?: string) => Promise<void>;
@@ -84,8 +108,32 @@ interface RepoState {
   clearError: () => void;
 }
 
+// Reopening a repository keeps its existing tab
+function upsertRepo(repos: OpenRepoInfo[], repoInfo: OpenRepoInfo) {
+  return repos.some((r) => r.repo_id === repoInfo.repo_id)
+    ? repos.map((r) => (r.repo_id === repoInfo.repo_id ? repoInfo : r))
+    : [...repos, repoInfo];
+}
+
+// Per-repository view state, cleared when switching tabs
+const emptyRepoView = {
+  status: null,
+  commits: [],
+  selectedCommit: null,
+  hasMoreCommits: true,
+  isLoadingMoreCommits: false,
+  branches: [],
+  currentBranch: null,
+  viewingBranch: null,
+  remotes: [],
+  lastPushResult: null,
+  stashes: [],
+  identity: null,
+};
+
 export const useRepoStore = create<RepoState>((set, get) => ({
   repoInfo: null,
+  openRepos: [],
   status: null,
   isLoading: false,
   error: null,
@@ -97,52 +145,78 @@ export const useRepoStore = create<RepoState>((set, get) => ({
   currentBranch: null,
   viewingBranch: null,
   remotes: [],
+  lastPushResult: null,
   stashes: [],
+  identity: null,
 
   openRepository: async (path: string) => {
     await runWithLoading(set, async () => {
       const repoInfo = await tauri.openRepository(path);
-      set({ repoInfo, currentBranch: repoInfo.head_name });
-      await get().refreshAll();
+      set({ openRepos: upsertRepo(get().openRepos, repoInfo) });
+      await get().switchRepository(repoInfo.repo_id);
     });
   },
 
   initRepository: async (path: string) => {
     await runWithLoading(set, async () => {
       const repoInfo = await tauri.initRepository(path);
-      set({ repoInfo, currentBranch: repoInfo.head_name });
-      await get().refreshAll();
+      set({ openRepos: upsertRepo(get().openRepos, repoInfo) });
+      await get().switchRepository(repoInfo.repo_id);
     });
   },
 
-  cloneRepository: async (url: string, path: string) => {
+  cloneRepository: async (
+    url: string,
+    path: string,
+    options?: CloneOptions,
+  ) => {
     await runWithLoading(set, async () => {
-      const repoInfo = await tauri.cloneRepository(url, path);
-      set({ repoInfo, currentBranch: repoInfo.head_name });
-      await get().refreshAll();
+      const job = await tauri.cloneRepository(url, path, options);
+      const repoInfo = await tauri.waitForJob<OpenRepoInfo>(job);
+      set({ openRepos: upsertRepo(get().openRepos, repoInfo) });
+      await get().switchRepository(repoInfo.repo_id);
     });
   },
 
   closeRepository: () => {
-    tauri.closeRepository();
-    set({
-      repoInfo: null,
-      status: null,
-      commits: [],
-      selectedCommit: null,
-      hasMoreCommits: true,
-      isLoadingMoreCommits: false,
-      branches: [],
-      currentBranch: null,
-      viewingBranch: null,
-      remotes: [],
-      stashes: [],
+    const { repoInfo, openRepos } = get();
+    if (!repoInfo) return;
+    tauri.closeRepository(repoInfo.repo_id);
+    const remaining = openRepos.filter((r) => r.repo_id !== repoInfo.repo_id);
+    set({ ...emptyRepoView, repoInfo: null, openRepos: remaining });
+    if (remaining.length > 0) {
+      get().switchRepository(remaining[remaining.length - 1].repo_id);
+    }
+  },
+
+  switchRepository: async (repoId: string) => {
+    const repoInfo = get().openRepos.find((r) => r.repo_id === repoId);
+    if (!repoInfo) return;
+    set({ ...emptyRepoView, repoInfo, currentBranch: repoInfo.head_name });
+    await get().refreshAll();
+  },
+
+  restoreSession: async () => {
+    await runWithLoading(set, async () => {
+      const { opened, missing, failed } = await tauri.restoreSession();
+      const errors = [
+        ...(missing.length > 0
+          ? [`Repository moved or deleted: ${missing.join(', ')}`]
+          : []),
+        ...failed.map((failure) => `Failed to reopen ${failure}`),
+      ];
+      if (errors.length > 0) {
+        set({ error: errors.join('\n') });
+      }
+      if (opened.length === 0) return;
+      set({ openRepos: opened });
+      await get().switchRepository(opened[opened.length - 1].repo_id);
     });
   },
 
   refreshStatus: async () => {
     await runAction(set, async () => {
-      const status = await tauri.getStatus();
+      const status = await tauri.getStatus(activeRepoId());
       set({ status });
     });
   },
@@ -151,6 +225,7 @@ export const useRepoStore = create<RepoState>((set, get) => ({
     try {
       const { viewingBranch } = get();
       const commits = await tauri.getCommitHistoryWithGraph(
+        activeRepoId(),
         limit,
         0,
         viewingBranch,
@@ -172,6 +247,7 @@ export const useRepoStore = create<RepoState>((set, get) => ({
     set({ isLoadingMoreCommits: true });
     try {
       const result = await tauri.getCommitHistoryWithGraph(
+        activeRepoId(),
         newLimit,
         0,
         viewingBranch,
@@ -188,7 +264,7 @@ export const useRepoStore = create<RepoState>((set, get) => ({
 
   refreshBranches: async () => {
     await runAction(set, async () => {
-      const branches = await tauri.listBranches();
+      const branches = await tauri.listBranches(activeRepoId());
       const currentBranch = branches.find((b) => b.is_head)?.name || null;
       set({ branches, currentBranch });
     });
@@ -196,7 +272,7 @@ export const useRepoStore = create<RepoState>((set, get) => ({
 
   refreshRemotes: async () => {
     try {
-      const remotes = await tauri.listRemotes();
+      const remotes = await tauri.listRemotes(activeRepoId());
       set({ remotes });
     } catch (_e) {
       set({ remotes: [] });
@@ -205,13 +281,22 @@ export const useRepoStore = create<RepoState>((set, get) => ({
 
   refreshStashes: async () => {
     try {
-      const stashes = await tauri.stashList();
+      const stashes = await tauri.stashList(activeRepoId());
       set({ stashes });
     } catch (_e) {
       set({ stashes: [] });
     }
   },
 
+  refreshIdentity: async () => {
+    try {
+      const identity = await tauri.getRepoIdentity(activeRepoId());
+      set({ identity });
+    } catch (_e) {
+      set({ identity: null });
+    }
+  },
+
   refreshAll: async () => {
     await Promise.all([
       get().refreshStatus(),
@@ -219,60 +304,63 @@ export const useRepoStore = create<RepoState>((set, get) => ({
       get().refreshBranches(),
       get().refreshRemotes(),
       get().refreshStashes(),
+      get().refreshIdentity(),
     ]);
   },
 
   stageFile: async (path: string) => {
     await runAction(set, async () => {
-      await tauri.stageFile(path);
+      await tauri.stageFile(activeRepoId(), path);
       await get().refreshStatus();
     });
   },
 
   unstageFile: async (path: string) => {
     await runAction(set, async () => {
-      await tauri.unstageFile(path);
+      await tauri.unstageFile(activeRepoId(), path);
       await get().refreshStatus();
     });
   },
 
   stageAll: async () => {
     await runAction(set, async () => {
-      await tauri.stageAll();
+      await tauri.stageAll(activeRepoId());
       await get().refreshStatus();
     });
   },
 
   unstageAll: async () => {
     await runAction(set, async () => {
-      await tauri.unstageAll();
+      await tauri.unstageAll(activeRepoId());
       await get().refreshStatus();
     });
   },
 
   discardChanges: async (path: string) => {
     await runAction(set, async () => {
-      await tauri.discardChanges(path);
+      await tauri.discardChanges(activeRepoId(), path);
       await get().refreshStatus();
     });
   },
 
   discardAll: async () => {
     await runAction(set, async () => {
-      await tauri.discardAllChanges();
+      await tauri.discardAllChanges(activeRepoId());
       await get().refreshStatus();
     });
   },
 
-  createCommit: async (message: string) => {
+  createCommit: async (message: string, options?: CommitOptions) => {
     await runAction(set, async () => {
-      await tauri.createCommit(message);
+      await tauri.createCommit(activeRepoId(), message, options);
       await Promise.all([
         get().refreshStatus(),
         get().refreshCommits(),
         get().refreshBranches(),
       ]);
     });
+    // The config may have changed since the identity was last read
+    await get().refreshIdentity();
   },
 
   selectCommit: (commit: CommitInfo | null) => {
@@ -281,28 +369,28 @@ export const useRepoStore = create<RepoState>((set, get) => ({
 
   resetToCommit: async (commitId: string, mode: 'soft' | 'hard') => {
     await runAction(set, async () => {
-      await tauri.resetToCommit(commitId, mode);
+      await tauri.resetToCommit(activeRepoId(), commitId, mode);
       await get().refreshAll();
     });
   },
 
   squashCommits: async (commitIds: string[], message: string) => {
     await runAction(set, async () => {
-      await tauri.squashCommits(commitIds, message);
+      await tauri.squashCommits(activeRepoId(), commitIds, message);
       await get().refreshAll();
     });
   },
 
   createBranch: async (name: string, sourceBranch?: string) => {
     await runAction(set, async () => {
-      await tauri.createBranch(name, sourceBranch);
+      await tauri.createBranch(activeRepoId(), name, sourceBranch);
       await get().refreshBranches();
     });
   },
 
   checkoutBranch: async (name: string) => {
     await runAction(set, async () => {
-      await tauri.checkoutBranch(name);
+      await tauri.checkoutBranch(activeRepoId(), name);
       set({ viewingBranch: null });
       await get().refreshAll();
     });
@@ -310,14 +398,14 @@ export const useRepoStore = create<RepoState>((set, get) => ({
 
   deleteBranch: async (name: string) => {
     await runAction(set, async () => {
-      await tauri.deleteBranch(name);
+      await tauri.deleteBranch(activeRepoId(), name);
       await get().refreshBranches();
     });
   },
 
   mergeBranch: async (name: string) => {
     try {
-      const result = await tauri.mergeBranch(name);
+      const result = await tauri.mergeBranch(activeRepoId(), name);
       if (!result.success) {
         set({ error: result.message });
       }
@@ -330,23 +418,33 @@ export const useRepoStore = create<RepoState>((set, get) => ({
   viewBranchCommits: async (name: string | null) => {
     set({ viewingBranch: name });
     try {
-      const commits = await tauri.getCommitHistoryWithGraph(50, 0, name);
+      const commits = await tauri.getCommitHistoryWithGraph(
+        activeRepoId(),
+        50,
+        0,
+        name,
+      );
       set({ commits, hasMoreCommits: commits.length >= 50 });
     } catch (_e) {
       set({ commits: [], hasMoreCommits: false });
     }
   },
 
-  fetch: async (remote: string) => {
+  fetch: async (remote: string, options?: FetchRemoteOptions) => {
     await runWithLoading(set, async () => {
-      await tauri.fetchRemote(remote);
+      const job = await tauri.fetchRemote(activeRepoId(), remote, options);
+      const result = await tauri.waitForJob<FetchResult>(job);
+      if (result.errors.length > 0) {
+        set({ error: result.errors.join('\n') });
+      }
       await get().refreshBranches();
     }, false);
   },
 
   pull: async (remote: string, branch: string) => {
     await runWithLoading(set, async () => {
-      const result = await tauri.pullRemote(remote, branch);
+      const job = await tauri.pullRemote(activeRepoId(), remote, branch);
+      const result = await tauri.waitForJob<PullResult>(job);
       if (!result.success) {
         set({ error: result.message });
       }
@@ -354,40 +452,62 @@ export const useRepoStore = create<RepoState>((set, get) => ({
     }, false);
   },
 
-  push: async (remote: string, branch: string) => {
+  push: async (remote: string, branch: string, options?: PushRemoteOptions) => {
     await runWithLoading(set, async () => {
-      await tauri.pushRemote(remote, branch);
+      const job = await tauri.pushRemote(
+        activeRepoId(),
+        remote,
+        branch,
+        options,
+      );
+      const result = await tauri.waitForJob<PushResult>(job);
+      set({ lastPushResult: result });
+      const rejected = result.updates.filter((u) => u.status !== 'ok');
+      if (rejected.length > 0) {
+        set({
+          error: rejected
+            .map((u) => `Failed to push ${u.refname}: ${u.message}`)
+            .join('\n'),
+        });
+      }
       await get().refreshBranches();
     }, false);
   },
 
   stashSave: async (message?: string) => {
     await runAction(set, async () => {
-      await tauri.stashSave(message);
+      await tauri.stashSave(activeRepoId(), message);
       await Promise.all([get().refreshStatus(), get().refreshStashes()]);
     });
   },
 
   stashPop: async (index: number) => {
     await runAction(set, async () => {
-      await tauri.stashPop(index);
+      await tauri.stashPop(activeRepoId(), index);
       await Promise.all([get().refreshStatus(), get().refreshStashes()]);
     });
   },
 
   stashApply: async (index: number) => {
     await runAction(set, async () => {
-      await tauri.stashApply(index);
+      await tauri.stashApply(activeRepoId(), index);
       await Promise.all([get().refreshStatus(), get().refreshStashes()]);
     });
   },
 
   stashDrop: async (index: number) => {
     await runAction(set, async () => {
-      await tauri.stashDrop(index);
+      await tauri.stashDrop(activeRepoId(), index);
       await get().refreshStashes();
     });
   },
 
   clearError: () => set({ error: null }),
 }));
+
+// ID of the repository shown in the active tab
+export function activeRepoId(): string {
+  const repoId = useRepoStore.getState().repoInfo?.repo_id;
+  if (!repoId) throw new Error('No repository open');
+  return repoId;
+}
@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import * as tauri from '@/lib/tauri';
 import type { CommitDiff, FileDiff, FileStatus } from '@/lib/types';
+import { activeRepoId } from './repoStore';
 
 export type ViewMode = 'changes' | 'history';
 export type SidebarTab = 'changes' | 'branches';
@@ -96,7 +97,7 @@ export const useUiStore = create<UiState>((set, get) => ({
   loadFileDiff: async (path: string, staged: boolean) => {
     set({ isDiffLoading: true });
     try {
-      const diff = await tauri.getFileDiff(path, staged);
+      const diff = await tauri.getFileDiff(activeRepoId(), path, staged);
       set({ currentFileDiff: diff, currentCommitDiff: null });
     } catch (e) {
       console.error('Failed to load diff:', e);
@@ -109,7 +110,7 @@ export const useUiStore = create<UiState>((set, get) => ({
   loadCommitDiff: async (oid: string) => {
     set({ isDiffLoading: true, detailView: 'commit' });
     try {
-      const diff = await tauri.getCommitDiff(oid);
+      const diff = await tauri.getCommitDiff(activeRepoId(), oid);
       set({ currentCommitDiff: diff, currentFileDiff: null });
     } catch (e) {
       console.error('Failed to load commit diff:', e);